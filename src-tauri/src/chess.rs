@@ -51,7 +51,9 @@ pub struct EngineProcess {
 }
 
 impl EngineProcess {
-    async fn new(path: PathBuf) -> Result<(Self, Lines<BufReader<ChildStdout>>), Error> {
+    pub(crate) async fn new(
+        path: PathBuf,
+    ) -> Result<(Self, Lines<BufReader<ChildStdout>>), Error> {
         let mut command = Command::new(&path);
         command.current_dir(path.parent().unwrap());
         command
@@ -123,7 +125,7 @@ impl EngineProcess {
         Ok(())
     }
 
-    async fn set_options(&mut self, options: EngineOptions) -> Result<(), Error> {
+    pub(crate) async fn set_options(&mut self, options: EngineOptions) -> Result<(), Error> {
         let fen: Fen = options.fen.parse()?;
         let mut pos: Chess = match fen.into_position(CastlingMode::Chess960) {
             Ok(p) => p,
@@ -173,7 +175,7 @@ impl EngineProcess {
         Ok(())
     }
 
-    async fn go(&mut self, mode: &GoMode) -> Result<(), Error> {
+    pub(crate) async fn go(&mut self, mode: &GoMode) -> Result<(), Error> {
         self.go_mode = mode.clone();
         let msg = match mode {
             GoMode::Depth(depth) => format!("go depth {}\n", depth),
@@ -199,14 +201,14 @@ impl EngineProcess {
         Ok(())
     }
 
-    async fn stop(&mut self) -> Result<(), Error> {
+    pub(crate) async fn stop(&mut self) -> Result<(), Error> {
         self.stdin.write_all(b"stop\n").await?;
         self.logs.push(EngineLog::Gui("stop\n".to_string()));
         self.running = false;
         Ok(())
     }
 
-    async fn kill(&mut self) -> Result<(), Error> {
+    pub(crate) async fn kill(&mut self) -> Result<(), Error> {
         self.stdin.write_all(b"quit\n").await?;
         self.logs.push(EngineLog::Gui("quit\n".to_string()));
         self.running = false;
@@ -229,14 +231,14 @@ pub struct AnalysisCacheKey {
 #[derivative(Default)]
 pub struct BestMoves {
     nodes: u32,
-    depth: u32,
-    score: Score,
+    pub(crate) depth: u32,
+    pub(crate) score: Score,
     #[serde(rename = "uciMoves")]
-    uci_moves: Vec<String>,
+    pub(crate) uci_moves: Vec<String>,
     #[serde(rename = "sanMoves")]
-    san_moves: Vec<String>,
+    pub(crate) san_moves: Vec<String>,
     #[derivative(Default(value = "1"))]
-    multipv: u16,
+    pub(crate) multipv: u16,
     nps: u32,
 }
 
@@ -264,7 +266,7 @@ fn invert_score(score: Score) -> Score {
     }
 }
 
-fn parse_uci_attrs(
+pub(crate) fn parse_uci_attrs(
     attrs: Vec<UciInfoAttribute>,
     fen: &Fen,
     moves: &Vec<String>,
@@ -364,8 +366,8 @@ pub struct EngineOptions {
 
 #[derive(Deserialize, Debug, Clone, Type, PartialEq, Eq)]
 pub struct EngineOption {
-    name: String,
-    value: String,
+    pub(crate) name: String,
+    pub(crate) value: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Type, PartialEq, Eq)]
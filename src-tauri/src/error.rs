@@ -24,6 +24,9 @@ pub enum Error {
     #[error(transparent)]
     ChessPosition(#[from] shakmaty::PositionError<Chess>),
 
+    #[error(transparent)]
+    VariantPosition(#[from] shakmaty::PositionError<shakmaty::variant::VariantPosition>),
+
     #[error(transparent)]
     IllegalUciMove(#[from] shakmaty::uci::IllegalUciMoveError),
 
@@ -48,6 +51,21 @@ pub enum Error {
     #[error(transparent)]
     SystemTime(#[from] std::time::SystemTimeError),
 
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    #[error(transparent)]
+    Chrono(#[from] chrono::ParseError),
+
+    #[error(transparent)]
+    Keyring(#[from] keyring::Error),
+
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+
     #[error("No stdin")]
     NoStdin,
 
@@ -77,6 +95,51 @@ pub enum Error {
 
     #[error("Players aren't the same. They have played against each other")]
     NotDistinctPlayers,
+
+    #[error("TLCS profile not found: {0}")]
+    TlcsProfileNotFound(String),
+
+    #[error("TLCS profile already exists: {0}")]
+    TlcsProfileExists(String),
+
+    #[error("No active TLCS recording")]
+    TlcsNotRecording,
+
+    #[error("Unrecognized move: {0}")]
+    TlcsUnrecognizedMove(String),
+
+    #[error("Invalid TLCS mock script: {0}")]
+    TlcsInvalidScript(String),
+
+    #[error("Not subscribed to TLCS game {0}")]
+    TlcsGameNotSubscribed(String),
+
+    #[error("Illegal move {0} in position {1}")]
+    TlcsIllegalMove(String, String),
+
+    #[error("Invalid TLCS chat channel: {0:?}")]
+    TlcsInvalidChannel(String),
+
+    #[error("Not connected to a TLCS server")]
+    TlcsNotConnected,
+
+    #[error("Arbiter commands are not enabled for this TLCS connection")]
+    TlcsArbiterDisabled,
+
+    #[error("Invalid game result: {0}")]
+    TlcsInvalidResult(String),
+
+    #[error("Invalid pairing file: {0}")]
+    TlcsInvalidPairings(String),
+
+    #[error("Invalid time control {0}+{1}")]
+    TlcsInvalidTimeControl(u32, u32),
+
+    #[error("Invalid TLCS challenge id: {0:?}")]
+    TlcsInvalidChallenge(String),
+
+    #[error("Invalid headless config: {0}")]
+    TlcsInvalidHeadlessConfig(String),
 }
 
 impl serde::Serialize for Error {
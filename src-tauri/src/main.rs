@@ -13,9 +13,13 @@ mod opening;
 mod pgn;
 mod puzzle;
 mod tlcs;
+mod tlcs_auto_subscribe;
 mod tlcs_client;
+mod tlcs_engine_seat;
+mod tlcs_profiles;
 
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::{fs::create_dir_all, path::Path};
 
@@ -47,7 +51,16 @@ use crate::oauth::authenticate;
 use crate::pgn::{count_pgn_games, delete_game, read_games, write_game};
 use crate::puzzle::{get_puzzle, get_puzzle_db_info};
 use crate::tlcs::{
-    start_tlcs_stream, stop_tlcs_stream, tlcs_analysis_options, tlcs_status, TlcsHandle,
+    attach_tlcs_recorder, compute_tlcs_standings, discard_interrupted_tlcs_session,
+    export_tlcs_trf, follow_tlcs_in_board, force_set_position, load_tlcs_pairings,
+    pause_tlcs_recording, query_tlcs_log, replay_tlcs_log, resume_interrupted_tlcs_session,
+    resume_tlcs_recording, resume_tlcs_stream, set_tlcs_log_level, set_tlcs_notifications,
+    shutdown_tlcs_sessions, start_tlcs_http_server, start_tlcs_kibitzer, start_tlcs_mock_server,
+    start_tlcs_stream, stop_tlcs_http_server, stop_tlcs_kibitzer, stop_tlcs_mock_server,
+    stop_tlcs_stream, tlcs_abort_game, tlcs_adjust_clock, tlcs_analysis_options, tlcs_diagnostics,
+    tlcs_eval_history, tlcs_interrupted_sessions, tlcs_pgn_delta, tlcs_set_result,
+    tlcs_simul_overview, tlcs_status, tlcs_tournament_status, tlcs_upload_status,
+    unfollow_tlcs_in_board, TlcsHandle, TlcsHttpServer, TlcsMockServer, TlcsNotifier,
 };
 use crate::{
     chess::get_best_moves,
@@ -57,12 +70,22 @@ use crate::{
     fs::{download_file, file_exists, get_file_metadata},
     opening::{get_opening_from_fen, get_opening_from_name, search_opening_name},
     tlcs::{
-        connect_tlcs, disconnect_tlcs, reconnect_tlcs, send_tlcs_action, SharedTlcs, TlcsManager,
+        cancel_pending_action, connect_tlcs, disconnect_tlcs, reconnect_tlcs, send_tlcs_action,
+        tlcs_connection_metrics, SharedTlcs, TlcsManager,
     },
     tlcs_client::{
-        connect as tlcs_connect, disconnect as tlcs_disconnect, keep_alive as tlcs_keep_alive,
-        send_move as tlcs_send_move, subscribe_game as tlcs_subscribe_game, TlcsErrorEvent,
-        TlcsMessageEvent, TlcsStatusEvent,
+        cancel_tlcs_premove, connect as tlcs_connect, disconnect as tlcs_disconnect,
+        keep_alive as tlcs_keep_alive, list_tlcs_subscriptions, play_tlcs_move, queue_tlcs_premove,
+        request_tlcs_game_list, send_move as tlcs_send_move, send_tlcs_chat,
+        set_tlcs_auto_subscribe, set_tlcs_input_encoding, set_tlcs_outbound_queue,
+        set_tlcs_rate_limit, start_tlcs_engine_seat, stop_tlcs_engine_seat,
+        subscribe_game as tlcs_subscribe_game, tlcs_accept_challenge, tlcs_post_seek,
+        unsubscribe_tlcs_game, TlcsChatEvent, TlcsErrorEvent, TlcsGameListEvent, TlcsLatencyEvent,
+        TlcsMessageEvent, TlcsOfferEvent, TlcsOutboundExpiredEvent, TlcsPremoveEvent,
+        TlcsPresenceEvent, TlcsServerMessageEvent, TlcsStatusEvent,
+    },
+    tlcs_profiles::{
+        delete_tlcs_profile, list_tlcs_profiles, save_tlcs_profile, update_tlcs_profile,
     },
 };
 use tokio::sync::{RwLock, Semaphore};
@@ -97,10 +120,20 @@ pub struct AppState {
     auth: AuthState,
     #[derivative(Default(value = "Arc::new(RwLock::new(None))"))]
     tlcs_handle: Arc<RwLock<Option<TlcsHandle>>>,
+    /// Sessions of a `--headless` config after the first, which the commands
+    /// do not reach.
+    tlcs_headless_handles: RwLock<Vec<TlcsHandle>>,
+    #[derivative(Default(value = "Arc::new(RwLock::new(None))"))]
+    tlcs_http_server: Arc<RwLock<Option<TlcsHttpServer>>>,
+    #[derivative(Default(value = "Arc::new(RwLock::new(None))"))]
+    tlcs_mock_server: Arc<RwLock<Option<TlcsMockServer>>>,
     #[derivative(Default(value = "Arc::new(TlcsManager::default())"))]
     tlcs: SharedTlcs,
+    tlcs_notifier: TlcsNotifier,
     #[derivative(Default(value = "Arc::new(RwLock::new(tlcs_client::TlcsManager::default()))"))]
     tlcs_client: Arc<RwLock<tlcs_client::TlcsManager>>,
+    /// Set by `--headless`, which runs without any window.
+    headless: AtomicBool,
 }
 
 const REQUIRED_DIRS: &[(BaseDirectory, &str)] = &[
@@ -173,16 +206,69 @@ fn main() {
             get_players,
             get_puzzle_db_info,
             start_tlcs_stream,
+            resume_tlcs_stream,
+            attach_tlcs_recorder,
             stop_tlcs_stream,
             tlcs_status,
+            tlcs_upload_status,
+            tlcs_diagnostics,
+            tlcs_eval_history,
+            tlcs_pgn_delta,
+            tlcs_simul_overview,
+            tlcs_interrupted_sessions,
+            resume_interrupted_tlcs_session,
+            discard_interrupted_tlcs_session,
+            follow_tlcs_in_board,
+            unfollow_tlcs_in_board,
+            set_tlcs_notifications,
+            set_tlcs_log_level,
+            load_tlcs_pairings,
+            export_tlcs_trf,
             tlcs_analysis_options,
+            tlcs_tournament_status,
+            compute_tlcs_standings,
+            replay_tlcs_log,
+            start_tlcs_http_server,
+            stop_tlcs_http_server,
+            start_tlcs_mock_server,
+            stop_tlcs_mock_server,
+            query_tlcs_log,
+            force_set_position,
+            pause_tlcs_recording,
+            resume_tlcs_recording,
+            start_tlcs_kibitzer,
+            stop_tlcs_kibitzer,
             connect_tlcs,
             disconnect_tlcs,
             send_tlcs_action,
+            cancel_pending_action,
+            tlcs_set_result,
+            tlcs_adjust_clock,
+            tlcs_abort_game,
             reconnect_tlcs,
+            tlcs_connection_metrics,
+            list_tlcs_profiles,
+            save_tlcs_profile,
+            update_tlcs_profile,
+            delete_tlcs_profile,
             tlcs_connect,
             tlcs_subscribe_game,
+            unsubscribe_tlcs_game,
+            list_tlcs_subscriptions,
             tlcs_send_move,
+            play_tlcs_move,
+            queue_tlcs_premove,
+            cancel_tlcs_premove,
+            send_tlcs_chat,
+            tlcs_post_seek,
+            tlcs_accept_challenge,
+            request_tlcs_game_list,
+            set_tlcs_auto_subscribe,
+            set_tlcs_input_encoding,
+            set_tlcs_outbound_queue,
+            set_tlcs_rate_limit,
+            start_tlcs_engine_seat,
+            stop_tlcs_engine_seat,
             tlcs_keep_alive,
             tlcs_disconnect
         ))
@@ -193,9 +279,40 @@ fn main() {
             ReportProgress,
             tlcs::TlcsConnectionEvent,
             tlcs::TlcsGameEvent,
+            tlcs::TlcsClockEvent,
+            tlcs::TlcsFlagEvent,
+            tlcs::TlcsEvalEvent,
+            tlcs::TlcsBlunderEvent,
+            tlcs::TlcsKibitzEvent,
+            tlcs::TlcsBroadcastEvent,
+            tlcs::TlcsDesyncEvent,
+            tlcs::TlcsResyncEvent,
+            tlcs::TlcsBackfillEvent,
+            tlcs::TlcsPgnAppendedEvent,
+            tlcs::TlcsTabMoveEvent,
+            tlcs::TlcsOpeningEvent,
+            tlcs::TlcsDrawClaimEvent,
+            tlcs::TlcsMoveRecordedEvent,
+            tlcs::TlcsSoundEvent,
+            tlcs::TlcsEnginePvEvent,
+            tlcs::TlcsNoveltyEvent,
+            tlcs::TlcsBookEvent,
+            tlcs::TlcsMetricsEvent,
+            tlcs::TlcsRateLimitedEvent,
+            tlcs::TlcsPendingActionEvent,
+            tlcs::TlcsWebhookEvent,
             TlcsStatusEvent,
             TlcsMessageEvent,
-            TlcsErrorEvent
+            TlcsErrorEvent,
+            TlcsLatencyEvent,
+            TlcsPremoveEvent,
+            TlcsChatEvent,
+            TlcsOfferEvent,
+            TlcsGameListEvent,
+            TlcsPresenceEvent,
+            TlcsOutboundExpiredEvent,
+            TlcsServerMessageEvent,
+            tlcs_auto_subscribe::TlcsAutoSubscribeEvent
         ));
 
     #[cfg(debug_assertions)]
@@ -232,6 +349,7 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(move |app| {
             log::info!("Setting up application");
 
@@ -263,6 +381,21 @@ fn main() {
             #[cfg(desktop)]
             app.handle().plugin(tauri_plugin_cli::init())?;
 
+            #[cfg(desktop)]
+            let headless = tlcs::headless_config_path(app.handle());
+            #[cfg(not(desktop))]
+            let headless: Option<PathBuf> = None;
+            match headless {
+                Some(config) => tlcs::start_headless(app.handle(), &config)?,
+                // Created here instead of at startup, so headless runs never
+                // open it.
+                None => {
+                    if let Some(config) = app.config().app.windows.first() {
+                        tauri::WebviewWindowBuilder::from_config(app.handle(), config)?.build()?;
+                    }
+                }
+            }
+
             #[cfg(desktop)]
             app.handle()
                 .plugin(tauri_plugin_updater::Builder::new().build())?;
@@ -272,8 +405,21 @@ fn main() {
             Ok(())
         })
         .manage(AppState::default())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Headless runs have no window to keep them open.
+            tauri::RunEvent::ExitRequested {
+                code: None, api, ..
+            } if app.state::<AppState>().headless.load(Ordering::Relaxed) => {
+                api.prevent_exit();
+            }
+            tauri::RunEvent::Exit => {
+                let state = app.state::<AppState>();
+                tauri::async_runtime::block_on(shutdown_tlcs_sessions(&state));
+            }
+            _ => {}
+        });
 }
 
 #[tauri::command]
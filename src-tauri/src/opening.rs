@@ -70,6 +70,15 @@ pub fn get_opening_from_setup(setup: Setup) -> Result<String, Error> {
         .ok_or_else(|| Error::NoOpeningFound)
 }
 
+/// Looks up the ECO code and name of a position reached in a standard game.
+/// The starting position and Chess960 setups are not classified.
+pub fn get_eco_from_setup(setup: &Setup) -> Option<(String, String)> {
+    OPENINGS
+        .iter()
+        .find(|o| o.pgn.is_some() && o.setup == *setup)
+        .map(|o| (o.eco.clone(), o.name.clone()))
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn search_opening_name(query: String) -> Result<Vec<OutOpening>, Error> {
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::zobrist::{Zobrist64, ZobristHash};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position};
+use specta::Type;
+use tauri_specta::Event;
+use tokio::sync::mpsc;
+
+use super::{RotatingLog, TlcsEmitter};
+
+/// Size of a Polyglot entry: key, move, weight and learn data.
+const ENTRY_SIZE: u64 = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBookMove {
+    pub san: String,
+    pub uci: String,
+    /// Sum of the weights the books give the move.
+    pub weight: u32,
+}
+
+/// Emitted for every live position found in the opening books, and once with
+/// no moves when a game leaves them.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBookEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub fen: String,
+    /// Book moves, most weighted first.
+    pub moves: Vec<TlcsBookMove>,
+}
+
+struct BookRequest {
+    board: Option<u32>,
+    ply: usize,
+    fen: String,
+}
+
+fn polyglot_key(position: &Chess) -> u64 {
+    position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
+}
+
+/// The move of a Polyglot entry in UCI, castling as king takes rook.
+fn decode_move(raw: u16) -> String {
+    let square = |index: u16| {
+        let file = (b'a' + (index & 7) as u8) as char;
+        let rank = (b'1' + ((index >> 3) & 7) as u8) as char;
+        format!("{file}{rank}")
+    };
+    let mut uci = format!("{}{}", square(raw >> 6), square(raw));
+    let promotion = (raw >> 12) & 7;
+    if promotion > 0 {
+        uci.extend(["n", "b", "r", "q"].get(promotion as usize - 1).copied());
+    }
+    uci
+}
+
+fn read_entry(file: &mut File, index: u64) -> std::io::Result<(u64, u16, u16)> {
+    let mut entry = [0; ENTRY_SIZE as usize];
+    file.seek(SeekFrom::Start(index * ENTRY_SIZE))?;
+    file.read_exact(&mut entry)?;
+    Ok((
+        u64::from_be_bytes(entry[..8].try_into().unwrap()),
+        u16::from_be_bytes([entry[8], entry[9]]),
+        u16::from_be_bytes([entry[10], entry[11]]),
+    ))
+}
+
+/// The moves and weights a Polyglot book, sorted by key, has for `key`.
+fn probe(path: &Path, key: u64) -> std::io::Result<Vec<(u16, u16)>> {
+    let mut file = File::open(path)?;
+    let entries = file.metadata()?.len() / ENTRY_SIZE;
+    let (mut low, mut high) = (0, entries);
+    while low < high {
+        let middle = (low + high) / 2;
+        if read_entry(&mut file, middle)?.0 < key {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    let mut moves = Vec::new();
+    for index in low..entries {
+        let (entry_key, raw, weight) = read_entry(&mut file, index)?;
+        if entry_key != key {
+            break;
+        }
+        moves.push((raw, weight));
+    }
+    Ok(moves)
+}
+
+/// The legal book moves of `position` across `books`, most weighted first.
+fn book_moves(books: &[PathBuf], position: &Chess, log: &RotatingLog) -> Vec<TlcsBookMove> {
+    let key = polyglot_key(position);
+    let mut weights: HashMap<String, (String, u32)> = HashMap::new();
+    for book in books {
+        let entries = match probe(book, key) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log.error(&format!(
+                    "Failed to read opening book {}: {err}",
+                    book.to_string_lossy()
+                ));
+                continue;
+            }
+        };
+        for (raw, weight) in entries {
+            let Some(mv) = UciMove::from_ascii(decode_move(raw).as_bytes())
+                .ok()
+                .and_then(|uci| uci.to_move(position).ok())
+            else {
+                continue;
+            };
+            let uci = mv.to_uci(CastlingMode::Standard).to_string();
+            let san = SanPlus::from_move(position.clone(), &mv).to_string();
+            weights.entry(uci).or_insert((san, 0)).1 += u32::from(weight);
+        }
+    }
+    let mut moves: Vec<TlcsBookMove> = weights
+        .into_iter()
+        .map(|(uci, (san, weight))| TlcsBookMove { san, uci, weight })
+        .collect();
+    moves.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.san.cmp(&b.san)));
+    moves
+}
+
+/// Looks up every new live position in Polyglot opening books, one at a
+/// time, so commentators can show the theory until each game leaves it.
+pub struct TlcsBookWatch {
+    requests: mpsc::UnboundedSender<BookRequest>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsBookWatch {
+    pub fn spawn(books: Vec<PathBuf>, events: TlcsEmitter, log: RotatingLog) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_book_watch(Arc::new(books), events, log, rx));
+        Self { requests, task }
+    }
+
+    pub fn check(&self, board: Option<u32>, ply: usize, fen: String) {
+        let _ = self.requests.send(BookRequest { board, ply, fen });
+    }
+
+    pub async fn stop(self) {
+        drop(self.requests);
+        let _ = self.task.await;
+    }
+}
+
+async fn run_book_watch(
+    books: Arc<Vec<PathBuf>>,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    mut rx: mpsc::UnboundedReceiver<BookRequest>,
+) {
+    // Boards whose last position was in book.
+    let mut in_book: HashMap<Option<u32>, bool> = HashMap::new();
+
+    while let Some(request) = rx.recv().await {
+        let Some(position) = request
+            .fen
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position::<Chess>(CastlingMode::Chess960).ok())
+        else {
+            continue;
+        };
+        let (books, log_clone) = (books.clone(), log.clone());
+        let moves =
+            match tokio::task::spawn_blocking(move || book_moves(&books, &position, &log_clone))
+                .await
+            {
+                Ok(moves) => moves,
+                Err(_) => continue,
+            };
+
+        let was_in_book = in_book.insert(request.board, !moves.is_empty());
+        if moves.is_empty() && was_in_book != Some(true) {
+            continue;
+        }
+        events.emit(
+            "tlcs-book",
+            TlcsBookEvent {
+                board: request.board,
+                ply: request.ply,
+                fen: request.fen,
+                moves,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polyglot_keys_and_moves() {
+        assert_eq!(polyglot_key(&Chess::default()), 0x463b96181691fc9c);
+        // e2e4, e1h1 and a7a8q.
+        assert_eq!(decode_move(12 << 6 | 28), "e2e4");
+        assert_eq!(decode_move(4 << 6 | 7), "e1h1");
+        assert_eq!(decode_move(4 << 12 | 48 << 6 | 56), "a7a8q");
+    }
+}
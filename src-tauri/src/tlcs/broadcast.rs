@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+use tokio::select;
+use tokio::sync::{watch, Notify, RwLock};
+
+use crate::error::Error;
+
+use super::{http_client, RotatingLog, TlcsDemux, TlcsEmitter};
+
+const LICHESS_BROADCAST_ROUND_URL: &str = "https://lichess.org/api/broadcast/round";
+const MIN_PUSH_INTERVAL_SECS: u64 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBroadcastOptions {
+    pub round_id: String,
+    pub token: String,
+    /// Push every N seconds. When unset, every recorded move triggers a push.
+    pub interval_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBroadcastEvent {
+    pub round_id: String,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+/// Pushes the live PGN of a recording session to a Lichess broadcast round.
+pub struct TlcsBroadcastPush {
+    moved: Arc<Notify>,
+    shutdown: watch::Sender<bool>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsBroadcastPush {
+    pub fn spawn(
+        options: TlcsBroadcastOptions,
+        recorder: Arc<RwLock<TlcsDemux>>,
+        events: TlcsEmitter,
+        log: RotatingLog,
+    ) -> Self {
+        let moved = Arc::new(Notify::new());
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(run_broadcast_push(
+            options,
+            recorder,
+            events,
+            log,
+            moved.clone(),
+            shutdown_rx,
+        ));
+        Self {
+            moved,
+            shutdown,
+            task,
+        }
+    }
+
+    pub fn notify_move(&self) {
+        self.moved.notify_one();
+    }
+
+    /// Stops the push loop after a final push of the current PGN.
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.task.await;
+    }
+}
+
+async fn push_pgn(
+    client: &Client,
+    options: &TlcsBroadcastOptions,
+    pgn: String,
+) -> Result<(), Error> {
+    client
+        .post(format!(
+            "{LICHESS_BROADCAST_ROUND_URL}/{}/push",
+            options.round_id
+        ))
+        .bearer_auth(&options.token)
+        .body(pgn)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+async fn run_broadcast_push(
+    options: TlcsBroadcastOptions,
+    recorder: Arc<RwLock<TlcsDemux>>,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    moved: Arc<Notify>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let client = http_client();
+    let mut last_pushed = String::new();
+    let mut interval = options
+        .interval_secs
+        .map(|secs| tokio::time::interval(Duration::from_secs(secs.max(MIN_PUSH_INTERVAL_SECS))));
+
+    loop {
+        let stopping = select! {
+            _ = shutdown_rx.changed() => true,
+            _ = moved.notified(), if interval.is_none() => false,
+            _ = async { interval.as_mut().unwrap().tick().await }, if interval.is_some() => false,
+        };
+
+        let pgn = recorder.read().await.live_pgn();
+
+        if pgn != last_pushed {
+            let result = push_pgn(&client, &options, pgn.clone()).await;
+            let message = match &result {
+                Ok(()) => {
+                    log.debug(&format!(
+                        "Pushed live PGN to broadcast {}",
+                        options.round_id
+                    ));
+                    last_pushed = pgn;
+                    None
+                }
+                Err(err) => {
+                    log.error(&format!("Broadcast push failed: {err}"));
+                    Some(err.to_string())
+                }
+            };
+            events.emit(
+                "tlcs-broadcast",
+                TlcsBroadcastEvent {
+                    round_id: options.round_id.clone(),
+                    success: result.is_ok(),
+                    message,
+                },
+            );
+        }
+
+        if stopping {
+            break;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use chrono::{SecondsFormat, Utc};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::error::Error;
+
+use super::redact_credentials;
+
+pub const CAPTURE_EXTENSION: &str = "tlcs";
+
+/// Sidecar file with every line exchanged with the TLCS server, for bug
+/// reports to relay server authors. Each line is prefixed with its UTC
+/// timestamp and `<` (received) or `>` (sent), followed by the bytes exactly
+/// as they went over the wire, line terminator included. Unlike the log, it
+/// is not filtered by level and received lines are not decoded. Credentials
+/// are masked in sent lines.
+#[derive(Clone)]
+pub struct TlcsCapture {
+    inner: Arc<Mutex<CaptureFile>>,
+}
+
+struct CaptureFile {
+    file: File,
+    /// Received bytes of a line whose terminator has not arrived yet, with
+    /// the time its first byte did.
+    partial: Vec<u8>,
+    partial_since: String,
+}
+
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+impl TlcsCapture {
+    /// Opens `path` for appending, so a resumed session continues its capture.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(CaptureFile {
+                file,
+                partial: Vec::new(),
+                partial_since: String::new(),
+            })),
+        })
+    }
+
+    pub fn received(&self, bytes: &[u8]) {
+        let Ok(mut capture) = self.inner.lock() else {
+            return;
+        };
+        for chunk in bytes.split_inclusive(|byte| *byte == b'\n') {
+            if capture.partial.is_empty() {
+                capture.partial_since = timestamp();
+            }
+            capture.partial.extend_from_slice(chunk);
+            if chunk.ends_with(b"\n") {
+                let line = std::mem::take(&mut capture.partial);
+                let since = std::mem::take(&mut capture.partial_since);
+                capture.write(&since, '<', &line);
+            }
+        }
+    }
+
+    pub fn sent(&self, bytes: &[u8]) {
+        let line = redact_credentials(&String::from_utf8_lossy(bytes));
+        if let Ok(mut capture) = self.inner.lock() {
+            capture.write(&timestamp(), '>', line.as_bytes());
+        }
+    }
+}
+
+impl CaptureFile {
+    fn write(&mut self, timestamp: &str, direction: char, bytes: &[u8]) {
+        let _ = write!(self.file, "{timestamp} {direction} ");
+        let _ = self.file.write_all(bytes);
+        if !bytes.ends_with(b"\n") {
+            let _ = self.file.write_all(b"\n");
+        }
+    }
+}
+
+impl Drop for CaptureFile {
+    fn drop(&mut self) {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            let since = std::mem::take(&mut self.partial_since);
+            self.write(&since, '<', &line);
+        }
+    }
+}
+
+/// Copies everything read from `inner` into the capture, when there is one.
+pub struct CaptureReader<R> {
+    inner: R,
+    capture: Option<TlcsCapture>,
+}
+
+impl<R> CaptureReader<R> {
+    pub fn new(inner: R, capture: Option<TlcsCapture>) -> Self {
+        Self { inner, capture }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CaptureReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let (Poll::Ready(Ok(())), Some(capture)) = (&poll, &self.capture) {
+            capture.received(&buf.filled()[filled..]);
+        }
+        poll
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio::select;
+use tokio::sync::watch;
+
+use super::RotatingLog;
+
+const CHESS_COM_URL: &str = "https://www.chess.com";
+const CHESS_COM_API_URL: &str = "https://api.chess.com/pub";
+const CHESS_COM_BUFFER_BYTES: usize = 64 * 1024;
+const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
+const MIN_POLL_INTERVAL_MS: u64 = 1000;
+/// Alphabet of chess.com's two-characters-per-move encoding.
+const TCN_ALPHABET: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!?{~}(^)[_]@#$,./&-*++=";
+
+/// The chess.com game, or player whose game, to record.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsChessComOptions {
+    /// Such as `https://www.chess.com/game/live/123456789`.
+    pub game_url: Option<String>,
+    /// Records the first ongoing game of this player when no URL is given.
+    /// The published API only lists daily games.
+    pub username: Option<String>,
+    pub poll_interval_ms: Option<u64>,
+}
+
+/// Splits a game URL into its kind, `live` or `daily`, and id.
+fn parse_game_url(url: &str) -> Option<(&str, &str)> {
+    let mut segments = url.trim_end_matches('/').rsplit('/');
+    let id = segments
+        .next()
+        .filter(|id| id.bytes().all(|b| b.is_ascii_digit()))?;
+    let kind = match segments.next()? {
+        "daily" => "daily",
+        _ => "live",
+    };
+    Some((kind, id))
+}
+
+/// Decodes a chess.com move list into UCI moves.
+fn decode_moves(encoded: &str) -> Option<Vec<String>> {
+    let index = |byte: u8| TCN_ALPHABET.iter().position(|c| *c == byte);
+    let square = |n: usize| format!("{}{}", (b'a' + (n % 8) as u8) as char, n / 8 + 1);
+    encoded
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let from = index(*pair.first()?)?;
+            let mut to = index(*pair.get(1)?)?;
+            let mut promotion = None;
+            if to > 63 {
+                promotion = Some(b"qnrbkp"[(to - 64) / 3] as char);
+                let forward = if from < 16 { -8 } else { 8 };
+                to = (from as isize + forward + ((to as isize - 1) % 3) - 1) as usize;
+            }
+            if from > 75 {
+                let piece = (b"qnrbkp"[from - 79] as char).to_ascii_uppercase();
+                return Some(format!("{piece}@{}", square(to)));
+            }
+            let mut uci = format!("{}{}", square(from), square(to));
+            uci.extend(promotion);
+            Some(uci)
+        })
+        .collect()
+}
+
+/// What one poll of a game returned.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ChessComGame {
+    moves: Vec<String>,
+    /// Remaining time of each side after the last move, in milliseconds.
+    clocks: Option<(u64, u64)>,
+    result: Option<String>,
+}
+
+impl ChessComGame {
+    fn parse(callback: &Value) -> Option<Self> {
+        let game = &callback["game"];
+        let moves = decode_moves(game["moveList"].as_str()?)?;
+        // Remaining time after every ply, in tenths of a second.
+        let timestamps: Vec<u64> = game["moveTimestamps"]
+            .as_str()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|time| time.trim().parse().ok())
+            .collect();
+        let last = |parity: usize| {
+            timestamps
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .last()
+                .map(|tenths| tenths * 100)
+        };
+        let result = game["pgnHeaders"]["Result"]
+            .as_str()
+            .filter(|_| game["isFinished"].as_bool() == Some(true))
+            .map(str::to_string);
+        Some(Self {
+            moves,
+            clocks: last(0).zip(last(1)),
+            result,
+        })
+    }
+
+    /// The TLCS lines that take the recording from `previous` to this state.
+    fn lines(&self, previous: &Self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.moves.starts_with(&previous.moves) {
+            lines.extend(self.moves[previous.moves.len()..].iter().cloned());
+        } else {
+            lines.push(format!("1. {}", self.moves.join(" ")));
+        }
+        if self.clocks != previous.clocks {
+            if let Some((white, black)) = self.clocks {
+                lines.push(format!("clock w={white} b={black}"));
+            }
+        }
+        if self.result != previous.result {
+            lines.extend(self.result.clone());
+        }
+        lines
+    }
+}
+
+async fn fetch(client: &Client, url: &str) -> reqwest::Result<Value> {
+    client
+        .get(url)
+        .header("User-Agent", "En Croissant")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Finds the game to poll: the one in `options.game_url`, or the first
+/// ongoing game of `options.username`.
+async fn callback_url(client: &Client, options: &TlcsChessComOptions) -> std::io::Result<String> {
+    let game_url = match (&options.game_url, &options.username) {
+        (Some(url), _) => url.clone(),
+        (None, Some(username)) => {
+            let url = format!("{CHESS_COM_API_URL}/player/{username}/games");
+            let games = fetch(client, &url).await.map_err(std::io::Error::other)?;
+            games["games"][0]["url"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| std::io::Error::other(format!("{username} has no ongoing game")))?
+        }
+        (None, None) => return Err(std::io::Error::other("No chess.com game or player given")),
+    };
+    let (kind, id) = parse_game_url(&game_url)
+        .ok_or_else(|| std::io::Error::other(format!("Not a chess.com game URL: {game_url}")))?;
+    Ok(format!("{CHESS_COM_URL}/callback/{kind}/game/{id}"))
+}
+
+/// Polls a chess.com game and returns a pipe carrying its new moves, clocks
+/// and result as TLCS lines. The pipe is closed when `shutdown` fires or the
+/// game is over.
+pub(super) async fn connect(
+    options: TlcsChessComOptions,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<DuplexStream> {
+    let client = Client::new();
+    let url = callback_url(&client, &options).await?;
+    let interval = Duration::from_millis(
+        options
+            .poll_interval_ms
+            .unwrap_or(DEFAULT_POLL_INTERVAL_MS)
+            .max(MIN_POLL_INTERVAL_MS),
+    );
+    log.info(&format!("Polling {url}"));
+
+    let (reader, mut writer) = tokio::io::duplex(CHESS_COM_BUFFER_BYTES);
+    tokio::spawn(async move {
+        let mut previous = ChessComGame::default();
+        loop {
+            match fetch(&client, &url)
+                .await
+                .map(|game| ChessComGame::parse(&game))
+            {
+                Ok(Some(game)) => {
+                    for line in game.lines(&previous) {
+                        if writer
+                            .write_all(format!("{line}\r\n").as_bytes())
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    let finished = game.result.is_some();
+                    previous = game;
+                    if finished {
+                        log.info("chess.com game finished");
+                        break;
+                    }
+                }
+                Ok(None) => log.error("Unexpected chess.com game format"),
+                Err(err) => log.error(&format!("chess.com poll failed: {err}")),
+            }
+            select! {
+                _ = shutdown.changed() => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_lists_are_decoded() {
+        // 1. e4 e5 2. Nf3, then a white pawn on e7 promoting to a queen on e8.
+        assert_eq!(decode_moves("mC0Kgv").unwrap(), ["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(decode_moves("0~").unwrap(), ["e7e8q"]);
+        assert_eq!(
+            parse_game_url("https://www.chess.com/game/live/123456789"),
+            Some(("live", "123456789"))
+        );
+    }
+
+    #[test]
+    fn polls_are_turned_into_lines() {
+        let previous = ChessComGame {
+            moves: vec!["e2e4".into()],
+            clocks: Some((179_500, 180_000)),
+            result: None,
+        };
+        let game = ChessComGame {
+            moves: vec!["e2e4".into(), "e7e5".into()],
+            clocks: Some((179_500, 179_000)),
+            result: None,
+        };
+        assert_eq!(game.lines(&previous), ["e7e5", "clock w=179500 b=179000"]);
+    }
+}
@@ -0,0 +1,291 @@
+use std::io::{Error, ErrorKind, Result};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::select;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+/// Head start of each address over the next one, as in RFC 8305.
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+/// Gives up on one address after this long, while the others are tried.
+const CONNECTION_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_SOCKS_PORT: u16 = 1080;
+const DEFAULT_HTTP_PROXY_PORT: u16 = 8080;
+const MAX_PROXY_RESPONSE_BYTES: usize = 8 * 1024;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+fn proxy_error(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::ConnectionRefused, message.into())
+}
+
+/// Alternates IPv6 and IPv4 addresses, starting with the family the resolver
+/// put first, so one badly routed family cannot stall the connection.
+fn interleave(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addresses.first() else {
+        return addresses;
+    };
+    let first_v6 = first.is_ipv6();
+    let (preferred, other): (Vec<_>, Vec<_>) = addresses
+        .into_iter()
+        .partition(|address| address.is_ipv6() == first_v6);
+    let (mut preferred, mut other) = (preferred.into_iter(), other.into_iter());
+    let mut ordered = Vec::new();
+    loop {
+        let next = [preferred.next(), other.next()];
+        if next.iter().all(Option::is_none) {
+            return ordered;
+        }
+        ordered.extend(next.into_iter().flatten());
+    }
+}
+
+/// Connects to every address `host` resolves to, happy eyeballs style: each
+/// address gets a short head start before the next one joins the race, and
+/// the first to connect wins. The address used is the stream's peer address.
+async fn connect_any(host: &str, port: u16) -> Result<TcpStream> {
+    let mut addresses = interleave(lookup_host((host, port)).await?.collect()).into_iter();
+    let mut attempts = JoinSet::new();
+    let mut next = addresses.next();
+    let mut last_error = None;
+    loop {
+        if let Some(address) = next.take() {
+            attempts.spawn(async move {
+                timeout(CONNECTION_ATTEMPT_TIMEOUT, TcpStream::connect(address))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(Error::new(
+                            ErrorKind::TimedOut,
+                            format!("Timed out connecting to {address}"),
+                        ))
+                    })
+            });
+        }
+        if attempts.is_empty() {
+            break;
+        }
+        let untried = !addresses.as_slice().is_empty();
+        select! {
+            Some(joined) = attempts.join_next() => match joined {
+                // Dropping the set aborts the attempts still running.
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(err)) => {
+                    last_error = Some(err);
+                    next = addresses.next();
+                }
+                Err(err) => {
+                    last_error = Some(Error::other(err));
+                    next = addresses.next();
+                }
+            },
+            _ = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY), if untried => {
+                next = addresses.next();
+            }
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| Error::new(ErrorKind::NotFound, format!("{host} has no address"))))
+}
+
+/// Opens a TCP connection to a TLCS server, tunnelling through `proxy_url`
+/// when one is given. Supported schemes are `socks5://`, `socks5h://` and
+/// `http://`, with optional `user:password@` credentials.
+pub async fn connect_tcp(host: &str, port: u16, proxy_url: Option<&str>) -> Result<TcpStream> {
+    let Some(proxy_url) = proxy_url.filter(|url| !url.trim().is_empty()) else {
+        return connect_any(host, port).await;
+    };
+
+    let proxy =
+        Url::parse(proxy_url).map_err(|err| invalid(format!("Invalid proxy URL: {err}")))?;
+    let proxy_host = proxy
+        .host_str()
+        .ok_or_else(|| invalid("Proxy URL has no host"))?;
+
+    match proxy.scheme() {
+        "socks5" | "socks5h" => {
+            let proxy_port = proxy.port().unwrap_or(DEFAULT_SOCKS_PORT);
+            let mut stream = connect_any(proxy_host, proxy_port).await?;
+            socks5_connect(&mut stream, &proxy, host, port).await?;
+            Ok(stream)
+        }
+        "http" => {
+            let proxy_port = proxy.port().unwrap_or(DEFAULT_HTTP_PROXY_PORT);
+            let mut stream = connect_any(proxy_host, proxy_port).await?;
+            http_connect(&mut stream, &proxy, host, port).await?;
+            Ok(stream)
+        }
+        scheme => Err(invalid(format!("Unsupported proxy scheme: {scheme}"))),
+    }
+}
+
+/// Performs the SOCKS5 handshake (RFC 1928), with username/password
+/// authentication (RFC 1929) when the proxy URL carries credentials.
+async fn socks5_connect(stream: &mut TcpStream, proxy: &Url, host: &str, port: u16) -> Result<()> {
+    let username = proxy.username();
+    let password = proxy.password().unwrap_or("");
+    let use_auth = !username.is_empty();
+
+    let methods: &[u8] = if use_auth { &[0x00, 0x02] } else { &[0x00] };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice[0] != 0x05 {
+        return Err(proxy_error("Proxy is not a SOCKS5 server"));
+    }
+    match choice[1] {
+        0x00 => {}
+        0x02 if use_auth => {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(invalid("SOCKS5 credentials are too long"));
+            }
+            let mut auth = vec![0x01, username.len() as u8];
+            auth.extend_from_slice(username.as_bytes());
+            auth.push(password.len() as u8);
+            auth.extend_from_slice(password.as_bytes());
+            stream.write_all(&auth).await?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await?;
+            if status[1] != 0x00 {
+                return Err(proxy_error("SOCKS5 proxy rejected the credentials"));
+            }
+        }
+        _ => return Err(proxy_error("SOCKS5 proxy offered no usable authentication")),
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(invalid("Host name is too long for SOCKS5"));
+            }
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        return Err(proxy_error(format!(
+            "SOCKS5 proxy refused the connection (code {})",
+            reply[1]
+        )));
+    }
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        _ => return Err(proxy_error("SOCKS5 proxy sent an invalid reply")),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+/// Opens a tunnel with an HTTP `CONNECT` request.
+async fn http_connect(stream: &mut TcpStream, proxy: &Url, host: &str, port: u16) -> Result<()> {
+    let target = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => format!("[{host}]:{port}"),
+        _ => format!("{host}:{port}"),
+    };
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if !proxy.username().is_empty() {
+        let credentials = format!("{}:{}", proxy.username(), proxy.password().unwrap_or(""));
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            base64_encode(credentials.as_bytes())
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read the response headers byte by byte, so nothing sent by the TLCS
+    // server right after the tunnel opens is swallowed.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_PROXY_RESPONSE_BYTES {
+            return Err(proxy_error("HTTP proxy response is too long"));
+        }
+        response.push(stream.read_u8().await?);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(proxy_error(format!(
+            "HTTP proxy refused the tunnel: {status_line}"
+        ))),
+    }
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[((triple >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_families_alternate() {
+        let v4 = |last: u8| SocketAddr::from(([192, 0, 2, last], 16001));
+        let v6 = |last: u16| SocketAddr::from(([0x2001, 0xdb8, 0, 0, 0, 0, 0, last], 16001));
+        assert_eq!(
+            interleave(vec![v6(1), v6(2), v6(3), v4(1)]),
+            vec![v6(1), v4(1), v6(2), v6(3)]
+        );
+        assert_eq!(
+            interleave(vec![v4(1), v4(2), v6(1), v6(2)]),
+            vec![v4(1), v6(1), v4(2), v6(2)]
+        );
+        assert!(interleave(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn base64_matches_rfc4648() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+    }
+}
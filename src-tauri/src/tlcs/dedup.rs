@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use super::{
+    is_result_token, parse_movetext, parse_takeback, position_key, MovetextToken, TlcsRecorder,
+};
+
+/// The players and start position that identify a game across boards.
+pub(super) type GameKey = (String, String, String);
+
+/// Games with an unknown player are never considered the same.
+pub(super) fn game_key(white: &str, black: &str, start: String) -> Option<GameKey> {
+    let player = |name: &str| {
+        let name = name.trim().to_lowercase();
+        (!name.is_empty() && name != "?" && name != "unknown").then_some(name)
+    };
+    Some((player(white)?, player(black)?, start))
+}
+
+pub(super) fn recorder_key(recorder: &TlcsRecorder) -> Option<GameKey> {
+    game_key(
+        recorder.headers.get("White")?,
+        recorder.headers.get("Black")?,
+        position_key(&recorder.start_position),
+    )
+}
+
+/// The ply a source reaches with a movetext line, starting from `from`, and
+/// whether the line has a result. `None` when the line has no moves.
+fn reported_ply(line: &str, from: usize) -> Option<(usize, bool)> {
+    let mut ply = from;
+    let mut moves = false;
+    let mut result = false;
+    for token in parse_movetext(line) {
+        match token {
+            MovetextToken::MoveNumber(number) => ply = number,
+            MovetextToken::Move(token) if is_result_token(&token) => result = true,
+            MovetextToken::Move(_) => {
+                ply += 1;
+                moves = true;
+            }
+            _ => {}
+        }
+    }
+    (moves || result).then_some((ply, result))
+}
+
+/// Boards that carry the same game, typically a primary and a backup relay,
+/// and how far the source of each got, so the game follows whichever source
+/// is ahead.
+#[derive(Default)]
+pub(super) struct TlcsDedup {
+    /// Board that records the game of each duplicate board.
+    aliases: BTreeMap<u32, u32>,
+    /// Ply reached by the source of each board of a merged game.
+    reported: BTreeMap<u32, usize>,
+}
+
+impl TlcsDedup {
+    /// The board whose recorder takes the lines of `board`.
+    pub(super) fn target(&self, board: u32) -> u32 {
+        self.aliases.get(&board).copied().unwrap_or(board)
+    }
+
+    /// Records the lines of `duplicate`, whose source reached `duplicate_ply`,
+    /// into the game of `into`, recorded up to `into_ply`.
+    pub(super) fn merge(
+        &mut self,
+        duplicate: u32,
+        into: u32,
+        duplicate_ply: usize,
+        into_ply: usize,
+    ) {
+        for target in self.aliases.values_mut() {
+            if *target == duplicate {
+                *target = into;
+            }
+        }
+        self.aliases.insert(duplicate, into);
+        self.reported.insert(duplicate, duplicate_ply);
+        self.reported.entry(into).or_insert(into_ply);
+    }
+
+    /// Whether a line from `board` should be applied to `recorder`, the game
+    /// it was merged into. Lines of a lagging source that would take the game
+    /// back are dropped: moves and clocks already recorded, positions seen
+    /// earlier and takebacks another source already sent.
+    pub(super) fn accept(&mut self, board: u32, line: &str, recorder: &TlcsRecorder) -> bool {
+        let target = self.target(board);
+        if !self.reported.contains_key(&target) {
+            return true;
+        }
+        let line = line.trim();
+        let recorded = recorder.moves.len();
+        if recorder.new_game_from_line(line).is_some() {
+            let sources: Vec<u32> = self
+                .reported
+                .keys()
+                .copied()
+                .filter(|source| self.target(*source) == target)
+                .collect();
+            for source in sources {
+                self.reported.insert(source, 0);
+            }
+            return true;
+        }
+
+        let reported = self.reported.get(&board).copied().unwrap_or(0);
+        if let Some(plies) = parse_takeback(line) {
+            self.reported.insert(board, reported.saturating_sub(plies));
+            return reported == recorded;
+        }
+        if line.starts_with("clock ") {
+            return reported >= recorded;
+        }
+        if let Some(fen) = line.strip_prefix("fen ") {
+            return !recorder
+                .variant
+                .start_position(Some(fen.trim()))
+                .is_ok_and(|position| {
+                    recorder
+                        .find_earlier_ply(&position_key(&position))
+                        .is_some()
+                });
+        }
+        if line.starts_with("status ") {
+            return true;
+        }
+        match reported_ply(line, reported) {
+            Some((ply, result)) => {
+                self.reported.insert(board, ply);
+                result || ply > recorded
+            }
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_report_their_ply() {
+        assert_eq!(reported_ply("1. e4 e5 2. Nf3", 0), Some((3, false)));
+        assert_eq!(reported_ply("12... Nf6", 5), Some((24, false)));
+        assert_eq!(reported_ply("Nf6", 5), Some((6, false)));
+        assert_eq!(reported_ply("1-0", 40), Some((40, true)));
+        assert_eq!(reported_ply("{ time trouble }", 7), None);
+        assert!(game_key("Carlsen", "Unknown", String::new()).is_none());
+        assert_eq!(
+            game_key(" Carlsen", "CARUANA", String::new()),
+            game_key("carlsen", "Caruana ", String::new())
+        );
+    }
+}
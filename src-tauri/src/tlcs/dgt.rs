@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use shakmaty::fen::Fen;
+use shakmaty::{Board, CastlingMode, Chess, EnPassantMode, Position};
+use specta::Type;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::select;
+use tokio::sync::watch;
+use tokio_serial::SerialPortBuilderExt;
+
+use super::fen_diff::find_move;
+use super::RotatingLog;
+use crate::AppState;
+
+const DGT_BAUD_RATE: u32 = 9600;
+const DGT_BUFFER_BYTES: usize = 64 * 1024;
+const DGT_SEND_RESET: u8 = 0x40;
+const DGT_SEND_BRD: u8 = 0x42;
+const DGT_SEND_UPDATE_BRD: u8 = 0x44;
+const DGT_BOARD_DUMP: u8 = 0x86;
+const DGT_FIELD_UPDATE: u8 = 0x8e;
+/// Piece codes of a board dump, starting at 1.
+const DGT_PIECES: &[u8; 12] = b"PRNBKQprnbkq";
+
+/// The locally connected DGT board to record.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsDgtOptions {
+    /// Serial port the board is on, such as `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    /// Also sends every move to this game on the TLCS server the client is
+    /// connected to.
+    pub forward_game_id: Option<String>,
+}
+
+/// Takes complete messages off the front of `pending`: the message id and
+/// its data.
+fn next_message(pending: &mut Vec<u8>) -> Option<(u8, Vec<u8>)> {
+    // Skip anything before the first message id.
+    let start = pending.iter().position(|byte| byte & 0x80 != 0)?;
+    pending.drain(..start);
+    if pending.len() < 3 {
+        return None;
+    }
+    let size = ((usize::from(pending[1]) << 7) | usize::from(pending[2])).max(3);
+    if pending.len() < size {
+        return None;
+    }
+    let message: Vec<u8> = pending.drain(..size).collect();
+    Some((message[0], message[3..].to_vec()))
+}
+
+/// The board of a dump, whose squares run from a8 to h1.
+fn parse_board_dump(squares: &[u8]) -> Option<Board> {
+    if squares.len() != 64 {
+        return None;
+    }
+    let mut placement = String::new();
+    for (i, code) in squares.iter().enumerate() {
+        if i > 0 && i % 8 == 0 {
+            placement.push('/');
+        }
+        match code {
+            0 => match placement.pop() {
+                Some(empty @ '1'..='7') => placement.push((empty as u8 + 1) as char),
+                other => {
+                    placement.extend(other);
+                    placement.push('1');
+                }
+            },
+            code => placement.push(*DGT_PIECES.get(usize::from(*code) - 1)? as char),
+        }
+    }
+    Board::from_ascii_board_fen(placement.as_bytes()).ok()
+}
+
+/// Opens the DGT board on `dgt.port` and returns a pipe carrying the moves
+/// played on it as TLCS lines. Setting up the initial position restarts the
+/// game. The pipe is closed when `shutdown` fires or the board disconnects.
+pub(super) async fn connect(
+    dgt: TlcsDgtOptions,
+    app: AppHandle,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<DuplexStream> {
+    let mut serial = tokio_serial::new(&dgt.port, DGT_BAUD_RATE)
+        .open_native_async()
+        .map_err(std::io::Error::other)?;
+    serial
+        .write_all(&[DGT_SEND_RESET, DGT_SEND_BRD, DGT_SEND_UPDATE_BRD])
+        .await?;
+    log.info(&format!("Reading DGT board on {}", dgt.port));
+
+    let (reader, mut writer) = tokio::io::duplex(DGT_BUFFER_BYTES);
+    tokio::spawn(async move {
+        let mut position = Chess::default();
+        let mut buffer = vec![0; 1024];
+        let mut pending = Vec::new();
+        loop {
+            let read = select! {
+                _ = shutdown.changed() => break,
+                read = serial.read(&mut buffer) => read,
+            };
+            match read {
+                Ok(0) => {
+                    log.info("DGT board disconnected");
+                    break;
+                }
+                Ok(len) => pending.extend_from_slice(&buffer[..len]),
+                Err(err) => {
+                    log.error(&format!("DGT read error: {err}"));
+                    break;
+                }
+            }
+
+            while let Some((id, data)) = next_message(&mut pending) {
+                // A field update names a single square; the whole board is
+                // needed to tell which move, if any, it completes.
+                if id == DGT_FIELD_UPDATE {
+                    if let Err(err) = serial.write_all(&[DGT_SEND_BRD]).await {
+                        log.error(&format!("DGT write error: {err}"));
+                    }
+                    continue;
+                }
+                if id != DGT_BOARD_DUMP {
+                    continue;
+                }
+                let Some(board) = parse_board_dump(&data) else {
+                    continue;
+                };
+                let line = if let Some(mv) = find_move(&position, &board) {
+                    let uci = mv.to_uci(CastlingMode::Standard).to_string();
+                    position.play_unchecked(&mv);
+                    if let Some(game_id) = &dgt.forward_game_id {
+                        let state = app.state::<AppState>();
+                        let client = state.tlcs_client.read().await;
+                        if let Err(err) = client
+                            .send_move(game_id.clone(), uci.clone(), app.clone())
+                            .await
+                        {
+                            log.error(&format!("Failed to forward DGT move: {err}"));
+                        }
+                    }
+                    uci
+                } else if board == Board::default() && position.board() != &board {
+                    position = Chess::default();
+                    format!(
+                        "fen {}",
+                        Fen::from_position(position.clone(), EnPassantMode::Legal)
+                    )
+                } else {
+                    continue;
+                };
+                if writer
+                    .write_all(format!("{line}\r\n").as_bytes())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_dumps_are_turned_into_moves() {
+        let mut squares = vec![0; 64];
+        squares[..8].copy_from_slice(&[8, 9, 10, 12, 11, 10, 9, 8]);
+        squares[8..16].fill(7);
+        squares[48..56].fill(1);
+        squares[56..].copy_from_slice(&[2, 3, 4, 6, 5, 4, 3, 2]);
+        let mut message = vec![DGT_BOARD_DUMP, 0, 67];
+        message.extend_from_slice(&squares);
+
+        let (id, data) = next_message(&mut message).unwrap();
+        assert_eq!(id, DGT_BOARD_DUMP);
+        assert_eq!(parse_board_dump(&data), Some(Board::default()));
+
+        // e2 emptied, e4 taken by a white pawn.
+        squares[52] = 0;
+        squares[36] = 1;
+        let board = parse_board_dump(&squares).unwrap();
+        let mv = find_move(&Chess::default(), &board).unwrap();
+        assert_eq!(mv.to_uci(CastlingMode::Standard).to_string(), "e2e4");
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use specta::Type;
+use sysinfo::{DiskExt, System, SystemExt};
+
+use crate::{error::Error, AppState};
+
+use super::TlcsMetricsEvent;
+
+/// State of the recording session's socket, updated by its read loop.
+#[derive(Default)]
+pub(super) struct TlcsSessionHealth {
+    connected: AtomicBool,
+    last_line: Mutex<Option<DateTime<Utc>>>,
+    parse_errors: AtomicU64,
+}
+
+impl TlcsSessionHealth {
+    pub(super) fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub(super) fn line_received(&self) {
+        if let Ok(mut last_line) = self.last_line.lock() {
+            *last_line = Some(Utc::now());
+        }
+    }
+
+    pub(super) fn parse_failed(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Everything needed to troubleshoot a recorder remotely.
+#[derive(Clone, Debug, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsDiagnostics {
+    pub recording: bool,
+    pub session: Option<String>,
+    /// Whether the recording session's socket is open.
+    pub socket_connected: bool,
+    /// RFC 3339 time of the last line read by the recording session.
+    pub last_line_at: Option<String>,
+    /// Lines the recorder failed to apply.
+    pub parse_errors: u64,
+    pub moves_recorded: usize,
+    pub pgn_path: Option<String>,
+    /// Free space on the disk holding the PGN, when it can be determined.
+    pub disk_available_bytes: Option<u64>,
+    pub log_errors: u64,
+    pub log_write_failures: u64,
+    /// Counters of the playing connection, when one is open.
+    pub connection: Option<TlcsMetricsEvent>,
+}
+
+/// The available space of the mount point that contains `path`, which is
+/// the longest of `mounts` that is a prefix of it.
+fn available_space<'a>(path: &Path, mounts: impl Iterator<Item = (&'a Path, u64)>) -> Option<u64> {
+    mounts
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.as_os_str().len())
+        .map(|(_, available)| available)
+}
+
+fn disk_available_bytes(path: &Path) -> Option<u64> {
+    let path = path
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())?;
+    let mut system = System::new();
+    system.refresh_disks_list();
+    available_space(
+        &path,
+        system
+            .disks()
+            .iter()
+            .map(|disk| (disk.mount_point(), disk.available_space())),
+    )
+}
+
+/// Reports the health of the recording session and the playing connection.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_diagnostics(state: tauri::State<'_, AppState>) -> Result<TlcsDiagnostics, Error> {
+    let mut diagnostics = TlcsDiagnostics {
+        connection: state.tlcs.metrics().await,
+        ..Default::default()
+    };
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return Ok(diagnostics);
+    };
+
+    let health = &handle.health;
+    let (pgn_path, moves_recorded) = {
+        let recorder = handle.recorder.read().await;
+        let moves_recorded = recorder
+            .board_statuses()
+            .iter()
+            .map(|board| board.moves_recorded)
+            .sum();
+        (recorder.default.pgn_path(), moves_recorded)
+    };
+    diagnostics.recording = true;
+    diagnostics.session = handle.log.session().map(str::to_string);
+    diagnostics.socket_connected = health.connected.load(Ordering::Relaxed);
+    diagnostics.last_line_at = health
+        .last_line
+        .lock()
+        .ok()
+        .and_then(|last_line| *last_line)
+        .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true));
+    diagnostics.parse_errors = health.parse_errors.load(Ordering::Relaxed);
+    diagnostics.moves_recorded = moves_recorded;
+    diagnostics.disk_available_bytes = disk_available_bytes(&pgn_path);
+    diagnostics.pgn_path = Some(pgn_path.to_string_lossy().to_string());
+    diagnostics.log_errors = handle.log.errors();
+    diagnostics.log_write_failures = handle.log.write_failures();
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_innermost_mount_holds_the_path() {
+        let mounts = [
+            (Path::new("/"), 10),
+            (Path::new("/home"), 20),
+            (Path::new("/home2"), 30),
+        ];
+        assert_eq!(
+            available_space(Path::new("/home/arbiter/tlcs"), mounts.into_iter()),
+            Some(20)
+        );
+        assert_eq!(
+            available_space(Path::new("/var/tlcs"), mounts.into_iter()),
+            Some(10)
+        );
+        assert_eq!(
+            available_space(Path::new("relative"), mounts.into_iter()),
+            None
+        );
+    }
+}
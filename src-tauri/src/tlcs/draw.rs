@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+
+/// Why a draw can be claimed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsDrawClaim {
+    ThreefoldRepetition,
+    FiftyMoves,
+}
+
+/// Emitted when a recorded game reaches a position in which a draw can be
+/// claimed.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsDrawClaimEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub fen: String,
+    pub claim: TlcsDrawClaim,
+    pub repetition_count: u32,
+    pub halfmove_clock: u32,
+}
+
+/// How close a position is to a claimable draw.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct DrawStatus {
+    /// Times the position occurred, counting the current one.
+    pub(super) repetition_count: u32,
+    /// Plies since the last capture or pawn move.
+    pub(super) halfmove_clock: u32,
+}
+
+impl DrawStatus {
+    pub(super) fn claim(&self) -> Option<TlcsDrawClaim> {
+        if self.repetition_count >= 3 {
+            Some(TlcsDrawClaim::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(TlcsDrawClaim::FiftyMoves)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts the positions of a game from its successive FENs. Only positions
+/// since the last capture or pawn move can repeat, so the count starts over
+/// whenever the halfmove clock does.
+#[derive(Default)]
+pub(super) struct RepetitionTracker {
+    positions: HashMap<String, u32>,
+    last: Option<String>,
+}
+
+impl RepetitionTracker {
+    /// Adds the position of `fen`, unless it is the position added last.
+    pub(super) fn update(&mut self, fen: &str) -> DrawStatus {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let key = fields.iter().take(4).copied().collect::<Vec<_>>().join(" ");
+        let halfmove_clock = fields
+            .get(4)
+            .and_then(|clock| clock.parse().ok())
+            .unwrap_or(0);
+        if self.last.as_deref() != Some(fen) {
+            if halfmove_clock == 0 {
+                self.positions.clear();
+            }
+            *self.positions.entry(key.clone()).or_default() += 1;
+            self.last = Some(fen.to_string());
+        }
+        DrawStatus {
+            repetition_count: self.positions.get(&key).copied().unwrap_or(0),
+            halfmove_clock,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repetitions_and_fifty_moves() {
+        // Both knights go out and back twice.
+        let boards = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+            "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b",
+            "rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w",
+            "rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b",
+        ];
+        let mut tracker = RepetitionTracker::default();
+        let mut counts = Vec::new();
+        for ply in 0..9 {
+            let fen = format!("{} KQkq - {ply} {}", boards[ply % 4], ply / 2 + 1);
+            let status = tracker.update(&fen);
+            // The same FEN twice in a row is one position.
+            assert_eq!(tracker.update(&fen), status);
+            counts.push(status.repetition_count);
+        }
+        assert_eq!(counts, [1, 1, 1, 1, 2, 2, 2, 2, 3]);
+        assert_eq!(
+            tracker.update(&format!("{} KQkq - 8 5", boards[0])).claim(),
+            Some(TlcsDrawClaim::ThreefoldRepetition)
+        );
+        // A pawn move starts the count over.
+        let status = tracker.update("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 5");
+        assert_eq!(status.repetition_count, 1);
+        assert_eq!(
+            tracker
+                .update(&format!("{} KQkq - 9 5", boards[0]))
+                .repetition_count,
+            1
+        );
+
+        let fifty = DrawStatus {
+            repetition_count: 1,
+            halfmove_clock: 100,
+        };
+        assert_eq!(fifty.claim(), Some(TlcsDrawClaim::FiftyMoves));
+    }
+}
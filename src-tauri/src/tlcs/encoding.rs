@@ -0,0 +1,100 @@
+use encoding_rs::{WINDOWS_1250, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Character set the server writes its lines in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsInputEncoding {
+    #[default]
+    Utf8,
+    /// ISO 8859-1.
+    Latin1,
+    /// Western European Windows code page.
+    Windows1252,
+    /// Central European Windows code page, for Polish, Czech or Hungarian
+    /// names.
+    Windows1250,
+    /// UTF-8 when a line is valid UTF-8, otherwise one of the Windows code
+    /// pages, picked by the letters the line seems to contain.
+    Auto,
+}
+
+/// Bytes that are letters in windows-1250 but rarely used symbols in
+/// windows-1252: Ś Ź ś ź Ł Ą Ż ł ą ż.
+const CENTRAL_EUROPEAN_LETTERS: [u8; 10] =
+    [0x8C, 0x8F, 0x9C, 0x9F, 0xA3, 0xA5, 0xAF, 0xB3, 0xB9, 0xBF];
+
+impl TlcsInputEncoding {
+    pub(crate) fn decode(self, bytes: &[u8]) -> String {
+        let encoding = match self {
+            Self::Utf8 => return String::from_utf8_lossy(bytes).into_owned(),
+            Self::Latin1 => return bytes.iter().map(|&byte| char::from(byte)).collect(),
+            Self::Windows1252 => WINDOWS_1252,
+            Self::Windows1250 => WINDOWS_1250,
+            Self::Auto => match std::str::from_utf8(bytes) {
+                Ok(text) => return text.to_string(),
+                Err(_) if bytes.iter().any(|b| CENTRAL_EUROPEAN_LETTERS.contains(b)) => {
+                    WINDOWS_1250
+                }
+                Err(_) => WINDOWS_1252,
+            },
+        };
+        encoding.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+/// Reads lines like `tokio::io::Lines`, but decodes them with a
+/// `TlcsInputEncoding` instead of failing on invalid UTF-8.
+pub(crate) struct DecodedLines<R> {
+    reader: R,
+    encoding: TlcsInputEncoding,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin> DecodedLines<R> {
+    pub(crate) fn new(reader: R, encoding: TlcsInputEncoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The next line without its line ending, or `None` at the end of the
+    /// stream. Cancel safe: a partly read line is kept for the next call.
+    pub(crate) async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        let read = self.reader.read_until(b'\n', &mut self.buffer).await?;
+        if read == 0 && self.buffer.is_empty() {
+            return Ok(None);
+        }
+        let bytes = std::mem::take(&mut self.buffer);
+        let line = bytes.strip_suffix(b"\n").unwrap_or(&bytes);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        Ok(Some(self.encoding.decode(line)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_survive_legacy_code_pages() {
+        assert_eq!(TlcsInputEncoding::Auto.decode(b"M\xfcller"), "Müller");
+        assert_eq!(
+            TlcsInputEncoding::Auto.decode(b"E\xb3\xbfbieta"),
+            "Ełżbieta"
+        );
+        assert_eq!(
+            TlcsInputEncoding::Auto.decode("Ełżbieta".as_bytes()),
+            "Ełżbieta"
+        );
+        assert_eq!(TlcsInputEncoding::Latin1.decode(b"M\xfcller"), "Müller");
+        assert_eq!(
+            TlcsInputEncoding::Utf8.decode(b"M\xfcller"),
+            "M\u{fffd}ller"
+        );
+    }
+}
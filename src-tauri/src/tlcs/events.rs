@@ -0,0 +1,117 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// An event payload with the id of the session it belongs to.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionPayload<E> {
+    session_id: String,
+    #[serde(flatten)]
+    event: E,
+}
+
+/// The name `event` is emitted under, `tlcs-game/{id}` when scoped.
+fn event_name(event: &str, session_id: &str, scoped: bool) -> String {
+    if scoped {
+        format!("{event}/{session_id}")
+    } else {
+        event.to_string()
+    }
+}
+
+/// `id` with the characters event names do not allow replaced by `_`, so a
+/// session named after a file such as `round 3.pgn` can scope its events.
+pub(crate) fn event_safe_id(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Emits the events of one session. Every payload carries a `sessionId`,
+/// so listeners can tell sessions apart, and scoped sessions also add their
+/// id to the event names, so a listener only hears the session it follows.
+#[derive(Clone)]
+pub struct TlcsEmitter {
+    app: AppHandle,
+    session_id: String,
+    scoped: bool,
+}
+
+impl TlcsEmitter {
+    pub fn new(app: AppHandle, session_id: String, scoped: bool) -> Self {
+        Self {
+            app,
+            session_id,
+            scoped,
+        }
+    }
+
+    pub fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn emit<E: Serialize + Clone>(&self, event: &str, payload: E) {
+        self.emit_as(&event_name(event, &self.session_id, self.scoped), payload);
+    }
+
+    /// Emits `event` under its plain name even when the session is scoped,
+    /// for listeners that pick their payloads by another field.
+    pub fn emit_unscoped<E: Serialize + Clone>(&self, event: &str, payload: E) {
+        self.emit_as(event, payload);
+    }
+
+    fn emit_as<E: Serialize + Clone>(&self, name: &str, payload: E) {
+        let payload = SessionPayload {
+            session_id: self.session_id.clone(),
+            event: payload,
+        };
+        if let Err(err) = self.app.emit_all(name, payload) {
+            log::warn!("Failed to emit {name}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Flag {
+        side: &'static str,
+        clock_ms: u64,
+    }
+
+    #[test]
+    fn payloads_and_names_carry_the_session() {
+        let payload = SessionPayload {
+            session_id: "tlcs-20260301T140000Z".to_string(),
+            event: Flag {
+                side: "White",
+                clock_ms: 0,
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(payload).unwrap(),
+            serde_json::json!({
+                "sessionId": "tlcs-20260301T140000Z",
+                "side": "White",
+                "clockMs": 0,
+            })
+        );
+        assert_eq!(event_name("tlcs-game", "7", false), "tlcs-game");
+        assert_eq!(event_name("tlcs-game", "7", true), "tlcs-game/7");
+        assert_eq!(event_safe_id("round 3"), "round_3");
+        assert_eq!(event_safe_id("open.r3"), "open_r3");
+    }
+}
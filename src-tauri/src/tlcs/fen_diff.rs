@@ -0,0 +1,104 @@
+use shakmaty::{Board, Move, Position};
+
+/// The piece placement of a bare FEN line, as sent instead of moves by relay
+/// boards that only see where the pieces stand. The other fields of such
+/// FENs are often wrong or missing, so only the placement is read.
+pub(super) fn parse_placement(line: &str) -> Option<Board> {
+    let placement = line.split_whitespace().next()?;
+    if placement.matches('/').count() != 7 {
+        return None;
+    }
+    Board::from_ascii_board_fen(placement.as_bytes()).ok()
+}
+
+/// Looks for one or two legal plies leading from `position` to a position
+/// `reached` accepts, the second for when an update was missed.
+pub(super) fn find_plies<P: Position + Clone>(
+    position: &P,
+    reached: impl Fn(&P) -> bool,
+) -> Option<Vec<Move>> {
+    for mv in position.legal_moves() {
+        let mut after = position.clone();
+        after.play_unchecked(&mv);
+        if reached(&after) {
+            return Some(vec![mv]);
+        }
+        for reply in after.legal_moves() {
+            let mut next = after.clone();
+            next.play_unchecked(&reply);
+            if reached(&next) {
+                return Some(vec![mv, reply]);
+            }
+        }
+    }
+    None
+}
+
+/// The legal move in `position` that leads to `board`. Castling, en passant
+/// and each promotion change different squares, so at most one matches.
+/// Boards with pieces still in hand, or set up by hand, match none.
+pub(super) fn find_move<P: Position + Clone>(position: &P, board: &Board) -> Option<Move> {
+    position.legal_moves().into_iter().find(|mv| {
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        after.board() == board
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Chess};
+
+    use super::*;
+
+    fn uci_to(fen: &str, placement: &str) -> Option<String> {
+        let position: Chess = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let board = parse_placement(placement).unwrap();
+        find_move(&position, &board).map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+    }
+
+    #[test]
+    fn moves_are_told_from_placements() {
+        assert_eq!(
+            uci_to(
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                "r3k2r/8/8/8/8/8/8/R4RK1"
+            )
+            .as_deref(),
+            Some("e1g1")
+        );
+        assert_eq!(
+            uci_to(
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                "4k3/8/3P4/8/8/8/8/4K3 b"
+            )
+            .as_deref(),
+            Some("e5d6")
+        );
+        assert_eq!(
+            uci_to("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "1N6/4k3/8/8/8/8/8/4K3").as_deref(),
+            Some("b7b8n")
+        );
+        assert_eq!(
+            uci_to("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "8/1P2k3/8/8/8/8/8/4K3"),
+            None
+        );
+    }
+
+    #[test]
+    fn a_missed_update_is_bridged() {
+        let board = parse_placement("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w").unwrap();
+        let plies = find_plies(&Chess::default(), |after| after.board() == &board).unwrap();
+        let plies: Vec<_> = plies
+            .iter()
+            .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+            .collect();
+        assert_eq!(plies, ["e2e4", "e7e5"]);
+        assert!(parse_placement("1. e4 e5").is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use specta::Type;
+use tauri_specta::Event;
+
+use crate::error::Error;
+use crate::AppState;
+
+use super::{running_session, TlcsMoveRecordedEvent};
+
+/// Emitted for every move recorded on a board an analysis tab follows, for
+/// that tab to put at `ply` of its mainline. Nothing the user analyzed there
+/// is overwritten: a move differing from the tab's mainline, after a takeback
+/// or the user playing ahead, becomes the mainline and the moves it displaces
+/// stay as variations.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsTabMoveEvent {
+    pub tab_id: String,
+    pub recorded: TlcsMoveRecordedEvent,
+}
+
+/// The game a tab starts following, to set its mainline to before the moves
+/// of `TlcsTabMoveEvent` arrive.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsFollowedGame {
+    pub start_fen: String,
+    /// SAN of the plies recorded so far.
+    pub moves: Vec<String>,
+    pub result: Option<String>,
+}
+
+/// The analysis tabs following the boards of a session.
+#[derive(Default)]
+pub(super) struct TlcsFollowers {
+    tabs: Mutex<BTreeMap<String, Option<u32>>>,
+}
+
+impl TlcsFollowers {
+    /// Makes `tab_id` follow `board`, instead of any board it followed.
+    fn follow(&self, tab_id: String, board: Option<u32>) {
+        if let Ok(mut tabs) = self.tabs.lock() {
+            tabs.insert(tab_id, board);
+        }
+    }
+
+    fn unfollow(&self, tab_id: &str) -> bool {
+        self.tabs
+            .lock()
+            .is_ok_and(|mut tabs| tabs.remove(tab_id).is_some())
+    }
+
+    /// The tabs following `board`.
+    pub(super) fn tabs(&self, board: Option<u32>) -> Vec<String> {
+        self.tabs
+            .lock()
+            .map(|tabs| {
+                tabs.iter()
+                    .filter(|(_, followed)| **followed == board)
+                    .map(|(tab_id, _)| tab_id.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Binds the analysis tab `tab_id` to `board` (the default board when
+/// `None`) of the running session, so the moves recorded there keep being
+/// added to its mainline while the user analyzes ahead in side lines.
+#[tauri::command]
+#[specta::specta]
+pub async fn follow_tlcs_in_board(
+    session: Option<String>,
+    tab_id: String,
+    board: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsFollowedGame, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = running_session(&guard, session.as_deref()).await?;
+    // Registered while the recorder is locked, so no move falls between the
+    // game returned and the first event.
+    let recorder = handle.recorder.read().await;
+    let board_recorder = recorder.recorder(board).ok_or(Error::TlcsNotRecording)?;
+    handle.followers.follow(tab_id, board);
+    Ok(TlcsFollowedGame {
+        start_fen: board_recorder.start_fen.clone(),
+        moves: board_recorder.sans.clone(),
+        result: board_recorder.result.clone(),
+    })
+}
+
+/// Stops adding recorded moves to `tab_id`. Returns `false` when it followed
+/// no board.
+#[tauri::command]
+#[specta::specta]
+pub async fn unfollow_tlcs_in_board(
+    tab_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, Error> {
+    let guard = state.tlcs_handle.read().await;
+    Ok(guard
+        .as_ref()
+        .is_some_and(|handle| handle.followers.unfollow(&tab_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_follow_one_board_each() {
+        let followers = TlcsFollowers::default();
+        followers.follow("analysis-1".into(), None);
+        followers.follow("analysis-2".into(), Some(3));
+        followers.follow("analysis-3".into(), Some(3));
+        followers.follow("analysis-1".into(), Some(3));
+        assert_eq!(
+            followers.tabs(Some(3)),
+            ["analysis-1", "analysis-2", "analysis-3"]
+        );
+        assert!(followers.tabs(None).is_empty());
+        assert!(followers.unfollow("analysis-2"));
+        assert!(!followers.unfollow("analysis-2"));
+        assert_eq!(followers.tabs(Some(3)), ["analysis-1", "analysis-3"]);
+    }
+}
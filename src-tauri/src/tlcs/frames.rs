@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use shakmaty::Board;
+use tokio::io::AsyncBufRead;
+use tokio::select;
+use tokio::time::Instant;
+
+use super::fen_diff::parse_placement;
+use super::{split_board_prefix, DecodedLines};
+
+/// A bare FEN line waiting to hold, with the other lines of its board that
+/// came after it.
+struct PendingFrame {
+    line: String,
+    placement: Board,
+    since: Instant,
+    held: Vec<String>,
+}
+
+/// Holds the bare FEN lines of each board until the placement stayed the
+/// same for `settle`. Boards that report every square change send frames
+/// with a piece in hand or half way through a move; those are replaced by
+/// the next frame before they settle and never reach the recorder.
+struct FrameFilter {
+    settle: Duration,
+    pending: BTreeMap<Option<u32>, PendingFrame>,
+    ready: VecDeque<String>,
+}
+
+impl FrameFilter {
+    fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            pending: BTreeMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, line: String, now: Instant) {
+        let (board, payload) = split_board_prefix(&line);
+        let Some(placement) = parse_placement(payload) else {
+            match self.pending.get_mut(&board) {
+                // Kept behind the frame, so a result never overtakes the
+                // move that ended the game.
+                Some(pending) => pending.held.push(line),
+                None => self.ready.push_back(line),
+            }
+            return;
+        };
+        if let Some(pending) = self.pending.get(&board) {
+            // Repeats of a settling frame do not restart its wait.
+            if pending.placement == placement {
+                return;
+            }
+        }
+        let frame = PendingFrame {
+            line,
+            placement,
+            since: now,
+            held: Vec::new(),
+        };
+        if let Some(replaced) = self.pending.insert(board, frame) {
+            self.ready.extend(replaced.held);
+        }
+    }
+
+    /// Releases the frames that held for `settle` by `now`.
+    fn release_settled(&mut self, now: Instant) {
+        let settled: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| now >= pending.since + self.settle)
+            .map(|(board, _)| *board)
+            .collect();
+        for board in settled {
+            if let Some(pending) = self.pending.remove(&board) {
+                self.ready.push_back(pending.line);
+                self.ready.extend(pending.held);
+            }
+        }
+    }
+
+    /// Releases every frame, when the stream ended.
+    fn release_all(&mut self) {
+        while let Some((_, pending)) = self.pending.pop_first() {
+            self.ready.push_back(pending.line);
+            self.ready.extend(pending.held);
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|pending| pending.since + self.settle)
+            .min()
+    }
+}
+
+/// The lines of a recording stream, with the bare FEN lines filtered when
+/// `fen_settle_ms` is set.
+pub(super) struct SettledLines<R> {
+    lines: DecodedLines<R>,
+    filter: Option<FrameFilter>,
+    ended: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> SettledLines<R> {
+    pub(super) fn new(lines: DecodedLines<R>, settle: Option<Duration>) -> Self {
+        Self {
+            lines,
+            filter: settle.map(FrameFilter::new),
+            ended: false,
+        }
+    }
+
+    /// Like `DecodedLines::next_line`, and cancel safe as well.
+    pub(super) async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        let Some(filter) = self.filter.as_mut() else {
+            return self.lines.next_line().await;
+        };
+        loop {
+            filter.release_settled(Instant::now());
+            if let Some(line) = filter.ready.pop_front() {
+                return Ok(Some(line));
+            }
+            if self.ended {
+                return Ok(None);
+            }
+            let deadline = filter.deadline();
+            let settled = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now));
+            select! {
+                line = self.lines.next_line() => match line? {
+                    Some(line) => filter.push(line, Instant::now()),
+                    None => {
+                        self.ended = true;
+                        filter.release_all();
+                    }
+                },
+                _ = settled, if deadline.is_some() => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+    /// The e2 pawn lifted.
+    const LIFTED: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPP1PPP/RNBQKBNR";
+    const E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR";
+
+    #[test]
+    fn only_settled_frames_are_released() {
+        let settle = Duration::from_millis(300);
+        let mut filter = FrameFilter::new(settle);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        filter.push(format!("board 1: {START}"), at(0));
+        filter.push(format!("board 1: {LIFTED}"), at(100));
+        filter.push("board 1: clock w=5400000 b=5400000".into(), at(150));
+        filter.push(format!("board 2: {START}"), at(200));
+        filter.push(format!("board 1: {E4}"), at(250));
+        filter.release_settled(at(400));
+        assert_eq!(
+            filter.ready.drain(..).collect::<Vec<_>>(),
+            ["board 1: clock w=5400000 b=5400000"]
+        );
+
+        filter.push(format!("board 1: {E4} b KQkq - 0 1"), at(500));
+        filter.push("board 1: status 1-0".into(), at(520));
+        assert_eq!(filter.deadline(), Some(at(500)));
+        filter.release_settled(at(550));
+        assert_eq!(
+            filter.ready.drain(..).collect::<Vec<_>>(),
+            [
+                format!("board 1: {E4}"),
+                "board 1: status 1-0".into(),
+                format!("board 2: {START}"),
+            ]
+        );
+        assert_eq!(filter.deadline(), None);
+    }
+}
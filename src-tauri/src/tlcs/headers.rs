@@ -0,0 +1,109 @@
+/// Headers every PGN game has, in the order the standard writes them.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// The tag pairs of a recorded game. The Seven Tag Roster is written first,
+/// then the other tags in the order they were added, so the output is the
+/// same on every run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PgnHeaders {
+    tags: Vec<(String, String)>,
+}
+
+impl PgnHeaders {
+    pub(crate) fn get(&self, key: &str) -> Option<&String> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets a tag, keeping its place when it is already present. Returns the
+    /// previous value.
+    pub(crate) fn insert(&mut self, key: String, value: String) -> Option<String> {
+        match self.tags.iter_mut().find(|(tag, _)| *tag == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.tags.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.tags.iter().position(|(tag, _)| tag == key)?;
+        Some(self.tags.remove(index).1)
+    }
+
+    /// The tags in the order they are written.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let roster = SEVEN_TAG_ROSTER
+            .iter()
+            .filter_map(|key| self.get(key).map(|value| (*key, value.as_str())));
+        let others = self
+            .tags
+            .iter()
+            .filter(|(tag, _)| !SEVEN_TAG_ROSTER.contains(&tag.as_str()))
+            .map(|(tag, value)| (tag.as_str(), value.as_str()));
+        roster.chain(others)
+    }
+}
+
+impl FromIterator<(String, String)> for PgnHeaders {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(tags: I) -> Self {
+        let mut headers = Self::default();
+        for (key, value) in tags {
+            headers.insert(key, value);
+        }
+        headers
+    }
+}
+
+/// `value` with its backslashes and quotes escaped, as a tag pair holds it.
+pub(crate) fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reverses `escape_tag_value`.
+pub(crate) fn unescape_tag_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roster_comes_first_then_insertion_order() {
+        let mut headers: PgnHeaders = [
+            ("TimeControl", "5400+30"),
+            ("Result", "*"),
+            ("White", "Carlsen"),
+            ("Event", "Olympiad"),
+            ("Board", "1"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        headers.insert("Result".into(), "1-0".into());
+
+        let keys: Vec<_> = headers.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["Event", "White", "Result", "TimeControl", "Board"]);
+        assert_eq!(headers.get("Result").map(String::as_str), Some("1-0"));
+        assert_eq!(headers.remove("Board"), Some("1".into()));
+    }
+
+    #[test]
+    fn tag_values_are_escaped() {
+        let value = r#"The "Open" C:\Club"#;
+        assert_eq!(escape_tag_value(value), r#"The \"Open\" C:\\Club"#);
+        assert_eq!(unescape_tag_value(&escape_tag_value(value)), value);
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use serde::{Deserialize, Deserializer};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_cli::CliExt;
+use tokio::sync::RwLock;
+
+use crate::error::Error;
+use crate::AppState;
+
+use super::logging::echo_to_app_log;
+use super::{start_session, TlcsConnectOptions};
+
+/// The file passed with `--headless`, e.g.
+///
+/// ```toml
+/// [[session]]
+/// host = "192.168.1.20"
+/// port = 16001
+/// event = "Club Championship"
+/// pgnPath = "/home/pi/round-3.pgn"
+///
+/// [session.broadcast]
+/// roundId = "abcd1234"
+/// token = "lip_..."
+///
+/// [[session]]
+/// host = "192.168.1.21"
+/// port = 16001
+/// event = "Club Championship, juniors"
+/// pgnPath = "/home/pi/round-3-juniors.pgn"
+/// ```
+///
+/// Every `[[session]]` is recorded to its own PGN, and a single `[session]`
+/// table is read as one session. Relays of several sections are recorded
+/// into one PGN with `session.merge` instead.
+#[derive(Deserialize)]
+pub struct TlcsHeadlessConfig {
+    /// Options of `start_tlcs_stream`, under the same names.
+    #[serde(deserialize_with = "one_or_more")]
+    pub session: Vec<TlcsConnectOptions>,
+}
+
+fn one_or_more<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<TlcsConnectOptions>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Sessions {
+        One(Box<TlcsConnectOptions>),
+        More(Vec<TlcsConnectOptions>),
+    }
+    Ok(match Sessions::deserialize(deserializer)? {
+        Sessions::One(session) => vec![*session],
+        Sessions::More(sessions) => sessions,
+    })
+}
+
+fn load_config(path: &Path) -> Result<TlcsHeadlessConfig, Error> {
+    let config: TlcsHeadlessConfig = toml::from_str(&std::fs::read_to_string(path)?)?;
+    if config.session.is_empty() {
+        return Err(Error::TlcsInvalidHeadlessConfig(
+            "no [[session]] to record".to_string(),
+        ));
+    }
+    // Sessions without a path would be named after the same second.
+    if config.session.len() > 1
+        && config
+            .session
+            .iter()
+            .any(|session| session.pgn_path.is_none())
+    {
+        return Err(Error::TlcsInvalidHeadlessConfig(
+            "every [[session]] needs its own pgnPath".to_string(),
+        ));
+    }
+    Ok(config)
+}
+
+/// The config file given with `--headless`, if any.
+pub fn headless_config_path(app: &AppHandle) -> Option<PathBuf> {
+    let matches = app.cli().matches().ok()?;
+    matches
+        .args
+        .get("headless")?
+        .value
+        .as_str()
+        .map(PathBuf::from)
+}
+
+/// Records the sessions of the config at `path` without the UI, e.g. on a
+/// Raspberry Pi at the venue. The main window is never created, the session
+/// logs are echoed to the application log on stdout, and Ctrl+C exits,
+/// finalizing the PGNs like closing the app does. The windowing toolkit still
+/// needs a display, so machines without a screen run it under `xvfb-run`.
+pub fn start_headless(app: &AppHandle, path: &Path) -> Result<(), Error> {
+    let config = load_config(path)?;
+    log::info!("Running headless from {}", path.to_string_lossy());
+    app.state::<AppState>()
+        .headless
+        .store(true, Ordering::Relaxed);
+    echo_to_app_log();
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        for (index, options) in config.session.into_iter().enumerate() {
+            // The first session is the one the commands reach, as in the app.
+            let own_slot = RwLock::new(None);
+            let slot = if index == 0 {
+                &*state.tlcs_handle
+            } else {
+                &own_slot
+            };
+            match start_session(options, app.clone(), slot).await {
+                Ok(started) => log::info!(
+                    "Recording session {} to {}",
+                    started.session_id,
+                    started.pgn_path
+                ),
+                Err(err) => {
+                    log::error!("Failed to start the headless session: {err}");
+                    app.exit(1);
+                    return;
+                }
+            }
+            if let Some(handle) = own_slot.into_inner() {
+                state.tlcs_headless_handles.write().await.push(handle);
+            }
+        }
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            log::error!("Cannot wait for Ctrl+C, the sessions run until killed: {err}");
+            return;
+        }
+        log::info!("Stopping the headless sessions");
+        app.exit(0);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_are_read_from_toml() {
+        let config: TlcsHeadlessConfig = toml::from_str(
+            r#"
+            [session]
+            host = "192.168.1.20"
+            port = 16001
+            pgnPath = "round-3.pgn"
+            tournament = true
+
+            [[session.merge]]
+            host = "10.0.0.2"
+            port = 16001
+            boardOffset = 20
+            "#,
+        )
+        .unwrap();
+        let [session] = &config.session[..] else {
+            panic!("expected one session");
+        };
+        assert_eq!(session.port, 16001);
+        assert_eq!(session.pgn_path.as_deref(), Some("round-3.pgn"));
+        assert!(session.tournament);
+        assert_eq!(session.merge[0].board_offset, 20);
+    }
+
+    #[test]
+    fn session_arrays_are_read_from_toml() {
+        let config: TlcsHeadlessConfig = toml::from_str(
+            r#"
+            [[session]]
+            host = "192.168.1.20"
+            port = 16001
+            pgnPath = "open.pgn"
+
+            [session.broadcast]
+            roundId = "abcd1234"
+            token = "lip_secret"
+
+            [[session]]
+            host = "192.168.1.21"
+            port = 16002
+            pgnPath = "juniors.pgn"
+            "#,
+        )
+        .unwrap();
+        let ports: Vec<_> = config.session.iter().map(|session| session.port).collect();
+        assert_eq!(ports, [16001, 16002]);
+        assert!(config.session[0].broadcast.is_some());
+        assert_eq!(config.session[1].pgn_path.as_deref(), Some("juniors.pgn"));
+    }
+}
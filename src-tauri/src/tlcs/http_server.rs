@@ -0,0 +1,197 @@
+use std::net::{SocketAddr, TcpListener};
+
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use tauri::Manager;
+use tokio::sync::watch;
+
+use crate::{error::Error, AppState};
+
+use super::metrics::{exposition, TlcsSessionMetrics};
+use super::TlcsDemux;
+
+const PGN_CONTENT_TYPE: &str = "application/x-chess-pgn";
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Embedded HTTP server that lets external viewers poll the games being
+/// recorded without sharing the PGN files.
+pub struct TlcsHttpServer {
+    address: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsHttpServer {
+    async fn stop(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.task.await;
+    }
+}
+
+fn text_response(content_type: &'static str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Runs `f` against the active recording session, or answers 404 when
+/// nothing is being recorded.
+async fn with_session<F>(app: &tauri::AppHandle, f: F) -> Response
+where
+    F: FnOnce(&TlcsDemux) -> Option<Response>,
+{
+    let state = app.state::<AppState>();
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return (StatusCode::NOT_FOUND, "No active TLCS recording").into_response();
+    };
+    let recorder = handle.recorder.read().await;
+    f(&recorder).unwrap_or_else(|| (StatusCode::NOT_FOUND, "Unknown board").into_response())
+}
+
+async fn games(app: Extension<tauri::AppHandle>) -> Response {
+    with_session(&app, |recorder| {
+        Some(text_response(PGN_CONTENT_TYPE, recorder.live_pgn()))
+    })
+    .await
+}
+
+async fn boards(app: Extension<tauri::AppHandle>) -> Response {
+    with_session(&app, |recorder| {
+        Some(Json(recorder.board_statuses()).into_response())
+    })
+    .await
+}
+
+async fn board_pgn(app: &tauri::AppHandle, board: Option<u32>) -> Response {
+    with_session(app, |recorder| {
+        let recorder = recorder.recorder(board)?;
+        Some(text_response(PGN_CONTENT_TYPE, recorder.render()))
+    })
+    .await
+}
+
+async fn board_fen(app: &tauri::AppHandle, board: Option<u32>) -> Response {
+    with_session(app, |recorder| {
+        let recorder = recorder.recorder(board)?;
+        Some(text_response(TEXT_CONTENT_TYPE, recorder.fen()))
+    })
+    .await
+}
+
+async fn default_pgn(app: Extension<tauri::AppHandle>) -> Response {
+    board_pgn(&app, None).await
+}
+
+async fn default_fen(app: Extension<tauri::AppHandle>) -> Response {
+    board_fen(&app, None).await
+}
+
+async fn numbered_pgn(app: Extension<tauri::AppHandle>, Path(board): Path<u32>) -> Response {
+    board_pgn(&app, Some(board)).await
+}
+
+async fn numbered_fen(app: Extension<tauri::AppHandle>, Path(board): Path<u32>) -> Response {
+    board_fen(&app, Some(board)).await
+}
+
+/// Connection and recording counters for Prometheus and similar monitors.
+/// Answers even when nothing is connected, so a dead relay shows up as down
+/// rather than as a failed scrape.
+async fn metrics(app: Extension<tauri::AppHandle>) -> Response {
+    let state = app.state::<AppState>();
+    let connection = state.tlcs.metrics().await;
+    let session = match state.tlcs_handle.read().await.as_ref() {
+        Some(handle) => Some(TlcsSessionMetrics {
+            session: handle.log.session().unwrap_or_default().to_string(),
+            moves_recorded: handle
+                .recorder
+                .read()
+                .await
+                .board_statuses()
+                .iter()
+                .map(|board| board.moves_recorded)
+                .sum(),
+            log_errors: handle.log.errors(),
+        }),
+        None => None,
+    };
+    text_response(
+        METRICS_CONTENT_TYPE,
+        exposition(connection.as_ref(), session.as_ref()),
+    )
+}
+
+fn router(app: tauri::AppHandle) -> Router {
+    Router::new()
+        .route("/games.pgn", get(games))
+        .route("/boards", get(boards))
+        .route("/pgn", get(default_pgn))
+        .route("/fen", get(default_fen))
+        .route("/boards/:board/pgn", get(numbered_pgn))
+        .route("/boards/:board/fen", get(numbered_fen))
+        .route("/metrics", get(metrics))
+        .layer(Extension(app))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_tlcs_http_server(
+    port: u16,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, Error> {
+    let mut guard = state.tlcs_http_server.write().await;
+    if let Some(server) = guard.take() {
+        server.stop().await;
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+    let server = axum::Server::from_tcp(listener).map_err(std::io::Error::other)?;
+
+    let (shutdown, mut shutdown_rx) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        let _ = server
+            .serve(router(app).into_make_service())
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_rx.changed().await;
+            })
+            .await;
+    });
+
+    log::info!("Serving live TLCS games on http://{address}");
+    *guard = Some(TlcsHttpServer {
+        address,
+        shutdown,
+        task,
+    });
+    Ok(address.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_tlcs_http_server(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<String>, Error> {
+    let mut guard = state.tlcs_http_server.write().await;
+    if let Some(server) = guard.take() {
+        let address = server.address.to_string();
+        server.stop().await;
+        return Ok(Some(address));
+    }
+    Ok(None)
+}
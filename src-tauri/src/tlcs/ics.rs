@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::select;
+use tokio::sync::watch;
+
+use super::{connect_tcp, RotatingLog};
+
+const ICS_BUFFER_BYTES: usize = 64 * 1024;
+const ICS_PROMPT: &str = "fics% ";
+
+/// Account and games for an ICS relay.
+#[derive(Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsIcsOptions {
+    /// Logs in as a guest when unset.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Game numbers to `observe`. With more than one, each game is recorded
+    /// as its own board.
+    pub games: Vec<u32>,
+}
+
+impl std::fmt::Debug for TlcsIcsOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlcsIcsOptions")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("games", &self.games)
+            .finish()
+    }
+}
+
+/// The parts of a style 12 board update the recorder needs.
+#[derive(Debug, PartialEq, Eq)]
+struct Style12 {
+    game: u32,
+    fen: String,
+    white_ms: u64,
+    black_ms: u64,
+    /// The move that led to the position, in SAN.
+    last_move: Option<String>,
+}
+
+impl Style12 {
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<_> = line.strip_prefix("<12> ")?.split_whitespace().collect();
+        if fields.len() < 29 {
+            return None;
+        }
+
+        let placement = fields[..8]
+            .iter()
+            .map(|rank| {
+                let mut row = String::new();
+                let mut empty = 0;
+                for square in rank.chars() {
+                    if square == '-' {
+                        empty += 1;
+                        continue;
+                    }
+                    if empty > 0 {
+                        row.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    row.push(square);
+                }
+                if empty > 0 {
+                    row.push_str(&empty.to_string());
+                }
+                row
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let white_to_move = fields[8] == "W";
+        let castling: String = ["K", "Q", "k", "q"]
+            .iter()
+            .zip(&fields[10..14])
+            .filter(|(_, allowed)| **allowed == "1")
+            .map(|(right, _)| *right)
+            .collect();
+        let en_passant = match fields[9].parse::<u8>() {
+            Ok(file) if file < 8 => {
+                let rank = if white_to_move { '6' } else { '3' };
+                format!("{}{rank}", (b'a' + file) as char)
+            }
+            _ => "-".to_string(),
+        };
+        let fen = format!(
+            "{placement} {} {} {en_passant} {} {}",
+            if white_to_move { 'w' } else { 'b' },
+            if castling.is_empty() {
+                "-"
+            } else {
+                castling.as_str()
+            },
+            fields[14],
+            fields[25],
+        );
+
+        let seconds = |field: &str| field.parse::<i64>().ok().map(|s| s.max(0) as u64 * 1000);
+        Some(Self {
+            game: fields[15].parse().ok()?,
+            fen,
+            white_ms: seconds(fields[23])?,
+            black_ms: seconds(fields[24])?,
+            last_move: Some(fields[28])
+                .filter(|mv| *mv != "none")
+                .map(str::to_string),
+        })
+    }
+
+    /// The TLCS lines for the update: the move, the clocks and the position
+    /// to check the recorded one against.
+    fn lines(&self, with_board: bool) -> Vec<String> {
+        let prefix = if with_board {
+            format!("board {}: ", self.game)
+        } else {
+            String::new()
+        };
+        let mut lines = Vec::new();
+        if let Some(mv) = &self.last_move {
+            lines.push(format!("{prefix}{mv}"));
+        }
+        lines.push(format!(
+            "{prefix}clock w={} b={}",
+            self.white_ms, self.black_ms
+        ));
+        lines.push(format!("{prefix}fen {}", self.fen));
+        lines
+    }
+}
+
+async fn send(writer: &mut OwnedWriteHalf, command: &str) -> std::io::Result<()> {
+    writer.write_all(format!("{command}\n").as_bytes()).await
+}
+
+/// Logs on to a FICS-style ICS server, observes `ics.games` and returns a
+/// pipe carrying their style 12 updates as TLCS lines. The pipe is closed
+/// when `shutdown` fires or the server disconnects.
+pub(super) async fn connect(
+    host: &str,
+    port: u16,
+    proxy_url: Option<&str>,
+    ics: TlcsIcsOptions,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<DuplexStream> {
+    let (mut server_reader, mut server_writer) =
+        connect_tcp(host, port, proxy_url).await?.into_split();
+
+    let (reader, mut writer) = tokio::io::duplex(ICS_BUFFER_BYTES);
+    tokio::spawn(async move {
+        let with_board = ics.games.len() > 1;
+        let mut buffer = vec![0; ICS_BUFFER_BYTES];
+        let mut pending = String::new();
+        let mut logged_in = false;
+        loop {
+            let read = select! {
+                _ = shutdown.changed() => break,
+                read = server_reader.read(&mut buffer) => read,
+            };
+            let len = match read {
+                Ok(0) => {
+                    log.info("ICS server closed the connection");
+                    break;
+                }
+                Ok(len) => len,
+                Err(err) => {
+                    log.error(&format!("ICS read error: {err}"));
+                    break;
+                }
+            };
+            pending.push_str(&String::from_utf8_lossy(&buffer[..len]));
+
+            while let Some(end) = pending.find('\n') {
+                let line: String = pending.drain(..=end).collect();
+                let line = line
+                    .trim_matches(['\r', '\n'])
+                    .trim_start_matches(ICS_PROMPT);
+                if line.contains("Invalid password") {
+                    log.error("ICS login failed: invalid password");
+                    let _ = writer.shutdown().await;
+                    return;
+                }
+                let Some(update) = Style12::parse(line) else {
+                    continue;
+                };
+                for line in update.lines(with_board) {
+                    if writer
+                        .write_all(format!("{line}\r\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+
+            if logged_in {
+                continue;
+            }
+            // Prompts are not terminated by a newline.
+            let reply = if pending.ends_with("login: ") {
+                Some(ics.username.clone().unwrap_or_else(|| "guest".into()))
+            } else if pending.ends_with("password: ") {
+                Some(ics.password.clone().unwrap_or_default())
+            } else if pending.contains("Press return to enter the server") {
+                Some(String::new())
+            } else {
+                None
+            };
+            if let Some(reply) = reply {
+                pending.clear();
+                if send(&mut server_writer, &reply).await.is_err() {
+                    break;
+                }
+            } else if pending.ends_with(ICS_PROMPT) {
+                logged_in = true;
+                log.info(&format!("Logged in to ICS, observing {:?}", ics.games));
+                let mut commands = vec!["set style 12".to_string()];
+                commands.extend(ics.games.iter().map(|game| format!("observe {game}")));
+                for command in commands {
+                    if send(&mut server_writer, &command).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style12_updates_are_translated() {
+        let line = "<12> rnbqkbnr pppppppp -------- -------- ----P--- -------- PPPP-PPP RNBQKBNR \
+                    B 4 1 1 1 1 0 7 Carlsen Nakamura 0 3 2 39 39 179 180 1 P/e2-e4 (0:01) e4 0 1 0";
+        let update = Style12::parse(line).unwrap();
+        assert_eq!(
+            update.lines(true),
+            [
+                "board 7: e4",
+                "board 7: clock w=179000 b=180000",
+                "board 7: fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            ]
+        );
+        assert_eq!(Style12::parse("fics% "), None);
+    }
+}
@@ -0,0 +1,263 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use shakmaty::fen::Fen;
+use specta::Type;
+use tauri_specta::Event;
+use tokio::select;
+use tokio::sync::mpsc;
+use vampirc_uci::{parse_one, uci::Score, UciMessage};
+
+use crate::chess::{parse_uci_attrs, EngineOption, EngineOptions, EngineProcess, GoMode};
+use crate::error::Error;
+use crate::AppState;
+
+use super::{RotatingLog, TlcsEmitter};
+
+pub const DEFAULT_KIBITZER_DEPTH: u32 = 24;
+const MAX_KIBITZER_LINES: u16 = 5;
+
+/// One of the engine's candidate lines for a live position.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsKibitzLine {
+    pub multipv: u16,
+    pub score: Score,
+    pub san: Vec<String>,
+}
+
+/// The kibitzer's top lines for a live position, emitted after every
+/// completed depth.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsKibitzEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub fen: String,
+    pub depth: u32,
+    pub lines: Vec<TlcsKibitzLine>,
+}
+
+struct KibitzRequest {
+    board: Option<u32>,
+    fen: String,
+    moves: Vec<String>,
+}
+
+/// Background UCI engine that searches every new live position in multi-PV
+/// mode. Unlike live analysis it never writes to the PGN; it only reports
+/// its lines to the frontend.
+pub struct TlcsKibitzer {
+    requests: mpsc::UnboundedSender<KibitzRequest>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsKibitzer {
+    fn spawn(
+        engine: PathBuf,
+        multipv: u16,
+        depth: u32,
+        events: TlcsEmitter,
+        log: RotatingLog,
+    ) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_kibitzer(engine, multipv, depth, events, log, rx));
+        Self { requests, task }
+    }
+
+    pub fn analyze(&self, board: Option<u32>, fen: String, moves: Vec<String>) {
+        let _ = self.requests.send(KibitzRequest { board, fen, moves });
+    }
+
+    pub async fn stop(self) {
+        drop(self.requests);
+        let _ = self.task.await;
+    }
+}
+
+async fn run_kibitzer(
+    engine: PathBuf,
+    multipv: u16,
+    depth: u32,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    mut rx: mpsc::UnboundedReceiver<KibitzRequest>,
+) {
+    let (mut proc, mut reader) = match EngineProcess::new(engine.clone()).await {
+        Ok(engine) => engine,
+        Err(err) => {
+            log.error(&format!(
+                "Unable to start kibitzer engine {}: {err}",
+                engine.to_string_lossy()
+            ));
+            return;
+        }
+    };
+    log.info(&format!(
+        "Kibitzer engine {} started with {multipv} lines",
+        engine.to_string_lossy()
+    ));
+
+    let mut pending: Option<KibitzRequest> = None;
+
+    loop {
+        let mut request = match pending.take() {
+            Some(request) => request,
+            None => match rx.recv().await {
+                Some(request) => request,
+                None => break,
+            },
+        };
+        while let Ok(next) = rx.try_recv() {
+            request = next;
+        }
+
+        let fen: Fen = match request.fen.parse() {
+            Ok(fen) => fen,
+            Err(_) => continue,
+        };
+        let options = EngineOptions {
+            fen: request.fen.clone(),
+            moves: request.moves.clone(),
+            extra_options: vec![EngineOption {
+                name: "MultiPV".to_string(),
+                value: multipv.to_string(),
+            }],
+        };
+        if let Err(err) = proc.set_options(options).await {
+            log.error(&format!("Kibitzer rejected position: {err}"));
+            continue;
+        }
+        if let Err(err) = proc.go(&GoMode::Depth(depth)).await {
+            log.error(&format!("Kibitzer engine failed: {err}"));
+            break;
+        }
+
+        let ply = request.moves.len();
+        let mut lines: Vec<TlcsKibitzLine> = Vec::new();
+        let mut line_depth = 0;
+        let mut emitted = true;
+        let mut superseded = false;
+        let mut closed = false;
+
+        let emit = |lines: &[TlcsKibitzLine], depth: u32| {
+            events.emit(
+                "tlcs-kibitz",
+                TlcsKibitzEvent {
+                    board: request.board,
+                    ply,
+                    fen: request.fen.clone(),
+                    depth,
+                    lines: lines.to_vec(),
+                },
+            );
+        };
+
+        loop {
+            select! {
+                next = rx.recv(), if !closed => {
+                    match next {
+                        Some(next) => pending = Some(next),
+                        None => closed = true,
+                    }
+                    if !superseded {
+                        superseded = true;
+                        let _ = proc.stop().await;
+                    }
+                }
+                line = reader.next_line() => {
+                    let Ok(Some(line)) = line else {
+                        log.error("Kibitzer engine exited");
+                        return;
+                    };
+                    match parse_one(&line) {
+                        UciMessage::Info(attrs) => {
+                            let Ok(best) = parse_uci_attrs(attrs, &fen, &request.moves) else {
+                                continue;
+                            };
+                            if best.depth != line_depth {
+                                line_depth = best.depth;
+                                lines.clear();
+                            }
+                            lines.retain(|line| line.multipv != best.multipv);
+                            lines.push(TlcsKibitzLine {
+                                multipv: best.multipv,
+                                score: best.score,
+                                san: best.san_moves,
+                            });
+                            lines.sort_by_key(|line| line.multipv);
+                            emitted = false;
+                            if !superseded && lines.len() == multipv as usize {
+                                emit(&lines, line_depth);
+                                emitted = true;
+                            }
+                        }
+                        UciMessage::BestMove { .. } => break,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if closed {
+            break;
+        }
+        // Positions with fewer legal moves than requested lines never fill
+        // every slot, so report what the final depth produced.
+        if !superseded && !emitted && !lines.is_empty() {
+            emit(&lines, line_depth);
+        }
+    }
+
+    let _ = proc.kill().await;
+}
+
+/// Starts a kibitzer on the running TLCS session, replacing any previous one.
+/// The current position of the default board is searched right away.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_tlcs_kibitzer(
+    engine_path: String,
+    multipv: u16,
+    depth_limit: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+    // Read the position before locking the kibitzer slot; the stream task
+    // takes the two locks in the opposite order.
+    let options = handle.recorder.read().await.default.analysis_options();
+
+    let mut kibitzer = handle.kibitzer.write().await;
+    if let Some(previous) = kibitzer.take() {
+        previous.stop().await;
+    }
+
+    handle
+        .log
+        .info(&format!("Starting kibitzer with {engine_path}"));
+    let started = TlcsKibitzer::spawn(
+        PathBuf::from(engine_path),
+        multipv.clamp(1, MAX_KIBITZER_LINES),
+        depth_limit.unwrap_or(DEFAULT_KIBITZER_DEPTH),
+        handle.events.clone(),
+        handle.log.clone(),
+    );
+    started.analyze(None, options.fen, options.moves);
+    *kibitzer = Some(started);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_tlcs_kibitzer(state: tauri::State<'_, AppState>) -> Result<(), Error> {
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return Ok(());
+    };
+    if let Some(kibitzer) = handle.kibitzer.write().await.take() {
+        handle.log.info("Stopping kibitzer");
+        kibitzer.stop().await;
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio::select;
+use tokio::sync::watch;
+
+use super::RotatingLog;
+
+const LICHESS_API_URL: &str = "https://lichess.org/api";
+const LICHESS_BUFFER_BYTES: usize = 64 * 1024;
+
+/// The Lichess game or TV channel to record.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsLichessOptions {
+    /// Game to follow. When unset, the TV channel is followed instead.
+    pub game_id: Option<String>,
+    /// TV channel such as `blitz` or `rapid`. The featured game when unset.
+    pub channel: Option<String>,
+}
+
+impl TlcsLichessOptions {
+    fn url(&self) -> String {
+        match (&self.game_id, &self.channel) {
+            (Some(game_id), _) => format!("{LICHESS_API_URL}/stream/game/{game_id}"),
+            (None, Some(channel)) => format!("{LICHESS_API_URL}/tv/{channel}/feed"),
+            (None, None) => format!("{LICHESS_API_URL}/tv/feed"),
+        }
+    }
+}
+
+/// Completes a FEN of piece placement and side to move only. Castling rights
+/// are assumed wherever king and rook still stand on their initial squares.
+fn complete_fen(fen: &str) -> Option<String> {
+    let mut fields = fen.split_whitespace();
+    let placement = fields.next()?;
+    let turn = fields.next().unwrap_or("w");
+    let ranks: Vec<String> = placement
+        .split('/')
+        .map(|rank| {
+            rank.chars()
+                .flat_map(|square| match square.to_digit(10) {
+                    Some(empty) => vec!['1'; empty as usize],
+                    None => vec![square],
+                })
+                .collect()
+        })
+        .collect();
+    if ranks.len() != 8 {
+        return None;
+    }
+    let square = |rank: usize, file: usize| ranks[rank].chars().nth(file);
+    let mut castling = String::new();
+    for (rank, king, rook, rights) in [(7, 'K', 'R', ['K', 'Q']), (0, 'k', 'r', ['k', 'q'])] {
+        if square(rank, 4) == Some(king) {
+            if square(rank, 7) == Some(rook) {
+                castling.push(rights[0]);
+            }
+            if square(rank, 0) == Some(rook) {
+                castling.push(rights[1]);
+            }
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+    Some(format!("{placement} {turn} {castling} - 0 1"))
+}
+
+/// The TLCS lines for one object of a Lichess stream. Game streams send the
+/// game first and then `{fen, lm, wc, bc}` per move; TV feeds wrap the same
+/// in `{t: "featured" | "fen", d}`, with a new `featured` object whenever the
+/// channel switches games. The position of a new game is passed on as a
+/// `fen` line, which restarts the recording.
+fn translate(object: &Value) -> Vec<String> {
+    let data = match object["t"].as_str() {
+        Some("featured" | "fen") => &object["d"],
+        Some(_) => return Vec::new(),
+        None => object,
+    };
+    let new_game = object["t"] == "featured" || data["id"].is_string();
+
+    let mut lines = Vec::new();
+    if let Some(mv) = data["lm"].as_str() {
+        lines.push(mv.to_string());
+    }
+    if let (Some(white), Some(black)) = (data["wc"].as_u64(), data["bc"].as_u64()) {
+        lines.push(format!("clock w={} b={}", white * 1000, black * 1000));
+    }
+    // TV feeds send the piece placement and side to move only. That is
+    // enough to start a game from, but not to check a recorded position
+    // against, so the FENs of later moves are only used when complete.
+    let fen = data["fen"].as_str().and_then(|fen| {
+        if fen.split_whitespace().count() >= 4 {
+            Some(fen.to_string())
+        } else if new_game {
+            complete_fen(fen)
+        } else {
+            None
+        }
+    });
+    if let Some(fen) = fen {
+        lines.push(format!("fen {fen}"));
+    }
+    lines
+}
+
+/// Opens the NDJSON stream of a Lichess game or TV channel and returns a pipe
+/// carrying it as TLCS lines. The pipe is closed when `shutdown` fires or the
+/// stream ends, as it does when a followed game finishes.
+pub(super) async fn connect(
+    lichess: TlcsLichessOptions,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<DuplexStream> {
+    let url = lichess.url();
+    let response = Client::new()
+        .get(&url)
+        .header("Accept", "application/x-ndjson")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(std::io::Error::other)?;
+    log.info(&format!("Streaming {url}"));
+
+    let (reader, mut writer) = tokio::io::duplex(LICHESS_BUFFER_BYTES);
+    tokio::spawn(async move {
+        let mut body = response.bytes_stream();
+        let mut pending = Vec::new();
+        loop {
+            let chunk = select! {
+                _ = shutdown.changed() => break,
+                chunk = body.next() => chunk,
+            };
+            let chunk = match chunk {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(err)) => {
+                    log.error(&format!("Lichess stream error: {err}"));
+                    break;
+                }
+                None => {
+                    log.info("Lichess stream ended");
+                    break;
+                }
+            };
+            pending.extend_from_slice(&chunk);
+
+            while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                // Empty lines are keep-alives.
+                let Ok(object) = serde_json::from_slice::<Value>(&line) else {
+                    continue;
+                };
+                for line in translate(&object) {
+                    if writer
+                        .write_all(format!("{line}\r\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_objects_are_translated() {
+        let mv = serde_json::json!({
+            "fen": "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "lm": "e2e4",
+            "wc": 179,
+            "bc": 180,
+        });
+        assert_eq!(
+            translate(&mv),
+            [
+                "e2e4",
+                "clock w=179000 b=180000",
+                "fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            ]
+        );
+
+        let tv = serde_json::json!({
+            "t": "fen",
+            "d": { "fen": "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b", "lm": "e2e4" },
+        });
+        assert_eq!(translate(&tv), ["e2e4"]);
+
+        let featured = serde_json::json!({
+            "t": "featured",
+            "d": { "id": "abcd1234", "fen": "r3k2r/8/8/8/8/8/8/4K2R w" },
+        });
+        assert_eq!(
+            translate(&featured),
+            ["fen r3k2r/8/8/8/8/8/8/4K2R w Kkq - 0 1"]
+        );
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Color, Position};
+use specta::Type;
+use tauri_specta::Event;
+use tokio::select;
+use tokio::sync::{mpsc, RwLock};
+use vampirc_uci::{parse_one, uci::Score, uci::ScoreValue, UciMessage};
+
+use crate::chess::{parse_uci_attrs, BestMoves, EngineOptions, EngineProcess, GoMode};
+use crate::{error::Error, AppState};
+
+use super::{running_session, RotatingLog, TlcsDemux, TlcsEmitter};
+
+pub const DEFAULT_LIVE_ANALYSIS_DEPTH: u32 = 18;
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsEvalEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub fen: String,
+    pub moves: Vec<String>,
+    pub depth: u32,
+    pub score: Score,
+    pub pv: Vec<String>,
+}
+
+/// Emitted when the evaluation swings against the side that moved by at
+/// least the session's blunder threshold.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBlunderEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub fen: String,
+    pub moves: Vec<String>,
+    /// The move played, in SAN.
+    pub played: String,
+    /// The engine's choice in the position before the move, in SAN.
+    pub best_move: Option<String>,
+    pub score_before: Score,
+    pub score_after: Score,
+    /// Centipawns lost by the side that moved.
+    pub loss: i32,
+}
+
+/// A white-relative evaluation of the position after `ply` plies.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsEvalPoint {
+    pub ply: usize,
+    pub cp: Option<i32>,
+    pub mate: Option<i32>,
+}
+
+/// The evaluation graph of one board.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsEvalSeries {
+    pub board: Option<u32>,
+    pub points: Vec<TlcsEvalPoint>,
+}
+
+type EvalHistory = Arc<Mutex<BTreeMap<Option<u32>, BTreeMap<usize, TlcsEvalPoint>>>>;
+
+/// Adds the evaluation of `ply`. Points after it belong to moves that were
+/// taken back, or to the previous game on the board, and are dropped.
+fn record_point(series: &mut BTreeMap<usize, TlcsEvalPoint>, ply: usize, score: &ScoreValue) {
+    series.split_off(&ply);
+    let (cp, mate) = match *score {
+        ScoreValue::Cp(cp) => (Some(cp), None),
+        ScoreValue::Mate(moves) => (None, Some(i32::from(moves))),
+    };
+    series.insert(ply, TlcsEvalPoint { ply, cp, mate });
+}
+
+/// How deep to search and what to do with each evaluation.
+#[derive(Clone, Copy)]
+pub struct LiveAnalysisSettings {
+    pub depth: u32,
+    /// Write `[%eval]` comments into the PGN.
+    pub annotate: bool,
+    /// Centipawn loss that raises a `TlcsBlunderEvent`.
+    pub blunder_threshold: Option<u32>,
+}
+
+/// Mate scores count as this many centipawns.
+const MATE_CP: i32 = 10_000;
+
+struct LiveAnalysisRequest {
+    board: Option<u32>,
+    fen: String,
+    moves: Vec<String>,
+}
+
+/// Background UCI engine that evaluates every new position of a recording
+/// session. Positions arriving faster than the engine can finish are coalesced,
+/// so only the latest one is searched.
+pub struct LiveAnalysis {
+    requests: mpsc::UnboundedSender<LiveAnalysisRequest>,
+    history: EvalHistory,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LiveAnalysis {
+    pub fn spawn(
+        engine: PathBuf,
+        settings: LiveAnalysisSettings,
+        recorder: Arc<RwLock<TlcsDemux>>,
+        events: TlcsEmitter,
+        log: RotatingLog,
+    ) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let history = EvalHistory::default();
+        let task = tokio::spawn(run_live_analysis(
+            engine,
+            settings,
+            recorder,
+            history.clone(),
+            events,
+            log,
+            rx,
+        ));
+        Self {
+            requests,
+            history,
+            task,
+        }
+    }
+
+    pub fn analyze(&self, board: Option<u32>, fen: String, moves: Vec<String>) {
+        let _ = self
+            .requests
+            .send(LiveAnalysisRequest { board, fen, moves });
+    }
+
+    /// The evaluations of every board analyzed so far.
+    pub fn history(&self) -> Vec<TlcsEvalSeries> {
+        let Ok(history) = self.history.lock() else {
+            return Vec::new();
+        };
+        history
+            .iter()
+            .map(|(board, points)| TlcsEvalSeries {
+                board: *board,
+                points: points.values().cloned().collect(),
+            })
+            .collect()
+    }
+
+    /// The last evaluation of `board`, if any position of it was analyzed.
+    pub fn latest(&self, board: Option<u32>) -> Option<TlcsEvalPoint> {
+        let history = self.history.lock().ok()?;
+        let (_, point) = history.get(&board)?.last_key_value()?;
+        Some(point.clone())
+    }
+
+    pub async fn stop(self) {
+        drop(self.requests);
+        let _ = self.task.await;
+    }
+}
+
+/// Formats a white-relative score as a `[%eval]` comment value.
+fn eval_comment(score: &Score) -> String {
+    match score.value {
+        ScoreValue::Cp(cp) => format!("[%eval {:.2}]", cp as f64 / 100.0),
+        ScoreValue::Mate(moves) => format!("[%eval #{moves}]"),
+    }
+}
+
+fn centipawns(score: &ScoreValue) -> i32 {
+    match *score {
+        ScoreValue::Cp(cp) => cp,
+        ScoreValue::Mate(moves) if moves > 0 => MATE_CP,
+        ScoreValue::Mate(_) => -MATE_CP,
+    }
+}
+
+/// Centipawns the side that moved lost between two white-relative scores.
+fn mover_loss(before: &ScoreValue, after: &ScoreValue, mover: Color) -> i32 {
+    let swing = centipawns(after) - centipawns(before);
+    match mover {
+        Color::White => -swing,
+        Color::Black => swing,
+    }
+}
+
+/// The side that played the last of `moves` and its SAN.
+fn last_move(fen: &Fen, moves: &[String]) -> Option<(Color, String)> {
+    let (last, earlier) = moves.split_last()?;
+    let mut position: Chess = fen.clone().into_position(CastlingMode::Chess960).ok()?;
+    for uci in earlier {
+        let mv = UciMove::from_ascii(uci.as_bytes())
+            .ok()?
+            .to_move(&position)
+            .ok()?;
+        position.play_unchecked(&mv);
+    }
+    let mv = UciMove::from_ascii(last.as_bytes())
+        .ok()?
+        .to_move(&position)
+        .ok()?;
+    let mover = position.turn();
+    Some((mover, SanPlus::from_move(position, &mv).to_string()))
+}
+
+/// The last completed evaluation of a board.
+struct Evaluated {
+    fen: String,
+    moves: Vec<String>,
+    score: Score,
+    best_move: Option<String>,
+}
+
+async fn run_live_analysis(
+    engine: PathBuf,
+    settings: LiveAnalysisSettings,
+    recorder: Arc<RwLock<TlcsDemux>>,
+    history: EvalHistory,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    mut rx: mpsc::UnboundedReceiver<LiveAnalysisRequest>,
+) {
+    let (mut proc, mut reader) = match EngineProcess::new(engine.clone()).await {
+        Ok(engine) => engine,
+        Err(err) => {
+            log.error(&format!(
+                "Unable to start live analysis engine {}: {err}",
+                engine.to_string_lossy()
+            ));
+            return;
+        }
+    };
+    log.info(&format!(
+        "Live analysis engine {} started",
+        engine.to_string_lossy()
+    ));
+
+    let mut pending: Option<LiveAnalysisRequest> = None;
+    let mut evaluated: HashMap<Option<u32>, Evaluated> = HashMap::new();
+
+    loop {
+        let mut request = match pending.take() {
+            Some(request) => request,
+            None => match rx.recv().await {
+                Some(request) => request,
+                None => break,
+            },
+        };
+        while let Ok(next) = rx.try_recv() {
+            request = next;
+        }
+
+        let fen: Fen = match request.fen.parse() {
+            Ok(fen) => fen,
+            Err(_) => continue,
+        };
+        let options = EngineOptions {
+            fen: request.fen.clone(),
+            moves: request.moves.clone(),
+            extra_options: Vec::new(),
+        };
+        if let Err(err) = proc.set_options(options).await {
+            log.error(&format!("Live analysis rejected position: {err}"));
+            continue;
+        }
+        if let Err(err) = proc.go(&GoMode::Depth(settings.depth)).await {
+            log.error(&format!("Live analysis engine failed: {err}"));
+            break;
+        }
+
+        let mut best: Option<BestMoves> = None;
+        let mut superseded = false;
+        let mut closed = false;
+
+        loop {
+            select! {
+                next = rx.recv(), if !closed => {
+                    match next {
+                        Some(next) => pending = Some(next),
+                        None => closed = true,
+                    }
+                    if !superseded {
+                        superseded = true;
+                        let _ = proc.stop().await;
+                    }
+                }
+                line = reader.next_line() => {
+                    let Ok(Some(line)) = line else {
+                        log.error("Live analysis engine exited");
+                        return;
+                    };
+                    match parse_one(&line) {
+                        UciMessage::Info(attrs) => {
+                            if let Ok(moves) = parse_uci_attrs(attrs, &fen, &request.moves) {
+                                if moves.multipv == 1 {
+                                    best = Some(moves);
+                                }
+                            }
+                        }
+                        UciMessage::BestMove { .. } => break,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if closed {
+            break;
+        }
+        if superseded {
+            continue;
+        }
+        let Some(best) = best else {
+            continue;
+        };
+
+        let ply = request.moves.len();
+        if settings.annotate {
+            let mut recorder = recorder.write().await;
+            if let Ok(board) = recorder.recorder_mut(request.board) {
+                if let Err(err) = board.annotate(ply, &eval_comment(&best.score)) {
+                    log.error(&format!("Failed to write eval comment: {err}"));
+                }
+            }
+        }
+
+        if let Ok(mut history) = history.lock() {
+            record_point(
+                history.entry(request.board).or_default(),
+                ply,
+                &best.score.value,
+            );
+        }
+
+        let previous = evaluated.insert(
+            request.board,
+            Evaluated {
+                fen: request.fen.clone(),
+                moves: request.moves.clone(),
+                score: best.score.clone(),
+                best_move: best.san_moves.first().cloned(),
+            },
+        );
+        // Only consecutive positions of the same game are compared, since
+        // coalesced requests skip plies.
+        let previous = previous.filter(|previous| {
+            previous.fen == request.fen
+                && ply > 0
+                && previous.moves.as_slice() == &request.moves[..ply - 1]
+        });
+        if let (Some(threshold), Some(previous)) = (settings.blunder_threshold, previous) {
+            if let Some((mover, played)) = last_move(&fen, &request.moves) {
+                let loss = mover_loss(&previous.score.value, &best.score.value, mover);
+                if loss >= threshold as i32 {
+                    log.info(&format!(
+                        "Possible blunder {played} at ply {ply}, losing {loss}cp"
+                    ));
+                    events.emit(
+                        "tlcs-blunder",
+                        TlcsBlunderEvent {
+                            board: request.board,
+                            ply,
+                            fen: request.fen.clone(),
+                            moves: request.moves.clone(),
+                            played,
+                            best_move: previous.best_move,
+                            score_before: previous.score,
+                            score_after: best.score.clone(),
+                            loss,
+                        },
+                    );
+                }
+            }
+        }
+
+        events.emit(
+            "tlcs-eval",
+            TlcsEvalEvent {
+                board: request.board,
+                ply,
+                fen: request.fen,
+                moves: request.moves,
+                depth: best.depth,
+                score: best.score,
+                pv: best.san_moves,
+            },
+        );
+    }
+
+    let _ = proc.kill().await;
+}
+
+/// The evaluation graph of every board of `session` (the running session
+/// when `None`) analyzed so far, for drawing it as the games go on. Empty
+/// when the session runs without live analysis.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_eval_history(
+    session: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TlcsEvalSeries>, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = running_session(&guard, session.as_deref()).await?;
+    Ok(handle
+        .analysis
+        .as_ref()
+        .map(|analysis| analysis.history())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takebacks_drop_later_points() {
+        let mut series = BTreeMap::new();
+        for (ply, cp) in [(1, 30), (2, 25), (3, -80)] {
+            record_point(&mut series, ply, &ScoreValue::Cp(cp));
+        }
+        record_point(&mut series, 2, &ScoreValue::Mate(-4));
+        let points: Vec<_> = series.into_values().collect();
+        assert_eq!(
+            points,
+            [
+                TlcsEvalPoint {
+                    ply: 1,
+                    cp: Some(30),
+                    mate: None
+                },
+                TlcsEvalPoint {
+                    ply: 2,
+                    cp: None,
+                    mate: Some(-4)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn losses_are_counted_for_the_side_that_moved() {
+        assert_eq!(
+            mover_loss(&ScoreValue::Cp(40), &ScoreValue::Cp(-260), Color::White),
+            300
+        );
+        assert_eq!(
+            mover_loss(&ScoreValue::Cp(40), &ScoreValue::Cp(-260), Color::Black),
+            -300
+        );
+        assert_eq!(
+            mover_loss(&ScoreValue::Cp(-50), &ScoreValue::Mate(3), Color::Black),
+            MATE_CP + 50
+        );
+
+        let fen: Fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let moves = ["e2e4".to_string(), "d8h4".to_string()];
+        assert_eq!(
+            last_move(&fen, &moves[..1]),
+            Some((Color::White, "e4".into()))
+        );
+        assert_eq!(last_move(&fen, &moves), None);
+    }
+}
@@ -0,0 +1,561 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{path::BaseDirectory, Manager};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::error::Error;
+
+const DEFAULT_ROTATION_BYTES: u64 = 512 * 1024;
+const DEFAULT_ROTATION_FILES: usize = 5;
+pub const LOG_FILE: &str = "tlcs.log";
+
+enum LogRequest {
+    Append(Arc<RotatingLogInner>, String),
+    Flush(oneshot::Sender<()>),
+}
+
+/// One writer thread per log file, shared by every `RotatingLog` writing to
+/// it, so that no stream task waits for the disk and no entry is appended to
+/// a file another session is rotating away.
+static FILE_WRITERS: Lazy<Mutex<HashMap<PathBuf, mpsc::UnboundedSender<LogRequest>>>> =
+    Lazy::new(Default::default);
+
+fn file_writer(path: &Path) -> mpsc::UnboundedSender<LogRequest> {
+    let mut writers = FILE_WRITERS.lock().unwrap_or_else(PoisonError::into_inner);
+    writers
+        .entry(path.to_path_buf())
+        .or_insert_with(|| {
+            let (requests, rx) = mpsc::unbounded_channel();
+            std::thread::spawn(move || run_log_writer(rx));
+            requests
+        })
+        .clone()
+}
+
+fn run_log_writer(mut rx: mpsc::UnboundedReceiver<LogRequest>) {
+    while let Some(request) = rx.blocking_recv() {
+        match request {
+            LogRequest::Append(log, line) => {
+                if log.append(&line).is_err() {
+                    log.write_failures.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            LogRequest::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Whether session logs are also written to the application log.
+static ECHO_TO_APP_LOG: AtomicBool = AtomicBool::new(false);
+
+/// Writes the entries of every session log to the application log too, for
+/// headless runs that are followed on stdout.
+pub(super) fn echo_to_app_log() {
+    ECHO_TO_APP_LOG.store(true, Ordering::Relaxed);
+}
+
+/// Rotation settings for the TLCS log. Unset values use the defaults.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsLogConfig {
+    /// Rotate the log once it grows past this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Number of rotated files to keep.
+    pub max_files: Option<usize>,
+    /// Gzip rotated files.
+    #[serde(default)]
+    pub compress: bool,
+    /// Patterns masked in every entry, on top of the credentials.
+    #[serde(default)]
+    pub redact: Vec<TlcsRedactRule>,
+}
+
+/// Masks credentials in TLCS protocol text (`USER <name> <password>`,
+/// `PASS <password>`) before it is logged or sent to the frontend.
+pub fn redact_credentials(message: &str) -> String {
+    let mut tokens: Vec<&str> = message.split(' ').collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        let secret = if tokens[i].eq_ignore_ascii_case("USER") {
+            i + 2
+        } else if tokens[i].eq_ignore_ascii_case("PASS")
+            || tokens[i].eq_ignore_ascii_case("PASSWORD")
+        {
+            i + 1
+        } else {
+            i += 1;
+            continue;
+        };
+        if let Some(token) = tokens.get_mut(secret) {
+            *token = "***";
+        }
+        i = secret + 1;
+    }
+    tokens.join(" ")
+}
+
+/// A regular expression whose matches are masked in logged lines and raw
+/// event payloads, e.g. `(?i)^tell \S+ .*` to hide private chat.
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsRedactRule {
+    pub pattern: String,
+    /// Replaces each match, `***` when unset. May refer to capture groups
+    /// like `$1`.
+    pub replacement: Option<String>,
+}
+
+/// Masks credentials, then the matches of the configured rules.
+#[derive(Clone, Default)]
+pub struct TlcsRedactor {
+    rules: Arc<Vec<(Regex, String)>>,
+}
+
+impl TlcsRedactor {
+    pub fn new(rules: &[TlcsRedactRule]) -> Result<Self, Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let replacement = rule.replacement.as_deref().unwrap_or("***").to_string();
+                Ok((Regex::new(&rule.pattern)?, replacement))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            rules: Arc::new(rules),
+        })
+    }
+
+    pub fn redact(&self, message: &str) -> String {
+        let mut redacted = redact_credentials(message);
+        for (pattern, replacement) in self.rules.iter() {
+            if let Cow::Owned(replaced) = pattern.replace_all(&redacted, replacement.as_str()) {
+                redacted = replaced;
+            }
+        }
+        redacted
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum TlcsLogLevel {
+    /// Raw protocol lines.
+    Trace,
+    Debug,
+    Info,
+    Error,
+}
+
+/// Whether an entry records protocol traffic, and which way it went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum TlcsLogDirection {
+    Rx,
+    Tx,
+}
+
+/// One line of the TLCS log, stored as JSON.
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsLogEntry {
+    pub timestamp: String,
+    pub level: TlcsLogLevel,
+    pub session: Option<String>,
+    pub direction: Option<TlcsLogDirection>,
+    pub payload: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsLogQuery {
+    /// Minimum level to return.
+    pub level: Option<TlcsLogLevel>,
+    /// RFC 3339 bounds of the time range, both inclusive.
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub session: Option<String>,
+    pub direction: Option<TlcsLogDirection>,
+    /// Only return the most recent `limit` matching entries.
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone)]
+pub struct RotatingLog {
+    inner: Arc<RotatingLogInner>,
+}
+
+struct RotatingLogInner {
+    path: PathBuf,
+    writer: mpsc::UnboundedSender<LogRequest>,
+    max_bytes: u64,
+    max_files: usize,
+    compress: bool,
+    session: Option<String>,
+    redactor: TlcsRedactor,
+    level: RwLock<TlcsLogLevel>,
+    errors: AtomicU64,
+    write_failures: AtomicU64,
+}
+
+impl RotatingLog {
+    pub fn new(
+        path: PathBuf,
+        config: &TlcsLogConfig,
+        session: Option<String>,
+    ) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        Ok(Self {
+            inner: Arc::new(RotatingLogInner {
+                writer: file_writer(&path),
+                path,
+                max_bytes: config.max_bytes.unwrap_or(DEFAULT_ROTATION_BYTES),
+                max_files: config.max_files.unwrap_or(DEFAULT_ROTATION_FILES).max(1),
+                compress: config.compress,
+                session,
+                redactor: TlcsRedactor::new(&config.redact)?,
+                level: RwLock::new(TlcsLogLevel::Trace),
+                errors: AtomicU64::new(0),
+                write_failures: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    pub fn info(&self, message: &str) {
+        self.write(TlcsLogLevel::Info, None, message);
+    }
+
+    pub fn debug(&self, message: &str) {
+        self.write(TlcsLogLevel::Debug, None, message);
+    }
+
+    pub fn error(&self, message: &str) {
+        self.inner.errors.fetch_add(1, Ordering::Relaxed);
+        self.write(TlcsLogLevel::Error, None, message);
+    }
+
+    pub fn session(&self) -> Option<&str> {
+        self.inner.session.as_deref()
+    }
+
+    /// Errors logged since the log was opened.
+    pub fn errors(&self) -> u64 {
+        self.inner.errors.load(Ordering::Relaxed)
+    }
+
+    /// Entries that could not be written since the log was opened.
+    pub fn write_failures(&self) -> u64 {
+        self.inner.write_failures.load(Ordering::Relaxed)
+    }
+
+    /// Records a raw line received from the TLCS server.
+    pub fn received(&self, line: &str) {
+        self.write(TlcsLogLevel::Trace, Some(TlcsLogDirection::Rx), line);
+    }
+
+    /// Waits until every entry logged so far is written.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.inner.writer.send(LogRequest::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    /// Skips the entries less severe than `level` from now on.
+    pub fn set_level(&self, level: TlcsLogLevel) {
+        if let Ok(mut current) = self.inner.level.write() {
+            *current = level;
+        }
+    }
+
+    fn write(&self, level: TlcsLogLevel, direction: Option<TlcsLogDirection>, message: &str) {
+        if self
+            .inner
+            .level
+            .read()
+            .is_ok_and(|minimum| level < *minimum)
+        {
+            return;
+        }
+        if ECHO_TO_APP_LOG.load(Ordering::Relaxed) {
+            let level = match level {
+                TlcsLogLevel::Trace => log::Level::Trace,
+                TlcsLogLevel::Debug => log::Level::Debug,
+                TlcsLogLevel::Info => log::Level::Info,
+                TlcsLogLevel::Error => log::Level::Error,
+            };
+            log::log!(level, "{}", self.inner.redactor.redact(message));
+        }
+        if self.write_entry(level, direction, message).is_err() {
+            self.inner.write_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Queues the entry for the writer thread of the file.
+    fn write_entry(
+        &self,
+        level: TlcsLogLevel,
+        direction: Option<TlcsLogDirection>,
+        message: &str,
+    ) -> Result<(), Error> {
+        let entry = TlcsLogEntry {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            level,
+            session: self.inner.session.clone(),
+            direction,
+            payload: self.inner.redactor.redact(message),
+        };
+        let line = serde_json::to_string(&entry)?;
+        self.inner
+            .writer
+            .send(LogRequest::Append(self.inner.clone(), line))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        Ok(())
+    }
+}
+
+impl RotatingLogInner {
+    /// Appends a serialized entry. Only the writer thread of the file calls
+    /// this, so rotations and writes never overlap.
+    fn append(&self, line: &str) -> Result<(), Error> {
+        self.rotate_if_needed()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")?;
+        Ok(())
+    }
+
+    fn rotate_if_needed(&self) -> Result<(), Error> {
+        let metadata = std::fs::metadata(&self.path);
+        if metadata.is_err() {
+            return Ok(());
+        }
+        let metadata = metadata?;
+        if metadata.len() < self.max_bytes {
+            return Ok(());
+        }
+
+        let path = &self.path;
+        for compressed in [false, true] {
+            let _ = std::fs::remove_file(rotated_path(path, self.max_files, compressed));
+        }
+        for index in (1..self.max_files).rev() {
+            for compressed in [false, true] {
+                let from = rotated_path(path, index, compressed);
+                if from.exists() {
+                    let _ = std::fs::rename(&from, rotated_path(path, index + 1, compressed));
+                }
+            }
+        }
+
+        if self.compress {
+            compress_file(path, &rotated_path(path, 1, true))?;
+            std::fs::remove_file(path)?;
+        } else {
+            let _ = std::fs::rename(path, rotated_path(path, 1, false));
+        }
+        Ok(())
+    }
+}
+
+/// Path of the `index`-th rotated log, e.g. `tlcs.log.2` or `tlcs.log.2.gz`.
+fn rotated_path(path: &Path, index: usize, compressed: bool) -> PathBuf {
+    if compressed {
+        path.with_extension(format!("log.{index}.gz"))
+    } else {
+        path.with_extension(format!("log.{index}"))
+    }
+}
+
+fn compress_file(from: &Path, to: &Path) -> Result<(), Error> {
+    let mut input = File::open(from)?;
+    let mut encoder = GzEncoder::new(File::create(to)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Lists the current log and its rotated predecessors, oldest first.
+fn log_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for index in 1.. {
+        let plain = rotated_path(path, index, false);
+        let compressed = rotated_path(path, index, true);
+        if plain.exists() {
+            files.push(plain);
+        } else if compressed.exists() {
+            files.push(compressed);
+        } else {
+            break;
+        }
+    }
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    files
+}
+
+pub(super) fn open_log(path: &Path) -> Result<Box<dyn BufRead>, Error> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+pub(super) fn parse_time(value: &str) -> Result<DateTime<Utc>, Error> {
+    Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+}
+
+impl TlcsLogQuery {
+    fn matches(
+        &self,
+        entry: &TlcsLogEntry,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> bool {
+        if self.level.is_some_and(|level| entry.level < level) {
+            return false;
+        }
+        if self.direction.is_some() && entry.direction != self.direction {
+            return false;
+        }
+        if self.session.is_some() && entry.session != self.session {
+            return false;
+        }
+        if since.is_some() || until.is_some() {
+            let Ok(timestamp) = parse_time(&entry.timestamp) else {
+                return false;
+            };
+            if since.is_some_and(|since| timestamp < since)
+                || until.is_some_and(|until| timestamp > until)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Returns the log entries matching `query`, oldest first. Lines that are not
+/// valid entries, such as those written by older versions, are skipped.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_tlcs_log(
+    query: TlcsLogQuery,
+    app: tauri::AppHandle,
+) -> Result<Vec<TlcsLogEntry>, Error> {
+    let path = app
+        .path()
+        .resolve(format!("tlcs/{LOG_FILE}"), BaseDirectory::AppData)?;
+    let since = query.since.as_deref().map(parse_time).transpose()?;
+    let until = query.until.as_deref().map(parse_time).transpose()?;
+
+    let mut entries = Vec::new();
+    for file in log_files(&path) {
+        for line in open_log(&file)?.lines() {
+            let Ok(entry) = serde_json::from_str::<TlcsLogEntry>(&line?) else {
+                continue;
+            };
+            if query.matches(&entry, since, until) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    if let Some(limit) = query.limit {
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_are_redacted() {
+        assert_eq!(
+            redact_credentials("USER arbiter s3cret"),
+            "USER arbiter ***"
+        );
+        assert_eq!(redact_credentials("pass hunter2"), "pass ***");
+        assert_eq!(redact_credentials("1. e4 e5"), "1. e4 e5");
+
+        let redactor = TlcsRedactor::new(&[TlcsRedactRule {
+            pattern: r"(?i)^(tell \S+) .*".to_string(),
+            replacement: Some("$1 ***".to_string()),
+        }])
+        .unwrap();
+        assert_eq!(
+            redactor.redact("tell arbiter see you at 5"),
+            "tell arbiter ***"
+        );
+        assert_eq!(redactor.redact("USER arbiter s3cret"), "USER arbiter ***");
+        assert!(TlcsRedactor::new(&[TlcsRedactRule {
+            pattern: "(".to_string(),
+            replacement: None,
+        }])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn sessions_sharing_a_log_rotate_it_without_losing_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOG_FILE);
+        let config = TlcsLogConfig {
+            max_bytes: Some(1024),
+            max_files: Some(1000),
+            compress: true,
+            redact: Vec::new(),
+        };
+        let logs: Vec<_> = (0..4)
+            .map(|session| {
+                RotatingLog::new(path.clone(), &config, Some(session.to_string())).unwrap()
+            })
+            .collect();
+        let writers: Vec<_> = logs
+            .iter()
+            .cloned()
+            .map(|log| {
+                std::thread::spawn(move || {
+                    for entry in 0..100 {
+                        log.info(&format!("entry {entry}"));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        logs[0].flush().await;
+        assert!(logs.iter().all(|log| log.write_failures() == 0));
+
+        let entries: usize = log_files(&path)
+            .iter()
+            .map(|file| open_log(file).unwrap().lines().count())
+            .sum();
+        assert_eq!(entries, 400);
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use specta::Type;
+
+/// Pieces of one side, kings aside.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Type)]
+pub struct TlcsMaterialCount {
+    pub pawns: u32,
+    pub knights: u32,
+    pub bishops: u32,
+    pub rooks: u32,
+    pub queens: u32,
+}
+
+impl TlcsMaterialCount {
+    /// Value in pawns, with minor pieces worth 3, rooks 5 and queens 9.
+    fn points(&self) -> i32 {
+        (self.pawns + 3 * (self.knights + self.bishops) + 5 * self.rooks + 9 * self.queens) as i32
+    }
+
+    /// Pieces this side has beyond `other`, queens first, like `2R+N`.
+    fn extra_pieces(&self, other: &Self) -> Vec<String> {
+        [
+            ("Q", self.queens, other.queens),
+            ("R", self.rooks, other.rooks),
+            ("B", self.bishops, other.bishops),
+            ("N", self.knights, other.knights),
+        ]
+        .into_iter()
+        .filter(|(_, own, theirs)| own > theirs)
+        .map(|(piece, own, theirs)| match own - theirs {
+            1 => piece.to_string(),
+            extra => format!("{extra}{piece}"),
+        })
+        .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Type)]
+pub struct TlcsMaterial {
+    pub white: TlcsMaterialCount,
+    pub black: TlcsMaterialCount,
+    /// White's advantage in pawns, negative when Black is ahead.
+    pub balance: i32,
+    /// White's extra pieces against Black's and White's extra pawns, like
+    /// `R vs B+N, +2 pawns`, or `=` when both sides have the same material.
+    pub imbalance: String,
+}
+
+/// Counts the material of the board part of `fen`.
+pub(super) fn material(fen: &str) -> Option<TlcsMaterial> {
+    let mut white = TlcsMaterialCount::default();
+    let mut black = TlcsMaterialCount::default();
+    for piece in fen.split_whitespace().next()?.chars() {
+        let side = if piece.is_ascii_uppercase() {
+            &mut white
+        } else {
+            &mut black
+        };
+        match piece.to_ascii_lowercase() {
+            'p' => side.pawns += 1,
+            'n' => side.knights += 1,
+            'b' => side.bishops += 1,
+            'r' => side.rooks += 1,
+            'q' => side.queens += 1,
+            _ => {}
+        }
+    }
+
+    let mut parts = Vec::new();
+    let (white_extra, black_extra) = (white.extra_pieces(&black), black.extra_pieces(&white));
+    if !white_extra.is_empty() || !black_extra.is_empty() {
+        let side = |extra: Vec<String>| {
+            if extra.is_empty() {
+                "-".to_string()
+            } else {
+                extra.join("+")
+            }
+        };
+        parts.push(format!("{} vs {}", side(white_extra), side(black_extra)));
+    }
+    let pawns = white.pawns as i32 - black.pawns as i32;
+    if pawns != 0 {
+        let plural = if pawns.abs() == 1 { "" } else { "s" };
+        parts.push(format!("{pawns:+} pawn{plural}"));
+    }
+    let imbalance = if parts.is_empty() {
+        "=".to_string()
+    } else {
+        parts.join(", ")
+    };
+
+    Some(TlcsMaterial {
+        balance: white.points() - black.points(),
+        white,
+        black,
+        imbalance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imbalances_are_described_for_white() {
+        let start = material("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(start.imbalance, "=");
+        assert_eq!(start.white.knights, 2);
+        assert_eq!(start.balance, 0);
+
+        let exchange = material("4k3/pp3ppp/2n1b3/8/8/8/PPPPPPPP/3RK3 w - - 0 1").unwrap();
+        assert_eq!(exchange.imbalance, "R vs B+N, +3 pawns");
+        assert_eq!(exchange.balance, 2);
+
+        let down = material("4k3/8/8/8/8/8/7P/4K3 b - - 0 1").unwrap();
+        assert_eq!(down.imbalance, "+1 pawn");
+        let queen = material("3qk3/8/8/8/8/8/8/RR2K3 w - - 0 1").unwrap();
+        assert_eq!(queen.imbalance, "2R vs Q");
+        assert!(material("").is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+
+use super::{connect_tcp, split_board_prefix, RotatingLog, BACKFILL_REQUEST};
+
+/// Wait before reconnecting to a relay that dropped, while the others keep
+/// being recorded.
+const SOURCE_RETRY: Duration = Duration::from_secs(5);
+
+/// One relay box of a merged broadcast, e.g. one section of an open.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsMergeSource {
+    pub host: String,
+    pub port: u16,
+    /// Added to the board numbers the relay sends, so sections do not
+    /// collide. Its unnumbered lines go to board `boardOffset + 1`.
+    #[serde(default)]
+    pub board_offset: u32,
+    /// Name used in the session log, `host:port` when unset.
+    pub name: Option<String>,
+}
+
+/// Moves a raw line to the merged board numbering. Only the ASCII prefix is
+/// rewritten, so the payload keeps the relay's encoding.
+fn renumber(line: &[u8], offset: u32) -> Vec<u8> {
+    let text = String::from_utf8_lossy(line);
+    let (board, payload) = split_board_prefix(&text);
+    let (board, payload) = match board {
+        // The prefix is ASCII, so it is as long in `line` as in `text`.
+        Some(board) => (board, &line[text.len() - payload.len()..]),
+        None => (1, line),
+    };
+    let mut merged = format!("board {}: ", board.saturating_add(offset)).into_bytes();
+    merged.extend_from_slice(payload);
+    merged
+}
+
+/// Reads one relay until shutdown, reconnecting when it drops.
+async fn read_source(
+    source: TlcsMergeSource,
+    proxy_url: Option<String>,
+    lines: mpsc::Sender<Vec<u8>>,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let name = source
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{}:{}", source.host, source.port));
+    let mut reconnected = false;
+    loop {
+        let connected = select! {
+            _ = shutdown.changed() => return,
+            stream = connect_tcp(&source.host, source.port, proxy_url.as_deref()) => stream,
+        };
+        match connected {
+            Ok(mut stream) => {
+                log.info(&format!("Merging boards of {name}"));
+                // Backfills the moves played while the relay was away.
+                if reconnected {
+                    let request = format!("{BACKFILL_REQUEST}\r\n");
+                    if let Err(err) = stream.write_all(request.as_bytes()).await {
+                        log.error(&format!("Failed to request move lists from {name}: {err}"));
+                    }
+                }
+                reconnected = true;
+                let mut reader = BufReader::new(stream);
+                let mut line = Vec::new();
+                loop {
+                    line.clear();
+                    let read = select! {
+                        _ = shutdown.changed() => return,
+                        read = reader.read_until(b'\n', &mut line) => read,
+                    };
+                    match read {
+                        Ok(0) => break,
+                        Ok(_) => {
+                            let end = line.trim_ascii_end().len();
+                            if end > 0
+                                && lines
+                                    .send(renumber(&line[..end], source.board_offset))
+                                    .await
+                                    .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Err(err) => {
+                            log.error(&format!("Read error from {name}: {err}"));
+                            break;
+                        }
+                    }
+                }
+                log.error(&format!("{name} disconnected"));
+            }
+            Err(err) => log.error(&format!("Unable to connect to {name}: {err}")),
+        }
+        select! {
+            _ = shutdown.changed() => return,
+            _ = tokio::time::sleep(SOURCE_RETRY) => {}
+        }
+    }
+}
+
+/// Connects to every relay and interleaves their lines, with boards
+/// renumbered by `TlcsMergeSource::board_offset`, into one stream that the
+/// session reads like a single server.
+pub(super) fn connect(
+    sources: Vec<TlcsMergeSource>,
+    proxy_url: Option<String>,
+    log: RotatingLog,
+    shutdown: watch::Receiver<bool>,
+) -> DuplexStream {
+    let (reader, mut writer) = tokio::io::duplex(64 * 1024);
+    let (tx, mut rx) = mpsc::channel(1024);
+    for source in sources {
+        tokio::spawn(read_source(
+            source,
+            proxy_url.clone(),
+            tx.clone(),
+            log.clone(),
+            shutdown.clone(),
+        ));
+    }
+    drop(tx);
+    tokio::spawn(async move {
+        while let Some(mut line) = rx.recv().await {
+            line.extend_from_slice(b"\r\n");
+            if writer.write_all(&line).await.is_err() {
+                return;
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    reader
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boards_are_offset_per_source() {
+        assert_eq!(renumber(b"board 3: 12. Nf3", 20), b"board 23: 12. Nf3");
+        assert_eq!(renumber(b"12. Nf3", 20), b"board 21: 12. Nf3");
+        assert_eq!(
+            renumber(b"Board 2: M\xfcller", 10),
+            b"board 12: M\xfcller".to_vec()
+        );
+    }
+}
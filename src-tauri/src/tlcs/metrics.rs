@@ -0,0 +1,258 @@
+use std::fmt::{Display, Write};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use serde::Serialize;
+use specta::Type;
+use tauri_specta::Event;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Traffic counters of a connection session, kept across reconnects.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsMetricsEvent {
+    pub connected: bool,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub lines_in: u64,
+    pub lines_out: u64,
+    /// Lines received per second over the last sampling interval.
+    pub lines_per_second: f64,
+    pub reconnects: u32,
+    pub uptime_ms: u64,
+}
+
+struct RateWindow {
+    since: Instant,
+    lines_in: u64,
+    per_second: f64,
+}
+
+pub struct TlcsMetrics {
+    connected: AtomicBool,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    lines_in: AtomicU64,
+    lines_out: AtomicU64,
+    reconnects: AtomicU32,
+    started: Instant,
+    window: Mutex<RateWindow>,
+}
+
+impl TlcsMetrics {
+    pub fn new() -> Arc<Self> {
+        let now = Instant::now();
+        Arc::new(Self {
+            connected: AtomicBool::new(false),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            lines_in: AtomicU64::new(0),
+            lines_out: AtomicU64::new(0),
+            reconnects: AtomicU32::new(0),
+            started: now,
+            window: Mutex::new(RateWindow {
+                since: now,
+                lines_in: 0,
+                per_second: 0.0,
+            }),
+        })
+    }
+
+    pub fn line_received(&self) {
+        self.lines_in.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn line_sent(&self, bytes: usize) {
+        self.lines_out.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub fn reconnected(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Closes the current sampling interval and returns the counters with
+    /// the line rate measured over it.
+    pub fn sample(&self) -> TlcsMetricsEvent {
+        if let Ok(mut window) = self.window.lock() {
+            let lines_in = self.lines_in.load(Ordering::Relaxed);
+            let elapsed = window.since.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                window.per_second = (lines_in - window.lines_in) as f64 / elapsed;
+            }
+            window.since = Instant::now();
+            window.lines_in = lines_in;
+        }
+        self.snapshot()
+    }
+
+    /// Returns the counters with the line rate of the last closed interval.
+    pub fn snapshot(&self) -> TlcsMetricsEvent {
+        TlcsMetricsEvent {
+            connected: self.connected.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            lines_in: self.lines_in.load(Ordering::Relaxed),
+            lines_out: self.lines_out.load(Ordering::Relaxed),
+            lines_per_second: self
+                .window
+                .lock()
+                .map(|window| window.per_second)
+                .unwrap_or_default(),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            uptime_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Counters of a recording session for the metrics endpoint.
+pub(super) struct TlcsSessionMetrics {
+    pub(super) session: String,
+    pub(super) moves_recorded: usize,
+    pub(super) log_errors: u64,
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    labels: &str,
+    value: impl Display,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "{name}{labels} {value}");
+}
+
+/// Renders the playing connection and the recording session in the
+/// Prometheus text exposition format.
+pub(super) fn exposition(
+    connection: Option<&TlcsMetricsEvent>,
+    session: Option<&TlcsSessionMetrics>,
+) -> String {
+    let mut out = String::new();
+    let connected = connection.is_some_and(|connection| connection.connected);
+    write_metric(
+        &mut out,
+        "tlcs_connection_up",
+        "gauge",
+        "Whether the TLCS connection is established.",
+        "",
+        u8::from(connected),
+    );
+    if let Some(connection) = connection {
+        for (name, help, value) in [
+            (
+                "tlcs_reconnects_total",
+                "Reconnects of the TLCS connection.",
+                u64::from(connection.reconnects),
+            ),
+            (
+                "tlcs_lines_received_total",
+                "Lines read from the TLCS server.",
+                connection.lines_in,
+            ),
+            (
+                "tlcs_bytes_received_total",
+                "Bytes read from the TLCS server.",
+                connection.bytes_in,
+            ),
+        ] {
+            write_metric(&mut out, name, "counter", help, "", value);
+        }
+    }
+    write_metric(
+        &mut out,
+        "tlcs_recording_up",
+        "gauge",
+        "Whether a TLCS recording session is running.",
+        "",
+        u8::from(session.is_some()),
+    );
+    if let Some(session) = session {
+        let labels = format!("{{session=\"{}\"}}", escape_label(&session.session));
+        write_metric(
+            &mut out,
+            "tlcs_moves_recorded_total",
+            "counter",
+            "Moves recorded on all boards of the session.",
+            &labels,
+            session.moves_recorded,
+        );
+        write_metric(
+            &mut out,
+            "tlcs_log_errors_total",
+            "counter",
+            "Errors written to the session log.",
+            &labels,
+            session.log_errors,
+        );
+    }
+    out
+}
+
+/// Counts the bytes read from `inner`.
+pub struct CountingReader<R> {
+    inner: R,
+    metrics: Arc<TlcsMetrics>,
+}
+
+impl<R> CountingReader<R> {
+    pub fn new(inner: R, metrics: Arc<TlcsMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &poll {
+            let read = buf.filled().len() - filled;
+            self.metrics
+                .bytes_in
+                .fetch_add(read as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_are_labelled_in_the_exposition() {
+        let text = exposition(
+            None,
+            Some(&TlcsSessionMetrics {
+                session: "round \"5\"".to_string(),
+                moves_recorded: 42,
+                log_errors: 1,
+            }),
+        );
+        assert!(text.contains("tlcs_connection_up 0\n"));
+        assert!(text.contains("tlcs_recording_up 1\n"));
+        assert!(text.contains("tlcs_moves_recorded_total{session=\"round \\\"5\\\"\"} 42\n"));
+        assert!(text.contains("# TYPE tlcs_log_errors_total counter\n"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::watch;
+
+use crate::{error::Error, AppState};
+
+/// One step of a mock server script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ScriptStep {
+    Send(String),
+    Wait(Duration),
+}
+
+/// Parses a mock server script. Every line is sent to the client as is,
+/// except for blank lines, `#` comments and these directives:
+///
+/// - `@wait <ms>` pauses the replay.
+/// - `@interval <ms>` pauses before each following line, until changed.
+fn parse_script(script: &str) -> Result<Vec<ScriptStep>, Error> {
+    let mut steps = Vec::new();
+    let mut interval = Duration::ZERO;
+    for (number, line) in script.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(directive) = trimmed.strip_prefix('@') {
+            let (name, value) = directive.split_once(' ').unwrap_or((directive, ""));
+            let millis = value.trim().parse::<u64>().map_err(|_| {
+                Error::TlcsInvalidScript(format!("line {}: expected milliseconds", number + 1))
+            })?;
+            match name {
+                "wait" => steps.push(ScriptStep::Wait(Duration::from_millis(millis))),
+                "interval" => interval = Duration::from_millis(millis),
+                _ => {
+                    return Err(Error::TlcsInvalidScript(format!(
+                        "line {}: unknown directive @{name}",
+                        number + 1
+                    )))
+                }
+            }
+            continue;
+        }
+        if !interval.is_zero() {
+            steps.push(ScriptStep::Wait(interval));
+        }
+        steps.push(ScriptStep::Send(line.trim_end().to_string()));
+    }
+    Ok(steps)
+}
+
+/// Local TCP server that replays a scripted TLCS feed to every client, for
+/// testing the recorder and the UI without a live tournament.
+pub struct TlcsMockServer {
+    address: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsMockServer {
+    async fn stop(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.task.await;
+    }
+}
+
+async fn replay(
+    mut stream: TcpStream,
+    steps: Arc<Vec<ScriptStep>>,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<()> {
+    for step in steps.iter() {
+        match step {
+            ScriptStep::Send(line) => {
+                stream.write_all(format!("{line}\r\n").as_bytes()).await?;
+            }
+            ScriptStep::Wait(duration) => {
+                select! {
+                    _ = tokio::time::sleep(*duration) => {}
+                    _ = shutdown.changed() => return Ok(()),
+                }
+            }
+        }
+    }
+    stream.shutdown().await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_tlcs_mock_server(
+    script_path: String,
+    port: u16,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, Error> {
+    let steps = Arc::new(parse_script(&std::fs::read_to_string(&script_path)?)?);
+
+    let mut guard = state.tlcs_mock_server.write().await;
+    if let Some(server) = guard.take() {
+        server.stop().await;
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let address = listener.local_addr()?;
+    let (shutdown, mut shutdown_rx) = watch::channel(false);
+    let client_shutdown = shutdown.subscribe();
+
+    let task = tokio::spawn(async move {
+        loop {
+            select! {
+                _ = shutdown_rx.changed() => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, peer)) = accepted else {
+                        continue;
+                    };
+                    log::info!("TLCS mock server replaying to {peer}");
+                    let steps = steps.clone();
+                    let shutdown = client_shutdown.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = replay(stream, steps, shutdown).await {
+                            log::info!("TLCS mock client {peer} disconnected: {err}");
+                        }
+                    });
+                }
+            }
+        }
+    });
+
+    log::info!("TLCS mock server replaying {script_path} on {address}");
+    *guard = Some(TlcsMockServer {
+        address,
+        shutdown,
+        task,
+    });
+    Ok(address.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_tlcs_mock_server(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<String>, Error> {
+    let mut guard = state.tlcs_mock_server.write().await;
+    if let Some(server) = guard.take() {
+        let address = server.address.to_string();
+        server.stop().await;
+        return Ok(Some(address));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_directives_are_parsed() {
+        let script = "# opening\n1. e4\n@interval 500\ne5\n\n@wait 2000\nclock w=1000 b=900\n";
+        assert_eq!(
+            parse_script(script).unwrap(),
+            vec![
+                ScriptStep::Send("1. e4".into()),
+                ScriptStep::Wait(Duration::from_millis(500)),
+                ScriptStep::Send("e5".into()),
+                ScriptStep::Wait(Duration::from_millis(2000)),
+                ScriptStep::Wait(Duration::from_millis(500)),
+                ScriptStep::Send("clock w=1000 b=900".into()),
+            ]
+        );
+        assert!(parse_script("@pause 10").is_err());
+    }
+}
@@ -0,0 +1,4600 @@
+mod book;
+mod broadcast;
+mod capture;
+mod chess_com;
+mod connection;
+mod dedup;
+mod dgt;
+mod diagnostics;
+mod draw;
+mod encoding;
+mod events;
+mod fen_diff;
+mod follow;
+mod frames;
+mod headers;
+mod headless;
+mod http_server;
+mod ics;
+mod kibitzer;
+mod lichess;
+mod live_analysis;
+mod logging;
+mod material;
+mod merge;
+mod metrics;
+mod mock_server;
+mod notify;
+mod novelty;
+mod overlay;
+mod pairings;
+mod parser;
+mod pgn_format;
+mod rate_limit;
+mod reconnect;
+mod recovery;
+mod replay;
+mod scripts;
+mod simul;
+mod sounds;
+mod standings;
+mod tlcv;
+mod trf;
+mod upload;
+mod webhook;
+mod writer;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+use shakmaty::{
+    fen::Fen,
+    san::SanPlus,
+    uci::UciMove,
+    variant::{Variant, VariantPosition},
+    Board, CastlingMode, Color, EnPassantMode, Move, Position,
+};
+use specta::Type;
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+use tauri_specta::Event;
+use tokio::io::{AsyncRead, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc, watch, Mutex, RwLock};
+
+use crate::chess::AnalysisOptions;
+use crate::error::Error;
+use crate::opening::get_eco_from_setup;
+use crate::AppState;
+
+use self::book::TlcsBookWatch;
+use self::broadcast::{TlcsBroadcastOptions, TlcsBroadcastPush};
+use self::capture::{CaptureReader, TlcsCapture, CAPTURE_EXTENSION};
+use self::chess_com::TlcsChessComOptions;
+use self::dedup::{game_key, recorder_key, GameKey, TlcsDedup};
+use self::dgt::TlcsDgtOptions;
+use self::diagnostics::TlcsSessionHealth;
+use self::draw::{DrawStatus, RepetitionTracker};
+use self::events::{event_safe_id, TlcsEmitter};
+use self::fen_diff::{find_plies, parse_placement};
+use self::follow::TlcsFollowers;
+use self::frames::SettledLines;
+use self::headers::{unescape_tag_value, PgnHeaders};
+use self::ics::TlcsIcsOptions;
+use self::kibitzer::TlcsKibitzer;
+use self::lichess::TlcsLichessOptions;
+use self::live_analysis::{LiveAnalysis, LiveAnalysisSettings, DEFAULT_LIVE_ANALYSIS_DEPTH};
+use self::logging::{
+    redact_credentials, RotatingLog, TlcsLogConfig, TlcsLogLevel, TlcsRedactRule, TlcsRedactor,
+    LOG_FILE,
+};
+use self::material::{material, TlcsMaterial};
+use self::merge::TlcsMergeSource;
+use self::metrics::{CountingReader, TlcsMetrics};
+use self::notify::TlcsNotificationOptions;
+use self::novelty::TlcsNoveltyWatch;
+use self::overlay::{TlcsOverlay, TlcsOverlayOptions};
+use self::pairings::TlcsPairing;
+use self::parser::{TlcsLineParser, TlcsParserRegistry, DEFAULT_PROTOCOL};
+use self::pgn_format::{take_clock_command, MovetextWriter, PgnSnapshot, TlcsPgnFormat};
+use self::recovery::TlcsSessionRecord;
+use self::replay::TlcsReplay;
+use self::scripts::{announces_new_game, ScriptRunner, TlcsScriptTrigger};
+use self::sounds::{move_cue, LowTimeWatch, TlcsSoundCue, DEFAULT_LOW_TIME_MS};
+use self::tlcv::{parse_pv, pv_comment};
+use self::upload::{TlcsUpload, TlcsUploadOptions, TlcsUploadStatus};
+use self::webhook::{TlcsFinishedGame, TlcsWebhook, TlcsWebhooks};
+use self::writer::PgnWriter;
+
+pub use self::book::TlcsBookEvent;
+pub use self::broadcast::TlcsBroadcastEvent;
+pub(crate) use self::connection::connect_tcp;
+pub use self::diagnostics::tlcs_diagnostics;
+pub use self::draw::{TlcsDrawClaim, TlcsDrawClaimEvent};
+pub(crate) use self::encoding::DecodedLines;
+pub use self::encoding::TlcsInputEncoding;
+pub use self::follow::{follow_tlcs_in_board, unfollow_tlcs_in_board, TlcsTabMoveEvent};
+pub use self::headless::{headless_config_path, start_headless};
+pub use self::http_server::{start_tlcs_http_server, stop_tlcs_http_server, TlcsHttpServer};
+pub use self::kibitzer::{start_tlcs_kibitzer, stop_tlcs_kibitzer, TlcsKibitzEvent};
+pub use self::live_analysis::{tlcs_eval_history, TlcsBlunderEvent, TlcsEvalEvent};
+pub use self::logging::query_tlcs_log;
+pub use self::metrics::TlcsMetricsEvent;
+pub use self::mock_server::{start_tlcs_mock_server, stop_tlcs_mock_server, TlcsMockServer};
+pub use self::notify::TlcsNotifier;
+pub use self::novelty::TlcsNoveltyEvent;
+pub use self::pairings::load_tlcs_pairings;
+pub(crate) use self::rate_limit::FrameLimiter;
+pub use self::rate_limit::{TlcsRateLimit, TlcsRateLimitedEvent};
+pub(crate) use self::reconnect::Reconnector;
+pub use self::reconnect::{ReconnectGiveUp, ReconnectPolicy, TlcsRetryInfo};
+pub use self::recovery::{
+    discard_interrupted_tlcs_session, resume_interrupted_tlcs_session, tlcs_interrupted_sessions,
+};
+pub use self::replay::replay_tlcs_log;
+pub use self::scripts::{TlcsScript, TlcsScriptStep};
+pub use self::simul::tlcs_simul_overview;
+pub use self::sounds::TlcsSoundEvent;
+pub use self::standings::compute_tlcs_standings;
+pub use self::tlcv::TlcsEnginePvEvent;
+pub use self::trf::export_tlcs_trf;
+pub use self::webhook::TlcsWebhookEvent;
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct TlcsStatus {
+    pub recording: bool,
+    pub pgn_path: Option<String>,
+    pub moves_recorded: usize,
+    pub boards: Vec<TlcsBoardStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct TlcsBoardStatus {
+    pub board: u32,
+    pub pgn_path: String,
+    pub moves_recorded: usize,
+}
+
+/// Not `Debug`, so that its credentials never reach a log.
+#[derive(Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsConnectOptions {
+    pub host: String,
+    pub port: u16,
+    pub event: Option<String>,
+    pub site: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub initial_fen: Option<String>,
+    pub pgn_path: Option<String>,
+    /// Path to a UCI engine that evaluates every new position while recording.
+    pub live_analysis_engine: Option<String>,
+    pub live_analysis_depth: Option<u32>,
+    /// Write the live evaluations as `[%eval]` comments into the PGN.
+    #[serde(default)]
+    pub annotate_eval: bool,
+    /// Emit `TlcsBlunderEvent` when the live evaluation drops by at least
+    /// this many centipawns for the side that just moved.
+    pub blunder_threshold_cp: Option<u32>,
+    /// Write the clock of the side that moved as `[%clk]` comments.
+    #[serde(default)]
+    pub annotate_clock: bool,
+    /// Write the time spent on each move, taken from consecutive clock
+    /// updates of its side, as `[%emt]` comments.
+    #[serde(default)]
+    pub annotate_emt: bool,
+    /// Write the last PV each engine reported before its move, from `WPV:`
+    /// and `BPV:` lines, as a `{+0.35/21 e5 Nf3}` comment after the move.
+    #[serde(default)]
+    pub annotate_engine_pv: bool,
+    /// Relay the recorded games to a Lichess broadcast round.
+    pub broadcast: Option<TlcsBroadcastOptions>,
+    /// Upload the session PGN to an HTTP or FTP destination periodically.
+    pub upload: Option<TlcsUploadOptions>,
+    /// Endpoints that receive the players, result and PGN of every game
+    /// that finishes.
+    pub webhooks: Option<Vec<TlcsWebhook>>,
+    /// Size, count and compression of the rotated session logs.
+    pub log_config: Option<TlcsLogConfig>,
+    /// Least severe entries written to the session log. Everything,
+    /// including the raw server lines, when unset.
+    pub log_level: Option<TlcsLogLevel>,
+    /// `socks5://` or `http://` proxy to reach the TLCS server through.
+    pub proxy_url: Option<String>,
+    /// Relay boxes recorded together into one multi-game PGN, each with its
+    /// own board offset, instead of the server at `host` and `port`.
+    #[serde(default)]
+    pub merge: Vec<TlcsMergeSource>,
+    /// Record a board whose players, from its pairing or `white` and
+    /// `black`, and start position match an unfinished game on another board
+    /// into that game, following whichever source is ahead. For backup
+    /// relays that send the same boards.
+    #[serde(default)]
+    pub deduplicate: bool,
+    /// Rules of the recorded games. Defaults to standard chess.
+    pub variant: Option<TlcsVariant>,
+    /// Stop recording a board when a line cannot be applied to it, instead of
+    /// skipping the offending move.
+    #[serde(default)]
+    pub strict: bool,
+    /// Terminate the unfinished games once no move arrived for this many
+    /// minutes, without closing the connection.
+    pub idle_timeout_mins: Option<u64>,
+    /// Keep files with the live position up to date for streaming software.
+    pub overlay: Option<TlcsOverlayOptions>,
+    /// Database of known games. The first position of a recorded game that
+    /// is missing from it is reported as a novelty.
+    pub reference_db: Option<String>,
+    /// Polyglot opening books whose moves are reported for every live
+    /// position until the game leaves them.
+    #[serde(default)]
+    pub opening_books: Vec<String>,
+    /// Append consecutive games on a board to the same PGN, one round each,
+    /// instead of continuing a single game.
+    #[serde(default)]
+    pub tournament: bool,
+    /// Write every line received from the server, verbatim, to a `.tlcs`
+    /// file next to the PGN. Only `Tcp` sessions can be captured.
+    #[serde(default)]
+    pub capture: bool,
+    /// Protocol spoken by the server at `host` and `port`.
+    #[serde(default)]
+    pub transport: TlcsTransport,
+    /// Login and observed games when `transport` is `Ics`.
+    pub ics: Option<TlcsIcsOptions>,
+    /// Game or TV channel when `transport` is `Lichess`.
+    pub lichess: Option<TlcsLichessOptions>,
+    /// Game or player when `transport` is `ChessCom`.
+    pub chess_com: Option<TlcsChessComOptions>,
+    /// Serial port and forwarding when `transport` is `Dgt`.
+    pub dgt: Option<TlcsDgtOptions>,
+    /// Further PGN headers such as `WhiteElo`, `TimeControl` or `Section`.
+    /// They override the headers above, except `Result`.
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// Line wrapping of the movetext.
+    pub pgn_format: Option<TlcsPgnFormat>,
+    /// Character set of the server's lines. UTF-8 when unset.
+    pub encoding: Option<TlcsInputEncoding>,
+    /// Emit the session's events as `tlcs-move-recorded/{session id}` and so
+    /// on, instead of under their plain names.
+    #[serde(default)]
+    pub scope_events: bool,
+    /// Hold bare FEN lines until the placement stayed the same for this many
+    /// milliseconds, and ignore the placements no legal move leads to, for
+    /// boards that report every piece lifted or slid on its way.
+    pub fen_settle_ms: Option<u64>,
+    /// Emit `TlcsSoundEvent` when a clock falls under this many
+    /// milliseconds, 30 seconds when unset.
+    pub low_time_ms: Option<u64>,
+}
+
+/// Where a session's moves come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsTransport {
+    /// TLCS lines over TCP.
+    #[default]
+    Tcp,
+    /// Tom's Live Chess Viewer datagrams over UDP. `proxy_url` does not
+    /// apply.
+    TlcvUdp,
+    /// Style 12 board updates of games observed on a FICS-style server.
+    Ics,
+    /// The move stream of a Lichess game or TV channel. `host` and `port`
+    /// are ignored.
+    Lichess,
+    /// A chess.com game, polled for new moves. `host` and `port` are
+    /// ignored.
+    ChessCom,
+    /// Moves played on a DGT board connected to this machine. `host` and
+    /// `port` are ignored.
+    Dgt,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsVariant {
+    #[default]
+    Standard,
+    Chess960,
+    Atomic,
+    Antichess,
+    Crazyhouse,
+    Horde,
+    KingOfTheHill,
+    RacingKings,
+    ThreeCheck,
+}
+
+impl TlcsVariant {
+    fn rules(self) -> Variant {
+        match self {
+            TlcsVariant::Standard | TlcsVariant::Chess960 => Variant::Chess,
+            TlcsVariant::Atomic => Variant::Atomic,
+            TlcsVariant::Antichess => Variant::Antichess,
+            TlcsVariant::Crazyhouse => Variant::Crazyhouse,
+            TlcsVariant::Horde => Variant::Horde,
+            TlcsVariant::KingOfTheHill => Variant::KingOfTheHill,
+            TlcsVariant::RacingKings => Variant::RacingKings,
+            TlcsVariant::ThreeCheck => Variant::ThreeCheck,
+        }
+    }
+
+    fn castling_mode(self) -> CastlingMode {
+        match self {
+            TlcsVariant::Chess960 => CastlingMode::Chess960,
+            _ => CastlingMode::Standard,
+        }
+    }
+
+    /// Value of the PGN `Variant` header, or `None` for standard chess.
+    fn header(self) -> Option<&'static str> {
+        match self {
+            TlcsVariant::Standard => None,
+            TlcsVariant::Chess960 => Some("Chess960"),
+            TlcsVariant::Atomic => Some("Atomic"),
+            TlcsVariant::Antichess => Some("Antichess"),
+            TlcsVariant::Crazyhouse => Some("Crazyhouse"),
+            TlcsVariant::Horde => Some("Horde"),
+            TlcsVariant::KingOfTheHill => Some("King of the Hill"),
+            TlcsVariant::RacingKings => Some("Racing Kings"),
+            TlcsVariant::ThreeCheck => Some("Three-check"),
+        }
+    }
+
+    fn from_header(header: &str) -> Self {
+        let normalized: String = header
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "chess960" | "fischerandom" | "fischerrandom" => TlcsVariant::Chess960,
+            "atomic" => TlcsVariant::Atomic,
+            "antichess" | "suicide" | "giveaway" => TlcsVariant::Antichess,
+            "crazyhouse" => TlcsVariant::Crazyhouse,
+            "horde" => TlcsVariant::Horde,
+            "kingofthehill" => TlcsVariant::KingOfTheHill,
+            "racingkings" => TlcsVariant::RacingKings,
+            "threecheck" | "3check" => TlcsVariant::ThreeCheck,
+            _ => TlcsVariant::Standard,
+        }
+    }
+
+    /// The initial position, from `fen` when given.
+    fn start_position(self, fen: Option<&str>) -> Result<VariantPosition, Error> {
+        match fen {
+            Some(fen) => {
+                let fen: Fen = fen.parse()?;
+                Ok(VariantPosition::from_setup(
+                    self.rules(),
+                    fen.into_setup(),
+                    CastlingMode::Chess960,
+                )?)
+            }
+            None => Ok(VariantPosition::new(self.rules())),
+        }
+    }
+}
+
+pub(crate) struct TlcsRecorder {
+    headers: PgnHeaders,
+    setup_fen: Option<String>,
+    variant: TlcsVariant,
+    start_position: VariantPosition,
+    position: VariantPosition,
+    moves: Vec<String>,
+    sans: Vec<String>,
+    comments: BTreeMap<usize, String>,
+    nags: BTreeMap<usize, Vec<u8>>,
+    /// Clock of the side that made the move at each ply, from the first
+    /// clock update after it.
+    clocks: BTreeMap<usize, u64>,
+    annotate_clock: bool,
+    annotate_emt: bool,
+    annotate_engine_pv: bool,
+    /// The last PV of the engine to move, written after its move.
+    engine_pv: Option<TlcsEnginePvEvent>,
+    start_fen: String,
+    result: Option<String>,
+    /// Whether `result` is the `*` set by `finish_idle`, which a later move
+    /// or result takes back.
+    idle_finished: bool,
+    white_clock_ms: Option<u64>,
+    black_clock_ms: Option<u64>,
+    /// The larger clock shown before the first move, to infer a missing
+    /// `TimeControl` header from.
+    initial_clock_ms: Option<u64>,
+    /// Games written before the last forced restart, kept verbatim. Shared
+    /// with the PGN writer rather than copied on every write.
+    archived: Arc<String>,
+    /// Summaries of the games in `archived`.
+    completed: Vec<TlcsTournamentGame>,
+    tournament: bool,
+    strict: bool,
+    /// Placements of bare FEN lines that no move leads to are noise, rather
+    /// than a takeback or a new game to resync to.
+    filter_placements: bool,
+    pgn_format: TlcsPgnFormat,
+    reference_db: Option<PathBuf>,
+    /// The line and reason that stopped recording in strict mode.
+    desync: Option<(String, String)>,
+    /// Repair made by the last `fen` line, until the demux reports it.
+    resync: Option<(TlcsResyncAction, String)>,
+    /// Plies kept, added and replaced by the last restated move list, until
+    /// the demux reports them.
+    backfill: Option<(usize, usize, usize)>,
+    /// Moves appended since the demux last reported them.
+    recorded: Vec<TlcsMoveRecordedEvent>,
+    /// Sound cues since the demux last reported them.
+    sounds: Vec<TlcsSoundEvent>,
+    low_time: LowTimeWatch,
+    /// Engine PVs since the demux last reported them.
+    engine_pvs: Vec<TlcsEnginePvEvent>,
+    /// Ply, ECO code and name of the deepest book position reached.
+    opening: Option<(usize, String, String)>,
+    /// When the first move of the game was recorded.
+    started_at: Option<DateTime<Utc>>,
+    log: RotatingLog,
+    writer: PgnWriter,
+    pgn_path: PathBuf,
+}
+
+/// Emitted when strict mode stops recording a board because a line could not
+/// be applied to the recorded position.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsDesyncEvent {
+    pub board: Option<u32>,
+    pub line: String,
+    pub fen: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsResyncAction {
+    /// Moves the feed skipped were found and added.
+    InsertedMoves,
+    /// The position occurred earlier in the game; later moves were dropped.
+    TookBack,
+    /// The position could not be reached; the game restarts from the FEN.
+    Restarted,
+}
+
+/// Emitted when a move list restated by the server, such as its answer to
+/// the `MOVES` request sent after a reconnect, filled in moves the recorder
+/// had missed.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBackfillEvent {
+    pub board: Option<u32>,
+    /// Recorded plies the move list agreed with.
+    pub from_ply: usize,
+    /// Plies added from the move list.
+    pub backfilled: usize,
+    /// Recorded plies the move list replaced.
+    pub replaced: usize,
+}
+
+/// Emitted with the movetext of the moves a line added to a game, so viewers
+/// extend their copy instead of reading the whole PGN again. Also returned by
+/// `tlcs_pgn_delta`.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPgnAppendedEvent {
+    pub board: Option<u32>,
+    /// Plies the movetext follows. Plies a viewer has past it were taken
+    /// back and are replaced.
+    pub since_ply: usize,
+    /// Plies of the game, the movetext included.
+    pub plies: usize,
+    /// Moves with their comments and NAGs on one line, e.g.
+    /// `21... Kg7 {[%clk 0:12:05]} 22. Rd7+`.
+    pub movetext: String,
+    /// Set once the game is over.
+    pub result: Option<String>,
+}
+
+/// Emitted for every move appended to a recorded game.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsMoveRecordedEvent {
+    pub board: Option<u32>,
+    pub san: String,
+    pub uci: String,
+    /// Ply of the move, counting from 1.
+    pub ply: usize,
+    pub fen_after: String,
+    /// Last clock reported for the side that moved, in milliseconds.
+    pub clock: Option<u64>,
+}
+
+/// Emitted when a `fen` line disagreed with the recorded position and the
+/// recorder repaired its move list.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsResyncEvent {
+    pub board: Option<u32>,
+    pub fen: String,
+    pub action: TlcsResyncAction,
+    pub description: String,
+}
+
+/// A game archived in the PGN of a board.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsTournamentGame {
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+}
+
+impl TlcsTournamentGame {
+    fn from_headers(headers: &PgnHeaders) -> Self {
+        let header = |name: &str| headers.get(name).cloned().unwrap_or_else(|| "?".into());
+        Self {
+            round: header("Round"),
+            white: header("White"),
+            black: header("Black"),
+            result: header("Result"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsTournamentStatus {
+    /// Round of the game being recorded.
+    pub round: String,
+    pub games: Vec<TlcsTournamentGame>,
+}
+
+/// Emitted when the opening of a recorded game is classified, or when later
+/// moves refine the classification.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOpeningEvent {
+    pub board: Option<u32>,
+    pub eco: String,
+    pub name: String,
+}
+
+/// ECO lines never run longer than this, so later plies are not looked up.
+const OPENING_MAX_PLIES: usize = 40;
+/// Plies without a deeper book position before `[ECO]` and `[Opening]` are
+/// written.
+const OPENING_STABLE_PLIES: usize = 6;
+
+/// The part of a FEN that identifies a position: placement, side to move,
+/// castling rights and en passant square.
+fn position_key<P: Position + Clone>(position: &P) -> String {
+    Fen::from_position(position.clone(), EnPassantMode::Legal)
+        .to_string()
+        .split_whitespace()
+        .take(4)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads the `w=<ms> b=<ms>` fields of a `clock` line.
+fn parse_clocks(clocks: &str) -> (Option<u64>, Option<u64>) {
+    let mut white = None;
+    let mut black = None;
+    for part in clocks.split_whitespace() {
+        if let Some(value) = part.strip_prefix("w=") {
+            white = value.parse().ok().or(white);
+        }
+        if let Some(value) = part.strip_prefix("b=") {
+            black = value.parse().ok().or(black);
+        }
+    }
+    (white, black)
+}
+
+pub(crate) fn is_result_token(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+/// Parses a SAN or UCI token into a legal move in `position`. Tokens that are
+/// neither are ignored.
+pub(crate) fn parse_move(position: &VariantPosition, token: &str) -> Result<Option<Move>, Error> {
+    if let Ok(san) = SanPlus::from_ascii(token.as_bytes()) {
+        if let Ok(mv) = san.san.to_move(position) {
+            return Ok(Some(mv));
+        }
+    }
+
+    match UciMove::from_ascii(token.as_bytes()) {
+        Ok(uci) => Ok(Some(uci.to_move(position)?)),
+        Err(_) => match SanPlus::from_ascii(token.as_bytes()) {
+            Ok(san) => Ok(Some(san.san.to_move(position)?)),
+            Err(_) => Ok(None),
+        },
+    }
+}
+
+/// A piece of movetext from the server.
+#[derive(Debug, PartialEq, Eq)]
+enum MovetextToken {
+    /// A move or a result.
+    Move(String),
+    /// The ply, counted from 0, of the move that follows.
+    MoveNumber(usize),
+    Comment(String),
+    Nag(u8),
+}
+
+/// The NAG of a suffix annotation such as `!?`.
+fn suffix_nag(glyph: &str) -> Option<u8> {
+    match glyph {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// Adds one whitespace-delimited word of movetext. Move numbers are split off,
+/// also when glued to the move (`12.Nf3`, `12...Nf6`), and castling written
+/// with zeros is normalized.
+fn push_movetext_word(tokens: &mut Vec<MovetextToken>, word: &str) {
+    if let Some(nag) = word.strip_prefix('$') {
+        tokens.extend(nag.parse().ok().map(MovetextToken::Nag));
+        return;
+    }
+    let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let after_number = word[digits..].trim_start_matches('.');
+    let dots = word.len() - digits - after_number.len();
+    if dots > 0 {
+        let black = usize::from(dots >= 3);
+        match word[..digits].parse::<usize>() {
+            Ok(number) if number > 0 => {
+                tokens.push(MovetextToken::MoveNumber((number - 1) * 2 + black));
+            }
+            // `12. ... Nf6`
+            _ if digits == 0 && black == 1 => {
+                if let Some(MovetextToken::MoveNumber(ply)) = tokens.last_mut() {
+                    *ply |= 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    let word = if dots > 0 { after_number } else { word };
+    if word.is_empty() || word.bytes().all(|b| b.is_ascii_digit()) || word == "e.p." {
+        return;
+    }
+
+    let (mv, glyph) = word.split_at(word.trim_end_matches(['!', '?']).len());
+    if !mv.is_empty() {
+        let mv = if mv.starts_with("0-0") {
+            mv.replace('0', "O")
+        } else {
+            mv.to_string()
+        };
+        tokens.push(MovetextToken::Move(mv));
+    }
+    tokens.extend(suffix_nag(glyph).map(MovetextToken::Nag));
+}
+
+/// Splits movetext into moves, brace comments and NAGs the way a PGN reader
+/// would, skipping variations and `;` comments. NAGs are given as `$n` or as
+/// suffix annotations, on their own or glued to the move. Unlike the crate's
+/// PGN reader, UCI moves are kept, since relays send those too.
+fn parse_movetext(line: &str) -> Vec<MovetextToken> {
+    let mut tokens = Vec::new();
+    let mut variations = 0usize;
+    let mut rest = line.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let end = match first {
+            '{' => {
+                let close = rest.find('}').unwrap_or(rest.len());
+                let comment = rest[1..close].trim();
+                if variations == 0 && !comment.is_empty() {
+                    tokens.push(MovetextToken::Comment(comment.to_string()));
+                }
+                (close + 1).min(rest.len())
+            }
+            ';' => break,
+            '(' => {
+                variations += 1;
+                1
+            }
+            ')' => {
+                variations = variations.saturating_sub(1);
+                1
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{}();".contains(c))
+                    .unwrap_or(rest.len());
+                if variations == 0 {
+                    push_movetext_word(&mut tokens, &rest[..end]);
+                }
+                end
+            }
+        };
+        rest = rest[end..].trim_start();
+    }
+    tokens
+}
+
+/// Parses correction commands such as `takeback 2` or `undo`, returning the
+/// number of plies to take back.
+fn parse_takeback(line: &str) -> Option<usize> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next()?;
+    if !command.eq_ignore_ascii_case("takeback") && !command.eq_ignore_ascii_case("undo") {
+        return None;
+    }
+    match parts.next() {
+        Some(count) => count.parse().ok(),
+        None => Some(1),
+    }
+}
+
+/// Maps the text of a TLCS `status` line to a PGN result and `Termination`
+/// header. A side named in the status is the loser unless the status says it
+/// won; otherwise the side to move in `position` is assumed to have lost.
+fn result_from_status<P: Position>(
+    status: &str,
+    position: &P,
+) -> Option<(&'static str, &'static str)> {
+    let status = status.to_ascii_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| status.contains(word));
+
+    let named = match (status.contains("white"), status.contains("black")) {
+        (true, false) => Some(Color::White),
+        (false, true) => Some(Color::Black),
+        _ => None,
+    };
+    let loser = match named {
+        Some(color) if has(&["wins", "won"]) => Some(!color),
+        named => named,
+    };
+    let won_against = |loser: Color| match loser {
+        Color::White => "0-1",
+        Color::Black => "1-0",
+    };
+
+    if has(&["offer", "declin"]) {
+        None
+    } else if has(&[
+        "stalemate",
+        "draw",
+        "repetition",
+        "insufficient",
+        "fifty",
+        "50-move",
+    ]) {
+        Some(("1/2-1/2", "Normal"))
+    } else if status.contains("mate") {
+        let loser = if position.is_checkmate() {
+            position.turn()
+        } else {
+            loser.unwrap_or(position.turn())
+        };
+        Some((won_against(loser), "Normal"))
+    } else if has(&["flag", "time forfeit", "out of time", "timeout", "on time"]) {
+        Some((
+            won_against(loser.unwrap_or(position.turn())),
+            "Time forfeit",
+        ))
+    } else if status.contains("resign") {
+        loser.map(|loser| (won_against(loser), "Normal"))
+    } else if has(&["abandon", "forfeit"]) {
+        loser.map(|loser| (won_against(loser), "Abandoned"))
+    } else {
+        None
+    }
+}
+
+/// Splits a PGN tag pair such as `[White "Carlsen"]` into key and value.
+fn parse_header(line: &str) -> Option<(&str, String)> {
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(' ')?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((key, unescape_tag_value(value)))
+}
+
+impl TlcsRecorder {
+    /// A recorder for a game followed outside a recording session, such as
+    /// an auto-subscription of the TLCS client. It logs to the TLCS log in
+    /// `tlcs_dir` and writes its PGN through a writer of its own.
+    pub(crate) fn for_game(
+        tlcs_dir: &Path,
+        pgn_path: PathBuf,
+        options: &TlcsConnectOptions,
+        board: Option<u32>,
+    ) -> Result<Self, Error> {
+        let log = session_log(tlcs_dir, options, &pgn_path)?;
+        let writer = PgnWriter::spawn(log.clone());
+        Self::new(pgn_path, options, board, log, writer)
+    }
+
+    fn new(
+        pgn_path: PathBuf,
+        options: &TlcsConnectOptions,
+        board: Option<u32>,
+        log: RotatingLog,
+        writer: PgnWriter,
+    ) -> Result<Self, Error> {
+        if let Some(parent) = pgn_path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let variant = options.variant.unwrap_or_default();
+        let mut headers = PgnHeaders::default();
+        headers.insert(
+            "Event".to_string(),
+            options.event.clone().unwrap_or_else(|| "TLCS Live".into()),
+        );
+        headers.insert(
+            "Site".to_string(),
+            options.site.clone().unwrap_or_else(|| "TLCS".into()),
+        );
+        headers.insert(
+            "Date".to_string(),
+            Utc::now().format("%Y.%m.%d").to_string(),
+        );
+        headers.insert(
+            "White".to_string(),
+            options.white.clone().unwrap_or_else(|| "Unknown".into()),
+        );
+        headers.insert(
+            "Black".to_string(),
+            options.black.clone().unwrap_or_else(|| "Unknown".into()),
+        );
+        headers.insert("Round".to_string(), "1".into());
+        headers.insert("Result".to_string(), "*".into());
+        if let Some(board) = board {
+            headers.insert("Board".to_string(), board.to_string());
+        }
+        if let Some(name) = variant.header() {
+            headers.insert("Variant".to_string(), name.to_string());
+        }
+        let mut extra_headers: Vec<_> = options.extra_headers.iter().flatten().collect();
+        extra_headers.sort();
+        for (key, value) in extra_headers {
+            if key != "Result" {
+                headers.insert(key.clone(), value.clone());
+            }
+        }
+
+        let recorder = Self::base(
+            pgn_path,
+            options,
+            headers,
+            options.initial_fen.clone(),
+            variant,
+            log,
+            writer,
+        )?;
+        recorder.persist()?;
+        Ok(recorder)
+    }
+
+    /// A recorder with no moves yet, at the start position of `variant` or
+    /// `setup_fen`.
+    fn base(
+        pgn_path: PathBuf,
+        options: &TlcsConnectOptions,
+        headers: PgnHeaders,
+        setup_fen: Option<String>,
+        variant: TlcsVariant,
+        log: RotatingLog,
+        writer: PgnWriter,
+    ) -> Result<Self, Error> {
+        let position = variant.start_position(setup_fen.as_deref())?;
+        Ok(Self {
+            headers,
+            setup_fen,
+            variant,
+            start_fen: Fen::from_position(position.clone(), EnPassantMode::Legal).to_string(),
+            start_position: position.clone(),
+            position,
+            moves: Vec::new(),
+            sans: Vec::new(),
+            comments: BTreeMap::new(),
+            nags: BTreeMap::new(),
+            clocks: BTreeMap::new(),
+            annotate_clock: options.annotate_clock,
+            annotate_emt: options.annotate_emt,
+            annotate_engine_pv: options.annotate_engine_pv,
+            engine_pv: None,
+            result: None,
+            idle_finished: false,
+            white_clock_ms: None,
+            black_clock_ms: None,
+            initial_clock_ms: None,
+            archived: Arc::default(),
+            completed: Vec::new(),
+            tournament: options.tournament,
+            strict: options.strict,
+            filter_placements: options.fen_settle_ms.is_some(),
+            pgn_format: options.pgn_format.clone().unwrap_or_default(),
+            reference_db: options.reference_db.as_ref().map(PathBuf::from),
+            desync: None,
+            resync: None,
+            backfill: None,
+            recorded: Vec::new(),
+            sounds: Vec::new(),
+            low_time: LowTimeWatch::new(options.low_time_ms.unwrap_or(DEFAULT_LOW_TIME_MS)),
+            engine_pvs: Vec::new(),
+            opening: None,
+            started_at: None,
+            log,
+            writer,
+            pgn_path,
+        })
+    }
+
+    /// Reopens a PGN written by an earlier session, replaying its moves so
+    /// recording continues where it stopped.
+    fn resume(
+        pgn_path: PathBuf,
+        options: &TlcsConnectOptions,
+        log: RotatingLog,
+        writer: PgnWriter,
+    ) -> Result<Self, Error> {
+        let pgn = std::fs::read_to_string(&pgn_path)?;
+
+        // Only the last game is resumed; earlier ones were archived by a
+        // forced restart and are kept as they are.
+        let mut archived = String::new();
+        let mut completed = Vec::new();
+        let mut game: Vec<&str> = Vec::new();
+        let mut in_movetext = false;
+        for line in pgn.lines() {
+            let is_header = parse_header(line).is_some();
+            if is_header && in_movetext {
+                archived.push_str(game.join("\n").trim_end());
+                archived.push_str("\n\n");
+                let headers = game
+                    .iter()
+                    .filter_map(|line| parse_header(line))
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect();
+                completed.push(TlcsTournamentGame::from_headers(&headers));
+                game.clear();
+                in_movetext = false;
+            }
+            in_movetext |= !is_header && !line.trim().is_empty();
+            game.push(line);
+        }
+
+        let mut headers = PgnHeaders::default();
+        let mut setup_fen = None;
+        let mut movetext = String::new();
+        for line in game {
+            match parse_header(line) {
+                Some(("FEN", value)) => setup_fen = Some(value),
+                Some(("SetUp", _)) => {}
+                Some((key, value)) => {
+                    headers.insert(key.to_string(), value);
+                }
+                None => {
+                    movetext.push_str(line);
+                    movetext.push(' ');
+                }
+            }
+        }
+
+        let variant = headers
+            .get("Variant")
+            .map(|name| TlcsVariant::from_header(name))
+            .unwrap_or_default();
+        let mut recorder = Self::base(pgn_path, options, headers, setup_fen, variant, log, writer)?;
+        recorder.archived = Arc::new(archived);
+        recorder.completed = completed;
+
+        for token in parse_movetext(&movetext) {
+            match token {
+                MovetextToken::Move(token) => {
+                    recorder.append_token(&token)?;
+                }
+                annotation => {
+                    recorder.annotate_last(annotation);
+                }
+            }
+        }
+        recorder.recorded.clear();
+        recorder.sounds.clear();
+
+        // An unfinished game is terminated with `*`, which must not stop the
+        // resumed session from recording a real result later.
+        if recorder.result.as_deref() == Some("*") {
+            recorder.result = None;
+        }
+
+        recorder.log.info(&format!(
+            "Resumed {} after {} plies",
+            recorder.pgn_path.to_string_lossy(),
+            recorder.moves.len()
+        ));
+        Ok(recorder)
+    }
+
+    fn pgn_path(&self) -> PathBuf {
+        self.pgn_path.clone()
+    }
+
+    fn moves_recorded(&self) -> usize {
+        self.moves.len()
+    }
+
+    fn fen(&self) -> String {
+        Fen::from_position(self.position.clone(), EnPassantMode::Legal).to_string()
+    }
+
+    /// Repetitions of the current position and its halfmove clock.
+    fn draw_status(&self) -> DrawStatus {
+        let fen = |position: &VariantPosition| {
+            Fen::from_position(position.clone(), EnPassantMode::Legal).to_string()
+        };
+        let mut tracker = RepetitionTracker::default();
+        let mut position = self.start_position.clone();
+        let mut status = tracker.update(&fen(&position));
+        for uci in &self.moves {
+            let Some(mv) = UciMove::from_ascii(uci.as_bytes())
+                .ok()
+                .and_then(|uci| uci.to_move(&position).ok())
+            else {
+                break;
+            };
+            position.play_unchecked(&mv);
+            status = tracker.update(&fen(&position));
+        }
+        status
+    }
+
+    /// Sets the players, ratings and federations of the board's pairing.
+    fn apply_pairing(&mut self, pairing: &TlcsPairing) -> Result<(), Error> {
+        for (tag, value) in pairing.headers() {
+            self.headers.insert(tag.to_string(), value);
+        }
+        self.persist()
+    }
+
+    /// Records the game result and updates the `Result` header. The caller
+    /// persists the PGN afterwards.
+    fn finish(&mut self, outcome: &str) {
+        self.reopen_idle();
+        if self.result.is_none() {
+            self.result = Some(outcome.to_string());
+            self.headers
+                .insert("Result".to_string(), outcome.to_string());
+        }
+    }
+
+    /// Whether the game has a result other than one set by `finish_idle`.
+    fn is_finished(&self) -> bool {
+        self.result.is_some() && !self.idle_finished
+    }
+
+    /// Takes back the `*` of a game finished by `finish_idle`, once the feed
+    /// turns out to still carry it.
+    fn reopen_idle(&mut self) {
+        if !std::mem::take(&mut self.idle_finished) {
+            return;
+        }
+        self.log.info(&format!(
+            "{} continues after it was finished as idle",
+            self.pgn_path.to_string_lossy()
+        ));
+        self.result = None;
+        self.headers.insert("Result".to_string(), "*".into());
+    }
+
+    fn append_moves_from_line(&mut self, line: &str) -> Result<(), Error> {
+        if let Some(setup_fen) = self.new_game_from_line(line) {
+            return self.start_next_round(setup_fen);
+        }
+
+        if let Some(fen) = line.trim().strip_prefix("fen ") {
+            return self.resync(fen.trim());
+        }
+
+        if let Some(clocks) = line.trim().strip_prefix("clock ") {
+            let (white, black) = parse_clocks(clocks);
+            self.white_clock_ms = white.or(self.white_clock_ms);
+            self.black_clock_ms = black.or(self.black_clock_ms);
+            for side in self
+                .low_time
+                .update(self.white_clock_ms, self.black_clock_ms)
+            {
+                self.sounds.push(TlcsSoundEvent {
+                    board: None,
+                    cue: TlcsSoundCue::LowTime,
+                    side,
+                    ply: self.moves.len(),
+                });
+            }
+            if self.moves.is_empty() {
+                self.initial_clock_ms = self.initial_clock_ms.max(white.max(black));
+            }
+            let mover = match self.position.turn() {
+                Color::White => black,
+                Color::Black => white,
+            };
+            let ply = self.moves.len();
+            if ply > 0 && !self.clocks.contains_key(&ply) {
+                if let Some(clock) = mover {
+                    self.clocks.insert(ply, clock);
+                    if self.annotate_clock || self.annotate_emt {
+                        self.persist()?;
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(pv) = parse_pv(line.trim()) {
+            self.record_engine_pv(pv);
+            return Ok(());
+        }
+
+        if self.desync.is_some() {
+            return Ok(());
+        }
+
+        if let Some(status) = line.trim().strip_prefix("status ") {
+            return self.apply_status(status);
+        }
+
+        if let Some(plies) = parse_takeback(line) {
+            return self.take_back(plies);
+        }
+
+        if let Some(board) = parse_placement(line) {
+            return self.follow_placement(line, &board);
+        }
+
+        let movetext = parse_movetext(line);
+        let tokens: Vec<String> = movetext
+            .iter()
+            .filter_map(|token| match token {
+                MovetextToken::Move(token) => Some(token.clone()),
+                _ => None,
+            })
+            .collect();
+        if line.trim_start().starts_with("1.") && tokens.len() > 1 {
+            return self.restate(&tokens);
+        }
+
+        let before = (self.moves.len(), self.is_finished());
+        let mut annotated = false;
+        let mut failure = None;
+        // Servers resend moves already recorded, typically after a
+        // reconnect. Numbered moves that match the recorded history are
+        // skipped, along with their annotations.
+        let mut expected_ply = None;
+        let mut replayed = false;
+        for token in movetext {
+            let token = match token {
+                MovetextToken::Move(token) => token,
+                MovetextToken::MoveNumber(ply) => {
+                    expected_ply = Some(ply);
+                    continue;
+                }
+                annotation => {
+                    if !replayed {
+                        annotated |= self.annotate_last(annotation);
+                    }
+                    continue;
+                }
+            };
+            let ply = expected_ply.take();
+            expected_ply = ply.map(|ply| ply + 1);
+            replayed = ply.is_some_and(|ply| self.is_recorded_at(ply, &token));
+            if replayed {
+                continue;
+            }
+            match self.append_token(&token) {
+                Ok(true) => {}
+                Ok(false) if self.strict => {
+                    failure = Some(Error::TlcsUnrecognizedMove(token));
+                    break;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+        if annotated || before != (self.moves.len(), self.is_finished()) {
+            self.persist()?;
+        }
+
+        match failure {
+            Some(err) if self.strict => {
+                self.log.error(&format!(
+                    "Stopped recording {} after \"{line}\": {err}",
+                    self.pgn_path.to_string_lossy()
+                ));
+                self.desync = Some((line.to_string(), err.to_string()));
+                Ok(())
+            }
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the recorded position against a `fen` line from the server and
+    /// repairs the move list when they differ: up to two skipped plies are
+    /// filled in, a position seen earlier is treated as a takeback, and
+    /// anything else restarts the game from the FEN.
+    fn resync(&mut self, fen: &str) -> Result<(), Error> {
+        let target = position_key(&self.variant.start_position(Some(fen))?);
+        if position_key(&self.position) == target {
+            self.desync = None;
+            return Ok(());
+        }
+        if self.is_finished() {
+            return Ok(());
+        }
+
+        let (action, description) = if let Some(missing) = self.find_missing_moves(&target) {
+            let mut sans = Vec::new();
+            for mv in missing {
+                sans.push(self.push_move(&mv));
+            }
+            (
+                TlcsResyncAction::InsertedMoves,
+                format!("Inserted missing moves {}", sans.join(" ")),
+            )
+        } else if let Some(ply) = self.find_earlier_ply(&target) {
+            let dropped = self.moves.len() - ply;
+            self.truncate(ply)?;
+            (
+                TlcsResyncAction::TookBack,
+                format!("Took back {dropped} plies to match the server position"),
+            )
+        } else {
+            self.restart_from(fen)?;
+            (
+                TlcsResyncAction::Restarted,
+                "Restarted the game from the server position".to_string(),
+            )
+        };
+
+        self.log.info(&format!(
+            "{}: {description}",
+            self.pgn_path.to_string_lossy()
+        ));
+        self.desync = None;
+        self.resync = Some((action, description));
+        self.persist()
+    }
+
+    /// Looks for one or two legal plies leading from the current position to
+    /// the one identified by `target`.
+    fn find_missing_moves(&self, target: &str) -> Option<Vec<Move>> {
+        find_plies(&self.position, |after| position_key(after) == target)
+    }
+
+    /// Records the moves leading to the placement of a bare FEN line, for
+    /// relays that send positions instead of moves. Placements no move or
+    /// two plies away are left to `resync`, or dropped when they are
+    /// filtered.
+    fn follow_placement(&mut self, line: &str, board: &Board) -> Result<(), Error> {
+        if self.position.board() == board || self.is_finished() {
+            return Ok(());
+        }
+        let Some(plies) = find_plies(&self.position, |after| after.board() == board) else {
+            if self.filter_placements {
+                return Ok(());
+            }
+            return self.resync(line.trim());
+        };
+        for mv in plies {
+            self.push_move(&mv);
+        }
+        self.persist()
+    }
+
+    /// Returns the latest ply before the current one at which the game
+    /// reached the position identified by `target`.
+    fn find_earlier_ply(&self, target: &str) -> Option<usize> {
+        let mut position = self.start_position.clone();
+        let mut found = (position_key(&position) == target).then_some(0);
+        for (index, uci) in self
+            .moves
+            .iter()
+            .enumerate()
+            .take(self.moves.len().saturating_sub(1))
+        {
+            let mv = UciMove::from_ascii(uci.as_bytes())
+                .ok()?
+                .to_move(&position)
+                .ok()?;
+            position.play_unchecked(&mv);
+            if position_key(&position) == target {
+                found = Some(index + 1);
+            }
+        }
+        found
+    }
+
+    /// Classifies the opening after the move list changed. Returns the new
+    /// ECO code and name when the classification changed, and writes the
+    /// headers once no deeper book position has followed for a few plies.
+    fn update_opening(&mut self) -> Result<Option<(String, String)>, Error> {
+        if self.variant != TlcsVariant::Standard {
+            return Ok(None);
+        }
+
+        let previous = self.opening.clone();
+        let stale = previous
+            .as_ref()
+            .is_some_and(|(ply, ..)| *ply > self.moves.len());
+        if self.moves.len() <= OPENING_MAX_PLIES || stale {
+            self.opening = self.classify_opening()?;
+        }
+
+        if let Some((ply, eco, name)) = &self.opening {
+            let stable = self.result.is_some() || self.moves.len() >= ply + OPENING_STABLE_PLIES;
+            if stable
+                && (self.headers.get("ECO") != Some(eco)
+                    || self.headers.get("Opening") != Some(name))
+            {
+                self.headers.insert("ECO".to_string(), eco.clone());
+                self.headers.insert("Opening".to_string(), name.clone());
+                self.persist()?;
+            }
+        } else if self.headers.remove("ECO").is_some() | self.headers.remove("Opening").is_some() {
+            // A takeback went back before the first book position.
+            self.persist()?;
+        }
+
+        let summary = |opening: &Option<(usize, String, String)>| {
+            opening
+                .as_ref()
+                .map(|(_, eco, name)| (eco.clone(), name.clone()))
+        };
+        let current = summary(&self.opening);
+        Ok(if current != summary(&previous) {
+            current
+        } else {
+            None
+        })
+    }
+
+    /// Replays the first plies of the game and returns the deepest position
+    /// found in the ECO book.
+    fn classify_opening(&self) -> Result<Option<(usize, String, String)>, Error> {
+        let mut position = self.start_position.clone();
+        let mut found = None;
+        for (index, uci) in self.moves.iter().take(OPENING_MAX_PLIES).enumerate() {
+            let mv = UciMove::from_ascii(uci.as_bytes())?.to_move(&position)?;
+            position.play_unchecked(&mv);
+            let setup = position.clone().into_setup(EnPassantMode::Legal);
+            if let Some((eco, name)) = get_eco_from_setup(&setup) {
+                found = Some((index + 1, eco, name));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Restarts the game from `fen`, keeping the moves recorded so far as a
+    /// separate game in the same file. Clears a strict-mode desync.
+    fn restart_from(&mut self, fen: &str) -> Result<(), Error> {
+        self.log.info(&format!(
+            "Restarting {} from {fen}",
+            self.pgn_path.to_string_lossy()
+        ));
+        self.reset_game(Some(fen.to_string()))?;
+        self.persist()
+    }
+
+    /// Whether `line` announces a new game in tournament mode: a `start`
+    /// line, or a `fen` line with the initial position once the current game
+    /// has begun. Returns the setup of the new game.
+    fn new_game_from_line(&self, line: &str) -> Option<Option<String>> {
+        if !self.tournament || (self.moves.is_empty() && self.result.is_none()) {
+            return None;
+        }
+        let line = line.trim();
+        if line.eq_ignore_ascii_case("start") || line.eq_ignore_ascii_case("new game") {
+            return Some(None);
+        }
+        if let Some(fen) = line.strip_prefix("start ") {
+            return Some(Some(fen.trim().to_string()));
+        }
+        let fen = line.strip_prefix("fen ")?.trim();
+        let initial = self.variant.start_position(None).ok()?;
+        let position = self.variant.start_position(Some(fen)).ok()?;
+        (position_key(&position) == position_key(&initial)).then_some(None)
+    }
+
+    /// Archives the current game and starts the next round, from `setup_fen`
+    /// or the initial position of the variant.
+    fn start_next_round(&mut self, setup_fen: Option<String>) -> Result<(), Error> {
+        let round = self
+            .headers
+            .get("Round")
+            .and_then(|round| round.parse::<u32>().ok())
+            .unwrap_or(1)
+            + 1;
+        self.log.info(&format!(
+            "Starting round {round} in {}",
+            self.pgn_path.to_string_lossy()
+        ));
+        self.reset_game(setup_fen)?;
+        self.headers.insert("Round".to_string(), round.to_string());
+        self.white_clock_ms = None;
+        self.black_clock_ms = None;
+        self.persist()
+    }
+
+    /// Moves the current game to `archived` and starts an empty one. The
+    /// caller persists the PGN afterwards.
+    fn reset_game(&mut self, setup_fen: Option<String>) -> Result<(), Error> {
+        let position = self.variant.start_position(setup_fen.as_deref())?;
+        if !self.moves.is_empty() || self.result.is_some() {
+            let game = self.render();
+            let archived = Arc::make_mut(&mut self.archived);
+            archived.push_str(game.trim_end());
+            archived.push_str("\n\n");
+            self.completed
+                .push(TlcsTournamentGame::from_headers(&self.headers));
+        }
+
+        self.setup_fen = setup_fen;
+        self.start_fen = Fen::from_position(position.clone(), EnPassantMode::Legal).to_string();
+        self.start_position = position.clone();
+        self.position = position;
+        self.moves.clear();
+        self.sans.clear();
+        self.comments.clear();
+        self.nags.clear();
+        self.clocks.clear();
+        self.initial_clock_ms = None;
+        self.low_time.reset();
+        self.engine_pv = None;
+        self.result = None;
+        self.idle_finished = false;
+        self.headers.insert("Result".to_string(), "*".into());
+        for header in ["Termination", "ECO", "Opening"] {
+            self.headers.remove(header);
+        }
+        self.opening = None;
+        self.started_at = None;
+        self.desync = None;
+        Ok(())
+    }
+
+    /// Replaces the result with one set by the arbiter, even when the game
+    /// already had one.
+    fn adjudicate(&mut self, result: &str, termination: &str) -> Result<(), Error> {
+        self.log
+            .info(&format!("Arbiter set the result to {result}"));
+        self.result = None;
+        self.headers
+            .insert("Termination".to_string(), termination.to_string());
+        self.finish(result);
+        self.persist()
+    }
+
+    /// Ends the game when a `status` line reports a finished game, for servers
+    /// that never send a result token.
+    fn apply_status(&mut self, status: &str) -> Result<(), Error> {
+        if self.is_finished() {
+            return Ok(());
+        }
+        let Some((result, termination)) = result_from_status(status, &self.position) else {
+            return Ok(());
+        };
+        self.log
+            .info(&format!("Status \"{status}\" ends the game with {result}"));
+        self.headers
+            .insert("Termination".to_string(), termination.to_string());
+        self.finish(result);
+        self.persist()
+    }
+
+    /// Removes the last `plies` moves.
+    fn take_back(&mut self, plies: usize) -> Result<(), Error> {
+        let keep = self.moves.len().saturating_sub(plies);
+        self.log
+            .info(&format!("Taking back {} plies", self.moves.len() - keep));
+        self.truncate(keep)?;
+        self.persist()
+    }
+
+    /// Records the moves of a game tracked elsewhere, in UCI, past those
+    /// recorded so far, then its result once it has one.
+    pub(crate) fn follow_moves(
+        &mut self,
+        moves: &[String],
+        result: Option<&str>,
+    ) -> Result<(), Error> {
+        if let Some(new) = moves.get(self.moves.len()..).filter(|new| !new.is_empty()) {
+            self.append_moves_from_line(&new.join(" "))?;
+        }
+        if let (Some(result), false) = (result, self.is_finished()) {
+            self.finish(result);
+            self.persist()?;
+        }
+        Ok(())
+    }
+
+    /// Handles a full restatement of the game from move 1. Moves that extend
+    /// the recorded game are appended; a diverging move list replaces it. A
+    /// list that stops short of the recorded game without diverging, such as
+    /// a wrapped or echoed one, changes nothing.
+    fn restate(&mut self, tokens: &[String]) -> Result<(), Error> {
+        let recorded = self.moves.len();
+        let mut position = self.start_position.clone();
+        let mut moves = Vec::new();
+        let mut move_tokens = Vec::new();
+        let mut end = tokens.len();
+        for (index, token) in tokens.iter().enumerate() {
+            if is_result_token(token) {
+                end = index;
+                break;
+            }
+            if let Some(mv) = parse_move(&position, token)? {
+                moves.push(mv.to_uci(self.variant.castling_mode()).to_string());
+                move_tokens.push(index);
+                position.play_unchecked(&mv);
+            }
+        }
+
+        let common = self
+            .moves
+            .iter()
+            .zip(&moves)
+            .take_while(|(recorded, restated)| recorded == restated)
+            .count();
+
+        if common == moves.len() && common < self.moves.len() {
+            return Ok(());
+        }
+        if common < self.moves.len() {
+            self.log.info(&format!(
+                "Move list restated, replacing moves after ply {common}"
+            ));
+            self.truncate(common)?;
+        }
+
+        let start = move_tokens.get(common).copied().unwrap_or(end);
+        for token in &tokens[start..end] {
+            self.append_token(token)?;
+        }
+        if let Some(result) = tokens.get(end) {
+            self.finish(result);
+        }
+        let backfilled = self.moves.len() - common;
+        if backfilled > 0 {
+            self.log.info(&format!(
+                "Backfilled {backfilled} plies from the restated move list"
+            ));
+            self.backfill = Some((common, backfilled, recorded - common));
+        }
+        self.persist()
+    }
+
+    fn truncate(&mut self, keep: usize) -> Result<(), Error> {
+        self.moves.truncate(keep);
+        self.sans.truncate(keep);
+        self.comments.retain(|ply, _| *ply <= keep);
+        self.nags.retain(|ply, _| *ply <= keep);
+        self.clocks.retain(|ply, _| *ply <= keep);
+        self.engine_pv = None;
+        self.result = None;
+        self.idle_finished = false;
+        self.headers.insert("Result".to_string(), "*".into());
+
+        let mut position = self.start_position.clone();
+        for uci in &self.moves {
+            let mv = UciMove::from_ascii(uci.as_bytes())?.to_move(&position)?;
+            position.play_unchecked(&mv);
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    /// Whether `token` is the move already recorded at `ply` (counted from 0).
+    fn is_recorded_at(&self, ply: usize, token: &str) -> bool {
+        let Some(recorded) = self.moves.get(ply) else {
+            return false;
+        };
+        let mut position = self.start_position.clone();
+        for uci in &self.moves[..ply] {
+            let Some(mv) = UciMove::from_ascii(uci.as_bytes())
+                .ok()
+                .and_then(|uci| uci.to_move(&position).ok())
+            else {
+                return false;
+            };
+            position.play_unchecked(&mv);
+        }
+        matches!(
+            parse_move(&position, token),
+            Ok(Some(mv)) if mv.to_uci(self.variant.castling_mode()).to_string() == *recorded
+        )
+    }
+
+    /// Plays a single token on the internal board, returning `false` when it
+    /// is neither a move nor a result. Callers persist the PGN once the whole
+    /// line has been applied.
+    fn append_token(&mut self, token: &str) -> Result<bool, Error> {
+        if token.is_empty() {
+            return Ok(true);
+        }
+
+        if is_result_token(token) {
+            self.finish(token);
+            return Ok(true);
+        }
+
+        let Some(mv) = parse_move(&self.position, token)? else {
+            return Ok(false);
+        };
+        self.push_move(&mv);
+        Ok(true)
+    }
+
+    /// Plays a legal move on the internal board, queues its event and returns
+    /// its SAN.
+    fn push_move(&mut self, mv: &Move) -> String {
+        let clock = match self.position.turn() {
+            Color::White => self.white_clock_ms,
+            Color::Black => self.black_clock_ms,
+        };
+        self.reopen_idle();
+        if self.moves.is_empty() {
+            self.started_at = Some(Utc::now());
+        }
+        let side = match self.position.turn() {
+            Color::White => TlcsSide::White,
+            Color::Black => TlcsSide::Black,
+        };
+        let uci = mv.to_uci(self.variant.castling_mode()).to_string();
+        let san = SanPlus::from_move_and_play_unchecked(&mut self.position, mv).to_string();
+        self.moves.push(uci.clone());
+        self.sans.push(san.clone());
+        if let Some(pv) = self.engine_pv.take().filter(|pv| pv.side == side) {
+            self.comments.insert(self.moves.len(), pv_comment(&pv));
+        }
+        // A line playing several moves sounds only the last.
+        self.sounds
+            .retain(|sound| sound.cue == TlcsSoundCue::LowTime);
+        self.sounds.push(TlcsSoundEvent {
+            board: None,
+            cue: move_cue(mv.is_capture(), self.position.is_check()),
+            side,
+            ply: self.moves.len(),
+        });
+        self.recorded.push(TlcsMoveRecordedEvent {
+            board: None,
+            san: san.clone(),
+            uci,
+            ply: self.moves.len(),
+            fen_after: self.fen(),
+            clock,
+        });
+        san
+    }
+
+    /// Queues the event of an engine PV, and keeps it for the comment of the
+    /// engine's move when it is the engine to move.
+    fn record_engine_pv(&mut self, pv: TlcsEnginePvEvent) {
+        let to_move = match self.position.turn() {
+            Color::White => TlcsSide::White,
+            Color::Black => TlcsSide::Black,
+        };
+        if self.annotate_engine_pv && pv.side == to_move {
+            self.engine_pv = Some(pv.clone());
+        }
+        self.engine_pvs.push(pv);
+    }
+
+    /// Attaches a comment or NAG from the stream to the last recorded move,
+    /// after any it already has. Returns `false` when no move was recorded
+    /// yet.
+    fn annotate_last(&mut self, annotation: MovetextToken) -> bool {
+        let ply = self.moves.len();
+        if ply == 0 {
+            return false;
+        }
+        match annotation {
+            MovetextToken::Comment(mut comment) => {
+                // Clock commands this recorder writes itself are kept apart
+                // so they are not written twice.
+                if self.annotate_clock {
+                    let (clock, rest) = take_clock_command(&comment, "clk");
+                    if let Some(clock) = clock {
+                        self.clocks.insert(ply, clock);
+                    }
+                    comment = rest;
+                }
+                if self.annotate_emt {
+                    comment = take_clock_command(&comment, "emt").1;
+                }
+                if comment.is_empty() {
+                    return true;
+                }
+                self.comments
+                    .entry(ply)
+                    .and_modify(|existing| {
+                        existing.push(' ');
+                        existing.push_str(&comment);
+                    })
+                    .or_insert(comment);
+            }
+            MovetextToken::Nag(nag) => self.nags.entry(ply).or_default().push(nag),
+            MovetextToken::Move(_) | MovetextToken::MoveNumber(_) => return false,
+        }
+        true
+    }
+
+    /// Attaches a comment to the move at `ply` (1-based).
+    fn annotate(&mut self, ply: usize, comment: &str) -> Result<(), Error> {
+        if ply == 0 || ply > self.moves.len() {
+            return Ok(());
+        }
+        self.comments.insert(ply, comment.to_string());
+        self.persist()
+    }
+
+    /// The game as `render` and the PGN writer read it.
+    fn snapshot(&self) -> PgnSnapshot<'_> {
+        PgnSnapshot {
+            headers: Cow::Borrowed(&self.headers),
+            setup_fen: Cow::Borrowed(&self.setup_fen),
+            sans: Cow::Borrowed(self.sans.as_slice()),
+            comments: Cow::Borrowed(&self.comments),
+            nags: Cow::Borrowed(&self.nags),
+            clocks: Cow::Borrowed(&self.clocks),
+            initial_clock_ms: self.initial_clock_ms,
+            annotate_clock: self.annotate_clock,
+            annotate_emt: self.annotate_emt,
+            result: Cow::Borrowed(&self.result),
+            format: Cow::Borrowed(&self.pgn_format),
+        }
+    }
+
+    /// The movetext of the plies after `since_ply`.
+    fn pgn_delta(&self, board: Option<u32>, since_ply: usize) -> TlcsPgnAppendedEvent {
+        let since_ply = since_ply.min(self.sans.len());
+        let format = TlcsPgnFormat {
+            line_width: Some(0),
+            ..TlcsPgnFormat::default()
+        };
+        let snapshot = self.snapshot();
+        let mut movetext = MovetextWriter::resumed(&format);
+        for ply in since_ply..self.sans.len() {
+            snapshot.write_ply(&mut movetext, ply);
+        }
+        TlcsPgnAppendedEvent {
+            board,
+            since_ply,
+            plies: self.sans.len(),
+            movetext: movetext.into_text(),
+            result: self.result.clone(),
+        }
+    }
+
+    /// Serializes the full game, headers included.
+    fn render(&self) -> String {
+        self.snapshot().render()
+    }
+
+    /// The webhook payload for the game once it has a result.
+    fn finished_game(&self, board: Option<u32>) -> Option<TlcsFinishedGame> {
+        let result = self.result.clone()?;
+        Some(TlcsFinishedGame {
+            board,
+            event: self.headers.get("Event").cloned(),
+            round: self.headers.get("Round").cloned(),
+            white: self.headers.get("White").cloned(),
+            black: self.headers.get("Black").cloned(),
+            result,
+            plies: self.moves.len(),
+            duration_secs: self
+                .started_at
+                .map(|started_at| (Utc::now() - started_at).num_seconds().max(0) as u64),
+            pgn: self.render(),
+        })
+    }
+
+    /// Queues the PGN for writing. The writer renders it and replaces the
+    /// file in one step, so a crash never leaves a truncated game behind.
+    fn persist(&self) -> Result<(), Error> {
+        self.writer.write(
+            self.pgn_path.clone(),
+            self.archived.clone(),
+            self.snapshot().into_owned(),
+        );
+        Ok(())
+    }
+
+    fn analysis_options(&self) -> AnalysisOptions {
+        AnalysisOptions {
+            fen: self.start_fen.clone(),
+            moves: self.moves.clone(),
+            annotate_novelties: self.reference_db.is_some(),
+            reference_db: self.reference_db.clone(),
+            reversed: false,
+        }
+    }
+}
+
+/// Splits a `board N: ...` prefixed line into its board number and payload.
+/// Lines without a prefix belong to the default board.
+fn split_board_prefix(line: &str) -> (Option<u32>, &str) {
+    let trimmed = line.trim_start();
+    let Some(prefix) = trimmed.get(..5) else {
+        return (None, line);
+    };
+    if !prefix.eq_ignore_ascii_case("board") {
+        return (None, line);
+    }
+
+    let rest = trimmed[5..].trim_start();
+    let Some((number, payload)) = rest.split_once(':') else {
+        return (None, line);
+    };
+    match number.trim().parse::<u32>() {
+        Ok(board) => (Some(board), payload.trim_start()),
+        Err(_) => (None, line),
+    }
+}
+
+/// What a single TLCS line changed.
+struct TlcsLineOutcome {
+    board: Option<u32>,
+    moved: bool,
+    recorded: Vec<TlcsMoveRecordedEvent>,
+    sounds: Vec<TlcsSoundEvent>,
+    engine_pvs: Vec<TlcsEnginePvEvent>,
+    desync: Option<TlcsDesyncEvent>,
+    resync: Option<TlcsResyncEvent>,
+    backfill: Option<TlcsBackfillEvent>,
+    appended: Option<TlcsPgnAppendedEvent>,
+    opening: Option<TlcsOpeningEvent>,
+    /// Set when the line played the first move of a game.
+    started: bool,
+    /// Set when the line ended the game.
+    finished: Option<TlcsFinishedGame>,
+    /// Set when the line reached the first of a run of positions in which a
+    /// draw can be claimed.
+    draw_claim: Option<TlcsDrawClaimEvent>,
+}
+
+impl TlcsLineOutcome {
+    fn unchanged(board: Option<u32>) -> Self {
+        Self {
+            board,
+            moved: false,
+            recorded: Vec::new(),
+            sounds: Vec::new(),
+            engine_pvs: Vec::new(),
+            desync: None,
+            resync: None,
+            backfill: None,
+            appended: None,
+            opening: None,
+            started: false,
+            finished: None,
+            draw_claim: None,
+        }
+    }
+}
+
+/// Routes the lines of a single TLCS feed to one recorder per board, so relays
+/// that interleave several games on one socket produce one PGN per board.
+struct TlcsDemux {
+    options: TlcsConnectOptions,
+    log: RotatingLog,
+    /// Continue board PGNs left by an earlier session instead of overwriting.
+    resume: bool,
+    default: TlcsRecorder,
+    boards: BTreeMap<u32, TlcsRecorder>,
+    /// Players of the numbered boards, from a pairing file.
+    pairings: BTreeMap<u32, TlcsPairing>,
+    /// Lines skipped since recording was paused.
+    paused: Option<usize>,
+    dedup: TlcsDedup,
+    /// Boards whose current position allows claiming a draw.
+    claimable: BTreeSet<Option<u32>>,
+}
+
+impl TlcsDemux {
+    fn new(
+        default: TlcsRecorder,
+        options: TlcsConnectOptions,
+        log: RotatingLog,
+        resume: bool,
+    ) -> Self {
+        Self {
+            options,
+            log,
+            resume,
+            default,
+            boards: BTreeMap::new(),
+            pairings: BTreeMap::new(),
+            paused: None,
+            dedup: TlcsDedup::default(),
+            claimable: BTreeSet::new(),
+        }
+    }
+
+    /// Replaces the pairings and fills in the players of the boards
+    /// already recorded.
+    fn set_pairings(&mut self, pairings: Vec<TlcsPairing>) -> Result<(), Error> {
+        self.pairings = pairings
+            .into_iter()
+            .map(|pairing| (pairing.board, pairing))
+            .collect();
+        self.log
+            .info(&format!("Loaded {} pairings", self.pairings.len()));
+        for (board, recorder) in &mut self.boards {
+            if let Some(pairing) = self.pairings.get(board) {
+                recorder.apply_pairing(pairing)?;
+            }
+        }
+        if self.options.deduplicate {
+            self.merge_duplicates();
+        }
+        Ok(())
+    }
+
+    /// Merges the boards that turned out to carry the same unfinished game
+    /// into the one that recorded the most moves.
+    fn merge_duplicates(&mut self) {
+        let mut games: BTreeMap<GameKey, Vec<(u32, usize)>> = BTreeMap::new();
+        for (board, recorder) in &self.boards {
+            if recorder.result.is_some() {
+                continue;
+            }
+            if let Some(key) = recorder_key(recorder) {
+                games
+                    .entry(key)
+                    .or_default()
+                    .push((*board, recorder.moves_recorded()));
+            }
+        }
+        for boards in games.into_values().filter(|boards| boards.len() > 1) {
+            let Some(&(kept, kept_plies)) = boards
+                .iter()
+                .max_by_key(|(board, plies)| (*plies, std::cmp::Reverse(*board)))
+            else {
+                continue;
+            };
+            for &(board, plies) in boards.iter().filter(|(board, _)| *board != kept) {
+                if let Some(recorder) = self.boards.remove(&board) {
+                    self.log.info(&format!(
+                        "Board {board} carries the game of board {kept}, {} is no longer updated",
+                        recorder.pgn_path().to_string_lossy()
+                    ));
+                }
+                self.dedup.merge(board, kept, plies, kept_plies);
+            }
+        }
+    }
+
+    /// The recorded board with the same unfinished game as a new `board`.
+    fn duplicated_board(&self, board: u32) -> Result<Option<u32>, Error> {
+        if !self.options.deduplicate {
+            return Ok(None);
+        }
+        let (white, black) = match self.pairings.get(&board) {
+            Some(pairing) => (pairing.white.name.as_str(), pairing.black.name.as_str()),
+            None => (
+                self.options.white.as_deref().unwrap_or_default(),
+                self.options.black.as_deref().unwrap_or_default(),
+            ),
+        };
+        let start = self
+            .options
+            .variant
+            .unwrap_or_default()
+            .start_position(self.options.initial_fen.as_deref())?;
+        let Some(key) = game_key(white, black, position_key(&start)) else {
+            return Ok(None);
+        };
+        Ok(self
+            .boards
+            .iter()
+            .find(|(_, recorder)| {
+                recorder.result.is_none() && recorder_key(recorder).as_ref() == Some(&key)
+            })
+            .map(|(board, _)| *board))
+    }
+
+    /// Stops applying lines until `resume_recording`. Returns `false` when
+    /// recording was already paused.
+    fn pause_recording(&mut self) -> bool {
+        if self.paused.is_some() {
+            return false;
+        }
+        self.paused = Some(0);
+        self.log.info("Recording paused");
+        true
+    }
+
+    /// Returns `false` when recording was not paused.
+    fn resume_recording(&mut self) -> bool {
+        let Some(skipped) = self.paused.take() else {
+            return false;
+        };
+        self.log.info(&format!(
+            "Recording resumed, {skipped} lines received while paused were skipped"
+        ));
+        true
+    }
+
+    fn recorder(&self, board: Option<u32>) -> Option<&TlcsRecorder> {
+        match board {
+            Some(board) => self.boards.get(&self.dedup.target(board)),
+            None => Some(&self.default),
+        }
+    }
+
+    fn board_path(&self, board: u32) -> PathBuf {
+        let base = self.default.pgn_path();
+        let stem = base
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "tlcs".into());
+        base.with_file_name(format!("{stem}-board{board}.pgn"))
+    }
+
+    fn recorder_mut(&mut self, board: Option<u32>) -> Result<&mut TlcsRecorder, Error> {
+        let Some(board) = board.map(|board| self.dedup.target(board)) else {
+            return Ok(&mut self.default);
+        };
+
+        if !self.boards.contains_key(&board) {
+            if let Some(original) = self.duplicated_board(board)? {
+                self.log.info(&format!(
+                    "Board {board} carries the game of board {original}, merging them"
+                ));
+                let plies = self.boards[&original].moves_recorded();
+                self.dedup.merge(board, original, 0, plies);
+                return Ok(self.boards.get_mut(&original).unwrap());
+            }
+            let path = self.board_path(board);
+            self.log.info(&format!(
+                "Recording board {board} to {}",
+                path.to_string_lossy()
+            ));
+            let mut recorder = if self.resume && path.exists() {
+                TlcsRecorder::resume(
+                    path,
+                    &self.options,
+                    self.log.clone(),
+                    self.default.writer.clone(),
+                )?
+            } else {
+                TlcsRecorder::new(
+                    path,
+                    &self.options,
+                    Some(board),
+                    self.log.clone(),
+                    self.default.writer.clone(),
+                )?
+            };
+            if let Some(pairing) = self.pairings.get(&board) {
+                recorder.apply_pairing(pairing)?;
+            }
+            self.boards.insert(board, recorder);
+        }
+        Ok(self.boards.get_mut(&board).unwrap())
+    }
+
+    /// Returns the board the line was routed to and whether it added moves.
+    fn append_line(&mut self, line: &str) -> Result<TlcsLineOutcome, Error> {
+        let (source, payload) = split_board_prefix(line);
+        if let Some(skipped) = self.paused.as_mut() {
+            *skipped += 1;
+            return Ok(TlcsLineOutcome::unchanged(source));
+        }
+        self.recorder_mut(source)?;
+        let board = source.map(|source| self.dedup.target(source));
+        if let (Some(source), Some(board)) = (source, board) {
+            if !self.dedup.accept(source, payload, &self.boards[&board]) {
+                return Ok(TlcsLineOutcome::unchanged(Some(board)));
+            }
+        }
+        let recorder = self.recorder_mut(board)?;
+        let before = (recorder.moves_recorded(), recorder.is_finished());
+        let was_started = recorder.started_at.is_some();
+        let was_desynced = recorder.desync.is_some();
+        recorder.append_moves_from_line(payload)?;
+        // A corrected move leaves the number of plies unchanged, so the moves
+        // played count as well.
+        let moved = recorder.moves_recorded() != before.0 || !recorder.recorded.is_empty();
+        let changed = moved || before.1 != recorder.is_finished();
+
+        let opening = if changed {
+            recorder
+                .update_opening()?
+                .map(|(eco, name)| TlcsOpeningEvent { board, eco, name })
+        } else {
+            None
+        };
+        let resync = recorder
+            .resync
+            .take()
+            .map(|(action, description)| TlcsResyncEvent {
+                board,
+                fen: recorder.fen(),
+                action,
+                description,
+            });
+        let backfill = recorder
+            .backfill
+            .take()
+            .map(|(from_ply, backfilled, replaced)| TlcsBackfillEvent {
+                board,
+                from_ply,
+                backfilled,
+                replaced,
+            });
+        let desync = match &recorder.desync {
+            Some((line, reason)) if !was_desynced => Some(TlcsDesyncEvent {
+                board,
+                line: line.clone(),
+                fen: recorder.fen(),
+                reason: reason.clone(),
+            }),
+            _ => None,
+        };
+        let appended = changed.then(|| {
+            // Plies recorded again after a takeback count from the first of
+            // them.
+            let since_ply = recorder
+                .recorded
+                .first()
+                .map_or(recorder.moves_recorded(), |event| event.ply - 1)
+                .min(before.0);
+            recorder.pgn_delta(board, since_ply)
+        });
+        let recorded = recorder
+            .recorded
+            .drain(..)
+            .map(|event| TlcsMoveRecordedEvent { board, ..event })
+            .collect();
+        let sounds = recorder
+            .sounds
+            .drain(..)
+            .map(|event| TlcsSoundEvent { board, ..event })
+            .collect();
+        let engine_pvs = recorder
+            .engine_pvs
+            .drain(..)
+            .map(|event| TlcsEnginePvEvent { board, ..event })
+            .collect();
+        let finished = if before.1 {
+            None
+        } else {
+            recorder.finished_game(board)
+        };
+        let claim = if moved {
+            let status = recorder.draw_status();
+            status.claim().map(|claim| TlcsDrawClaimEvent {
+                board,
+                ply: recorder.moves_recorded(),
+                fen: recorder.fen(),
+                claim,
+                repetition_count: status.repetition_count,
+                halfmove_clock: status.halfmove_clock,
+            })
+        } else {
+            None
+        };
+        let mut outcome = TlcsLineOutcome {
+            board,
+            moved,
+            recorded,
+            sounds,
+            engine_pvs,
+            desync,
+            resync,
+            backfill,
+            appended,
+            opening,
+            started: !was_started && recorder.started_at.is_some(),
+            finished,
+            draw_claim: None,
+        };
+        if moved {
+            match claim {
+                Some(claim) if self.claimable.insert(board) => outcome.draw_claim = Some(claim),
+                Some(_) => {}
+                None => {
+                    self.claimable.remove(&board);
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Concatenates the PGN of every recorded board into one multi-game PGN.
+    fn live_pgn(&self) -> String {
+        let default =
+            (self.boards.is_empty() || self.default.moves_recorded() > 0).then_some(&self.default);
+        default
+            .into_iter()
+            .chain(self.boards.values())
+            .map(|recorder| recorder.render())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Like `live_pgn`, but also includes the games archived on each board.
+    fn session_pgn(&self) -> String {
+        std::iter::once(&self.default)
+            .chain(self.boards.values())
+            .map(|recorder| format!("{}{}", recorder.archived, recorder.render()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn board_statuses(&self) -> Vec<TlcsBoardStatus> {
+        self.boards
+            .iter()
+            .map(|(board, recorder)| TlcsBoardStatus {
+                board: *board,
+                pgn_path: recorder.pgn_path().to_string_lossy().to_string(),
+                moves_recorded: recorder.moves_recorded(),
+            })
+            .collect()
+    }
+
+    /// Terminates every unfinished game with `*` and queues the final PGNs.
+    fn finish_unfinished(&mut self) {
+        for recorder in std::iter::once(&mut self.default).chain(self.boards.values_mut()) {
+            if recorder.result.is_none() {
+                recorder.finish("*");
+                let _ = recorder.persist();
+            }
+        }
+    }
+
+    /// Terminates the started games left without a result by a feed that
+    /// went quiet, with the result of their final position when it has one
+    /// and `*` otherwise.
+    fn finish_idle(&mut self) -> Vec<TlcsFinishedGame> {
+        let boards = self
+            .boards
+            .iter_mut()
+            .map(|(board, recorder)| (Some(*board), recorder));
+        let mut finished = Vec::new();
+        for (board, recorder) in std::iter::once((None, &mut self.default)).chain(boards) {
+            if recorder.result.is_some() || recorder.moves.is_empty() {
+                continue;
+            }
+            let result = recorder
+                .position
+                .outcome()
+                .map_or_else(|| "*".to_string(), |outcome| outcome.to_string());
+            recorder.finish(&result);
+            recorder.idle_finished = result == "*";
+            let _ = recorder.persist();
+            finished.extend(recorder.finished_game(board));
+        }
+        finished
+    }
+}
+
+pub struct TlcsHandle {
+    shutdown: watch::Sender<bool>,
+    task: tokio::task::JoinHandle<()>,
+    recorder: Arc<RwLock<TlcsDemux>>,
+    analysis: Option<Arc<LiveAnalysis>>,
+    broadcast: Option<Arc<TlcsBroadcastPush>>,
+    upload: Option<TlcsUpload>,
+    webhooks: Option<Arc<TlcsWebhooks>>,
+    novelty: Option<Arc<TlcsNoveltyWatch>>,
+    book: Option<Arc<TlcsBookWatch>>,
+    kibitzer: Arc<RwLock<Option<TlcsKibitzer>>>,
+    /// Analysis tabs the recorded moves are added to.
+    followers: Arc<TlcsFollowers>,
+    health: Arc<TlcsSessionHealth>,
+    /// Lets the session be resumed after a crash, until it is stopped.
+    record: Option<Arc<TlcsSessionRecord>>,
+    events: TlcsEmitter,
+    log: RotatingLog,
+}
+
+impl TlcsHandle {
+    async fn stop(self) {
+        self.shut_down(false).await;
+    }
+
+    /// Stops the session like `stop`, and also terminates the unfinished
+    /// games, for when the recording will not be resumed by this process.
+    async fn finalize(self) {
+        self.shut_down(true).await;
+    }
+
+    async fn shut_down(self, finalize: bool) {
+        let _ = self.shutdown.send(true);
+        let log = &self.log;
+        let mut task = self.task;
+        let stopped = stop_within(log, "stream", async {
+            let _ = (&mut task).await;
+        })
+        .await;
+        if !stopped {
+            // A stream that ignores the stop request would otherwise keep
+            // the parts below alive, and they would never be stopped.
+            task.abort();
+            let _ = task.await;
+        }
+        if let Some(analysis) = self.analysis.and_then(Arc::into_inner) {
+            stop_within(log, "live analysis", analysis.stop()).await;
+        }
+        if let Some(broadcast) = self.broadcast.and_then(Arc::into_inner) {
+            stop_within(log, "broadcast", broadcast.stop()).await;
+        }
+        if let Some(webhooks) = self.webhooks.and_then(Arc::into_inner) {
+            stop_within(log, "webhooks", webhooks.stop()).await;
+        }
+        if let Some(novelty) = self.novelty.and_then(Arc::into_inner) {
+            stop_within(log, "novelty check", novelty.stop()).await;
+        }
+        if let Some(book) = self.book.and_then(Arc::into_inner) {
+            stop_within(log, "book watch", book.stop()).await;
+        }
+        if let Some(kibitzer) = self.kibitzer.write().await.take() {
+            stop_within(log, "kibitzer", kibitzer.stop()).await;
+        }
+        let writer = {
+            let mut recorder = self.recorder.write().await;
+            if finalize {
+                recorder.finish_unfinished();
+            }
+            recorder.default.writer.clone()
+        };
+        writer.flush().await;
+        if let Some(record) = self.record {
+            if let Err(err) = record.remove() {
+                self.log
+                    .error(&format!("Failed to forget the stopped TLCS session: {err}"));
+            }
+        }
+        // Last, so the final upload includes the games finished above.
+        if let Some(upload) = self.upload {
+            stop_within(&self.log, "upload", upload.stop()).await;
+        }
+        self.log.flush().await;
+    }
+}
+
+/// Longest wait for each part of a session to stop, so that an endpoint that
+/// hangs cannot keep the application from closing.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for an HTTP request to a broadcast, webhook or upload
+/// endpoint.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns whether `stop` completed before the timeout.
+async fn stop_within(
+    log: &RotatingLog,
+    part: &str,
+    stop: impl std::future::Future<Output = ()>,
+) -> bool {
+    if tokio::time::timeout(STOP_TIMEOUT, stop).await.is_err() {
+        log.error(&format!("Gave up waiting for the TLCS {part} to stop"));
+        return false;
+    }
+    true
+}
+
+/// The HTTP client of the session's endpoints, which never waits on one
+/// longer than `HTTP_TIMEOUT`.
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Stops every TLCS session before the application exits, so the recorded
+/// PGNs are terminated and fully written.
+pub async fn shutdown_tlcs_sessions(state: &AppState) {
+    if let Some(handle) = state.tlcs_handle.write().await.take() {
+        handle
+            .log
+            .info("Application exiting, finalizing TLCS recording");
+        handle.finalize().await;
+    }
+    for handle in state.tlcs_headless_handles.write().await.drain(..) {
+        handle
+            .log
+            .info("Application exiting, finalizing TLCS recording");
+        handle.finalize().await;
+    }
+    state.tlcs.disconnect().await;
+    let _ = state.tlcs_client.write().await.disconnect().await;
+}
+
+/// Identifies a recording session in the log by its PGN file name, so a
+/// resumed session shares the id of the one it continues. Its characters are
+/// limited to those event names allow, for `scope_events`.
+fn session_id(pgn_path: &Path) -> Option<String> {
+    pgn_path
+        .file_stem()
+        .map(|stem| event_safe_id(&stem.to_string_lossy()))
+}
+
+fn session_log(
+    tlcs_dir: &Path,
+    options: &TlcsConnectOptions,
+    pgn_path: &Path,
+) -> Result<RotatingLog, Error> {
+    let log = RotatingLog::new(
+        tlcs_dir.join(LOG_FILE),
+        &options.log_config.clone().unwrap_or_default(),
+        session_id(pgn_path),
+    )?;
+    if let Some(level) = options.log_level {
+        log.set_level(level);
+    }
+    Ok(log)
+}
+
+/// What the commands that start a recording session return.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsSessionStarted {
+    /// Carried by every event of the session, and part of the event names
+    /// when `scope_events` is set.
+    pub session_id: String,
+    pub pgn_path: String,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_tlcs_stream(
+    options: TlcsConnectOptions,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsSessionStarted, Error> {
+    start_session(options, app, &state.tlcs_handle).await
+}
+
+/// Starts recording a session into `slot`, stopping the one it held. The
+/// commands reach the session in `AppState::tlcs_handle`.
+async fn start_session(
+    options: TlcsConnectOptions,
+    app: tauri::AppHandle,
+    slot: &RwLock<Option<TlcsHandle>>,
+) -> Result<TlcsSessionStarted, Error> {
+    let tlcs_dir = app.path().resolve("tlcs", BaseDirectory::AppData)?;
+    create_dir_all(&tlcs_dir)?;
+
+    let pgn_path = options
+        .pgn_path
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            tlcs_dir.join(format!("tlcs-{}.pgn", Utc::now().format("%Y%m%dT%H%M%SZ")))
+        });
+
+    let log = session_log(&tlcs_dir, &options, &pgn_path)?;
+    log.info(&format!(
+        "Starting TLCS stream {}:{} -> {}",
+        options.host,
+        options.port,
+        pgn_path.to_string_lossy()
+    ));
+
+    let writer = PgnWriter::spawn(log.clone());
+    let recorder = TlcsRecorder::new(pgn_path.clone(), &options, None, log.clone(), writer)?;
+    let recorder = TlcsDemux::new(recorder, options.clone(), log.clone(), false);
+    let session_id =
+        spawn_tlcs_stream(recorder, options, TlcsSource::Server, log, app, slot).await?;
+
+    Ok(TlcsSessionStarted {
+        session_id,
+        pgn_path: pgn_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Continues recording into a PGN left by an interrupted session. The moves
+/// already in the file are replayed, and new moves from the stream are
+/// appended after them. `options.pgn_path` is ignored.
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_tlcs_stream(
+    pgn_path: String,
+    options: TlcsConnectOptions,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsSessionStarted, Error> {
+    let tlcs_dir = app.path().resolve("tlcs", BaseDirectory::AppData)?;
+    create_dir_all(&tlcs_dir)?;
+
+    let pgn_path = PathBuf::from(pgn_path);
+    let log = session_log(&tlcs_dir, &options, &pgn_path)?;
+    log.info(&format!(
+        "Resuming TLCS stream {}:{} -> {}",
+        options.host,
+        options.port,
+        pgn_path.to_string_lossy()
+    ));
+
+    let writer = PgnWriter::spawn(log.clone());
+    let recorder = TlcsRecorder::resume(pgn_path.clone(), &options, log.clone(), writer)?;
+    let recorder = TlcsDemux::new(recorder, options.clone(), log.clone(), true);
+    let session_id = spawn_tlcs_stream(
+        recorder,
+        options,
+        TlcsSource::Server,
+        log,
+        app,
+        &state.tlcs_handle,
+    )
+    .await?;
+
+    Ok(TlcsSessionStarted {
+        session_id,
+        pgn_path: pgn_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Records the games of the playing connection (`connect_tlcs`) without
+/// opening a second socket to the server. The host, port and transport in
+/// `options` are ignored.
+#[tauri::command]
+#[specta::specta]
+pub async fn attach_tlcs_recorder(
+    options: TlcsConnectOptions,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsSessionStarted, Error> {
+    let lines = state.tlcs.subscribe_lines().await?;
+    let tlcs_dir = app.path().resolve("tlcs", BaseDirectory::AppData)?;
+    create_dir_all(&tlcs_dir)?;
+
+    let pgn_path = options
+        .pgn_path
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            tlcs_dir.join(format!("tlcs-{}.pgn", Utc::now().format("%Y%m%dT%H%M%SZ")))
+        });
+
+    let log = session_log(&tlcs_dir, &options, &pgn_path)?;
+    log.info(&format!(
+        "Recording the playing connection -> {}",
+        pgn_path.to_string_lossy()
+    ));
+
+    let writer = PgnWriter::spawn(log.clone());
+    let recorder = TlcsRecorder::new(pgn_path.clone(), &options, None, log.clone(), writer)?;
+    let recorder = TlcsDemux::new(recorder, options.clone(), log.clone(), false);
+    let session_id = spawn_tlcs_stream(
+        recorder,
+        options,
+        TlcsSource::Attached(lines),
+        log,
+        app,
+        &state.tlcs_handle,
+    )
+    .await?;
+
+    Ok(TlcsSessionStarted {
+        session_id,
+        pgn_path: pgn_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Where a session reads its lines from.
+enum TlcsSource {
+    /// The TLCS server given in the connect options.
+    Server,
+    /// Lines captured in a log.
+    Replay(TlcsReplay),
+    /// Lines read by the playing connection.
+    Attached(broadcast::Receiver<String>),
+}
+
+/// Passes the lines of the playing connection on through a pipe, so the
+/// stream task reads them like any other source.
+fn pipe_lines(
+    mut lines: broadcast::Receiver<String>,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> DuplexStream {
+    let (reader, mut writer) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        loop {
+            let line = select! {
+                _ = shutdown.changed() => break,
+                line = lines.recv() => line,
+            };
+            match line {
+                Ok(line) => {
+                    if writer
+                        .write_all(format!("{line}\r\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log.error(&format!("Recorder fell behind, {skipped} lines were lost"));
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    reader
+}
+
+async fn spawn_tlcs_stream(
+    recorder: TlcsDemux,
+    options: TlcsConnectOptions,
+    source: TlcsSource,
+    log: RotatingLog,
+    app: tauri::AppHandle,
+    slot: &RwLock<Option<TlcsHandle>>,
+) -> Result<String, Error> {
+    let session_id = session_id(&recorder.default.pgn_path()).unwrap_or_default();
+    let events = TlcsEmitter::new(app.clone(), session_id.clone(), options.scope_events);
+    let capture = match source {
+        TlcsSource::Server
+            if options.capture
+                && options.transport == TlcsTransport::Tcp
+                && options.merge.is_empty() =>
+        {
+            let path = recorder
+                .default
+                .pgn_path()
+                .with_extension(CAPTURE_EXTENSION);
+            log.info(&format!(
+                "Capturing TLCS traffic to {}",
+                path.to_string_lossy()
+            ));
+            Some(TlcsCapture::open(&path)?)
+        }
+        _ => None,
+    };
+    let recorder = Arc::new(RwLock::new(recorder));
+    let (shutdown, mut shutdown_rx) = watch::channel(false);
+    let mut guard = slot.write().await;
+
+    if let Some(handle) = guard.take() {
+        log.info("Stopping existing TLCS session before starting new one");
+        handle.stop().await;
+    }
+
+    // After the previous session is stopped, as a session resumed in its
+    // place shares its file.
+    let record = if matches!(source, TlcsSource::Server) {
+        let recorder = recorder.read().await;
+        match TlcsSessionRecord::create(
+            &app,
+            &session_id,
+            &recorder.default.pgn_path(),
+            recorder.plies_recorded(),
+            &options,
+        ) {
+            Ok(record) => Some(Arc::new(record)),
+            Err(err) => {
+                log.error(&format!(
+                    "Failed to save the TLCS session, it cannot be resumed after a crash: {err}"
+                ));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let analysis = options.live_analysis_engine.as_ref().map(|engine| {
+        log.info(&format!("Starting live analysis with {engine}"));
+        Arc::new(LiveAnalysis::spawn(
+            PathBuf::from(engine),
+            LiveAnalysisSettings {
+                depth: options
+                    .live_analysis_depth
+                    .unwrap_or(DEFAULT_LIVE_ANALYSIS_DEPTH),
+                annotate: options.annotate_eval,
+                blunder_threshold: options.blunder_threshold_cp,
+            },
+            recorder.clone(),
+            events.clone(),
+            log.clone(),
+        ))
+    });
+
+    let novelty = options.reference_db.as_ref().map(|reference| {
+        log.info(&format!("Checking for novelties against {reference}"));
+        Arc::new(TlcsNoveltyWatch::spawn(
+            PathBuf::from(reference),
+            events.clone(),
+            log.clone(),
+        ))
+    });
+
+    let book = (!options.opening_books.is_empty()).then(|| {
+        log.info(&format!(
+            "Probing {} opening books",
+            options.opening_books.len()
+        ));
+        Arc::new(TlcsBookWatch::spawn(
+            options.opening_books.iter().map(PathBuf::from).collect(),
+            events.clone(),
+            log.clone(),
+        ))
+    });
+
+    let broadcast = options.broadcast.clone().map(|broadcast| {
+        log.info(&format!(
+            "Pushing live PGN to Lichess broadcast round {}",
+            broadcast.round_id
+        ));
+        Arc::new(TlcsBroadcastPush::spawn(
+            broadcast,
+            recorder.clone(),
+            events.clone(),
+            log.clone(),
+        ))
+    });
+
+    let upload = options.upload.clone().map(|upload| {
+        log.info("Uploading session PGN periodically");
+        TlcsUpload::spawn(upload, recorder.clone(), log.clone())
+    });
+
+    let webhooks = options
+        .webhooks
+        .clone()
+        .filter(|webhooks| !webhooks.is_empty())
+        .map(|webhooks| {
+            log.info(&format!(
+                "Posting finished games to {} webhooks",
+                webhooks.len()
+            ));
+            Arc::new(TlcsWebhooks::spawn(webhooks, events.clone(), log.clone()))
+        });
+
+    let host = options.host.clone();
+    let port = options.port;
+    let proxy_url = options.proxy_url.clone();
+    let merge_sources = options.merge.clone();
+    let transport = options.transport;
+    let encoding = options.encoding.unwrap_or_default();
+    let fen_settle = options.fen_settle_ms.map(Duration::from_millis);
+    let idle_timeout = options
+        .idle_timeout_mins
+        .map(|minutes| Duration::from_secs(minutes * 60));
+    let ics_options = options.ics.clone().unwrap_or_default();
+    let lichess_options = options.lichess.clone().unwrap_or_default();
+    let chess_com_options = options.chess_com.clone().unwrap_or_default();
+    let dgt_options = options.dgt.clone().unwrap_or_default();
+    let log_clone = log.clone();
+    let recorder_clone = recorder.clone();
+    let analysis_clone = analysis.clone();
+    let broadcast_clone = broadcast.clone();
+    let webhooks_clone = webhooks.clone();
+    let novelty_clone = novelty.clone();
+    let book_clone = book.clone();
+    let kibitzer: Arc<RwLock<Option<TlcsKibitzer>>> = Arc::new(RwLock::new(None));
+    let kibitzer_clone = kibitzer.clone();
+    let followers = Arc::new(TlcsFollowers::default());
+    let followers_clone = followers.clone();
+    let health = Arc::new(TlcsSessionHealth::default());
+    let health_clone = health.clone();
+    let record_clone = record.clone();
+    let overlay = options.overlay.as_ref().map(TlcsOverlay::new);
+    if let Some(overlay) = &overlay {
+        if let Some(board_recorder) = recorder.read().await.recorder(overlay.board) {
+            if let Err(err) = overlay.write(board_recorder) {
+                log.error(&format!("Failed to write TLCS overlay: {err}"));
+            }
+        }
+    }
+    let app_clone = app.clone();
+    let events_clone = events.clone();
+
+    let task = tokio::spawn(async move {
+        let mut connect_stop = shutdown_rx.clone();
+        let connect = async {
+            let stream: std::io::Result<Box<dyn AsyncRead + Send + Unpin>> = match source {
+                TlcsSource::Server if !merge_sources.is_empty() => Ok(Box::new(merge::connect(
+                    merge_sources,
+                    proxy_url,
+                    log_clone.clone(),
+                    shutdown_rx.clone(),
+                ))),
+                TlcsSource::Server if transport == TlcsTransport::TlcvUdp => {
+                    tlcv::connect(&host, port, log_clone.clone(), shutdown_rx.clone())
+                        .await
+                        .map(|stream| Box::new(stream) as _)
+                }
+                TlcsSource::Server if transport == TlcsTransport::Ics => ics::connect(
+                    &host,
+                    port,
+                    proxy_url.as_deref(),
+                    ics_options,
+                    log_clone.clone(),
+                    shutdown_rx.clone(),
+                )
+                .await
+                .map(|stream| Box::new(stream) as _),
+                TlcsSource::Server if transport == TlcsTransport::Lichess => {
+                    lichess::connect(lichess_options, log_clone.clone(), shutdown_rx.clone())
+                        .await
+                        .map(|stream| Box::new(stream) as _)
+                }
+                TlcsSource::Server if transport == TlcsTransport::ChessCom => {
+                    chess_com::connect(chess_com_options, log_clone.clone(), shutdown_rx.clone())
+                        .await
+                        .map(|stream| Box::new(stream) as _)
+                }
+                TlcsSource::Server if transport == TlcsTransport::Dgt => dgt::connect(
+                    dgt_options,
+                    app_clone.clone(),
+                    log_clone.clone(),
+                    shutdown_rx.clone(),
+                )
+                .await
+                .map(|stream| Box::new(stream) as _),
+                TlcsSource::Server => {
+                    connect_tcp(&host, port, proxy_url.as_deref())
+                        .await
+                        .map(|stream| {
+                            if let Ok(address) = stream.peer_addr() {
+                                log_clone.info(&format!("Reached {host}:{port} at {address}"));
+                            }
+                            Box::new(CaptureReader::new(stream, capture)) as _
+                        })
+                }
+                TlcsSource::Replay(replay) => Ok(Box::new(replay.spawn(shutdown_rx.clone()))),
+                TlcsSource::Attached(lines) => Ok(Box::new(pipe_lines(
+                    lines,
+                    log_clone.clone(),
+                    shutdown_rx.clone(),
+                ))),
+            };
+            stream
+        };
+        // Connecting can take long, so a stop must not wait for it to finish.
+        let stream = tokio::select! {
+            stream = connect => stream,
+            _ = connect_stop.changed() => {
+                log_clone.info("TLCS stream stop requested while connecting");
+                return;
+            }
+        };
+        match stream {
+            Ok(stream) => {
+                log_clone.info("Connected to TLCS server");
+                health_clone.set_connected(true);
+                let mut reader = SettledLines::new(
+                    DecodedLines::new(BufReader::new(stream), encoding),
+                    fen_settle,
+                );
+                let mut last_move = tokio::time::Instant::now();
+                let mut idle_finished = false;
+
+                loop {
+                    tokio::select! {
+                        _ = shutdown_rx.changed() => {
+                            log_clone.info("TLCS stream stop requested");
+                            break;
+                        }
+                        _ = tokio::time::sleep_until(last_move + idle_timeout.unwrap_or_default()), if idle_timeout.is_some() && !idle_finished => {
+                            idle_finished = true;
+                            let finished = recorder_clone.write().await.finish_idle();
+                            if !finished.is_empty() {
+                                log_clone.info(&format!("No moves for {} minutes, finishing {} idle games", idle_timeout.unwrap_or_default().as_secs() / 60, finished.len()));
+                                if let Some(broadcast) = &broadcast_clone {
+                                    broadcast.notify_move();
+                                }
+                            }
+                            for game in finished {
+                                app_clone.state::<AppState>().tlcs_notifier.game_finished(&app_clone, &game);
+                                if let Some(webhooks) = &webhooks_clone {
+                                    webhooks.notify(game);
+                                }
+                            }
+                        }
+                        line = reader.next_line() => {
+                            match line {
+                                Ok(Some(l)) => {
+                                    log_clone.received(&l);
+                                    health_clone.line_received();
+                                    let mut recorder = recorder_clone.write().await;
+                                    match recorder.append_line(&l) {
+                                        Ok(outcome) => {
+                                            let tabs = followers_clone.tabs(outcome.board);
+                                            for recorded in outcome.recorded {
+                                                for tab_id in &tabs {
+                                                    events_clone.emit_unscoped(
+                                                        "tlcs-tab-move",
+                                                        TlcsTabMoveEvent {
+                                                            tab_id: tab_id.clone(),
+                                                            recorded: recorded.clone(),
+                                                        },
+                                                    );
+                                                }
+                                                events_clone.emit("tlcs-move-recorded", recorded);
+                                            }
+                                            for sound in outcome.sounds {
+                                                events_clone.emit("tlcs-sound", sound);
+                                            }
+                                            for pv in outcome.engine_pvs {
+                                                events_clone.emit("tlcs-engine-pv", pv);
+                                            }
+                                            if let Some(appended) = outcome.appended {
+                                                events_clone.emit("tlcs-pgn-appended", appended);
+                                            }
+                                            if outcome.moved {
+                                                last_move = tokio::time::Instant::now();
+                                                idle_finished = false;
+                                                if let Some(broadcast) = &broadcast_clone {
+                                                    broadcast.notify_move();
+                                                }
+                                                if let Some(record) = &record_clone {
+                                                    if let Err(err) = record.update(recorder.plies_recorded()) {
+                                                        log_clone.error(&format!("Failed to save the TLCS session: {err}"));
+                                                    }
+                                                }
+                                                if let Some(board_recorder) = recorder.recorder(outcome.board) {
+                                                    if let Some(novelty) = &novelty_clone {
+                                                        novelty.check(
+                                                            outcome.board,
+                                                            board_recorder.moves_recorded(),
+                                                            board_recorder.sans.last().cloned().unwrap_or_default(),
+                                                            board_recorder.fen(),
+                                                        );
+                                                    }
+                                                    if let Some(book) = &book_clone {
+                                                        book.check(outcome.board, board_recorder.moves_recorded(), board_recorder.fen());
+                                                    }
+                                                    let options = board_recorder.analysis_options();
+                                                    if let Some(kibitzer) = kibitzer_clone.read().await.as_ref() {
+                                                        kibitzer.analyze(outcome.board, options.fen.clone(), options.moves.clone());
+                                                    }
+                                                    if let Some(analysis) = &analysis_clone {
+                                                        analysis.analyze(outcome.board, options.fen, options.moves);
+                                                    }
+                                                }
+                                            }
+                                            if let Some(overlay) = overlay.as_ref().filter(|o| o.board == outcome.board) {
+                                                if let Some(board_recorder) = recorder.recorder(outcome.board) {
+                                                    if let Err(err) = overlay.write(board_recorder) {
+                                                        log_clone.error(&format!("Failed to write TLCS overlay: {err}"));
+                                                    }
+                                                }
+                                            }
+                                            if let Some(desync) = outcome.desync {
+                                                events_clone.emit("tlcs-desync", desync);
+                                            }
+                                            if let Some(resync) = outcome.resync {
+                                                events_clone.emit("tlcs-resync", resync);
+                                            }
+                                            if let Some(backfill) = outcome.backfill {
+                                                events_clone.emit("tlcs-backfill", backfill);
+                                            }
+                                            if let Some(opening) = outcome.opening {
+                                                events_clone.emit("tlcs-opening", opening);
+                                            }
+                                            if let Some(draw_claim) = outcome.draw_claim {
+                                                events_clone.emit("tlcs-draw-claim", draw_claim);
+                                            }
+                                            if outcome.started {
+                                                if let Some(board_recorder) = recorder.recorder(outcome.board) {
+                                                    app_clone.state::<AppState>().tlcs_notifier.game_started(
+                                                        &app_clone,
+                                                        outcome.board,
+                                                        board_recorder.headers.get("White").map(String::as_str),
+                                                        board_recorder.headers.get("Black").map(String::as_str),
+                                                    );
+                                                }
+                                            }
+                                            if let Some(finished) = outcome.finished {
+                                                app_clone.state::<AppState>().tlcs_notifier.game_finished(&app_clone, &finished);
+                                                if let Some(webhooks) = &webhooks_clone {
+                                                    webhooks.notify(finished);
+                                                }
+                                            }
+                                        }
+                                        Err(err) => {
+                                            health_clone.parse_failed();
+                                            log_clone.error(&format!("Failed to parse TLCS line: {err}"));
+                                        }
+                                    }
+                                }
+                                Ok(None) => {
+                                    log_clone.info("TLCS stream closed by server");
+                                    app_clone
+                                        .state::<AppState>()
+                                        .tlcs_notifier
+                                        .disconnected(&app_clone, "TLCS stream closed by server");
+                                    break;
+                                }
+                                Err(err) => {
+                                    log_clone.error(&format!("TLCS stream read error: {err}"));
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                health_clone.set_connected(false);
+            }
+            Err(err) => {
+                log_clone.error(&format!("Unable to connect to TLCS server: {err}"));
+            }
+        }
+    });
+
+    *guard = Some(TlcsHandle {
+        shutdown,
+        task,
+        recorder,
+        analysis,
+        broadcast,
+        upload,
+        webhooks,
+        novelty,
+        book,
+        kibitzer,
+        followers,
+        health,
+        record,
+        events,
+        log,
+    });
+
+    Ok(session_id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_tlcs_stream(state: tauri::State<'_, AppState>) -> Result<Option<String>, Error> {
+    let mut guard = state.tlcs_handle.write().await;
+    if let Some(handle) = guard.take() {
+        let path = {
+            let recorder = handle.recorder.read().await;
+            recorder.default.pgn_path()
+        };
+        handle.log.info("Stopping TLCS stream");
+        handle.stop().await;
+        return Ok(Some(path.to_string_lossy().to_string()));
+    }
+    Ok(None)
+}
+
+/// The running session, checked against `session` when the caller names one.
+async fn running_session<'a>(
+    guard: &'a Option<TlcsHandle>,
+    session: Option<&str>,
+) -> Result<&'a TlcsHandle, Error> {
+    let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+    if let Some(session) = session {
+        let pgn_path = handle.recorder.read().await.default.pgn_path();
+        if session_id(&pgn_path).as_deref() != Some(session) {
+            return Err(Error::TlcsNotRecording);
+        }
+    }
+    Ok(handle)
+}
+
+/// Stops recording moves in `session` (the running session when `None`)
+/// while its connection stays open, for instance while an arbiter fixes a
+/// position on the relay. Lines received meanwhile only go to the session
+/// log. Returns `false` when recording was already paused.
+#[tauri::command]
+#[specta::specta]
+pub async fn pause_tlcs_recording(
+    session: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = running_session(&guard, session.as_deref()).await?;
+    let paused = handle.recorder.write().await.pause_recording();
+    Ok(paused)
+}
+
+/// Resumes recording moves in a session paused by `pause_tlcs_recording`.
+/// Returns `false` when it was not paused.
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_tlcs_recording(
+    session: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = running_session(&guard, session.as_deref()).await?;
+    let resumed = handle.recorder.write().await.resume_recording();
+    Ok(resumed)
+}
+
+/// Restarts the recording of `board` (the default board when `None`) from
+/// `fen`, after the operator has checked the real position. The moves
+/// recorded so far are kept as a separate game.
+#[tauri::command]
+#[specta::specta]
+pub async fn force_set_position(
+    fen: String,
+    board: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+    let mut recorder = handle.recorder.write().await;
+    recorder.recorder_mut(board)?.restart_from(&fen)
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+pub struct TlcsConnectionEvent {
+    pub status: TlcsConnectionStatus,
+    pub message: Option<String>,
+    /// Set while waiting to reconnect.
+    pub retry: Option<TlcsRetryInfo>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Type)]
+pub enum TlcsConnectionStatus {
+    Disconnected,
+    Connecting,
+    Connected,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event, Default)]
+pub struct TlcsGameEvent {
+    pub state: TlcsGameState,
+    pub raw: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum TlcsSide {
+    White,
+    Black,
+}
+
+impl From<TlcsSide> for Color {
+    fn from(side: TlcsSide) -> Self {
+        match side {
+            TlcsSide::White => Color::White,
+            TlcsSide::Black => Color::Black,
+        }
+    }
+}
+
+/// Locally simulated clocks, emitted every `CLOCK_TICK` between server
+/// updates.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+pub struct TlcsClockEvent {
+    pub white_clock_ms: Option<u64>,
+    pub black_clock_ms: Option<u64>,
+    pub running: Option<TlcsSide>,
+}
+
+/// Emitted once when the simulated clock of `side` reaches zero, before the
+/// server confirms the flag.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+pub struct TlcsFlagEvent {
+    pub side: TlcsSide,
+}
+
+/// How often the simulated clocks are emitted.
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// Time control of the played game, so the clocks can be kept running
+/// between `clock` lines of servers that send them rarely.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsClockConfig {
+    /// Starting time of each side.
+    pub base_ms: u64,
+    /// Fischer increment, added after every move.
+    #[serde(default)]
+    pub increment_ms: u64,
+    /// Bronstein delay: up to this much of the time spent on a move is
+    /// given back after it.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Counts down the clock of the side to move between `clock` lines.
+struct ClockSimulation {
+    white_ms: Option<u64>,
+    black_ms: Option<u64>,
+    running: Option<TlcsSide>,
+    synced_at: tokio::time::Instant,
+    flagged: bool,
+    config: Option<TlcsClockConfig>,
+}
+
+impl ClockSimulation {
+    fn new(config: Option<TlcsClockConfig>) -> Self {
+        let base_ms = config.map(|config| config.base_ms);
+        Self {
+            white_ms: base_ms,
+            black_ms: base_ms,
+            running: None,
+            synced_at: tokio::time::Instant::now(),
+            flagged: false,
+            config,
+        }
+    }
+
+    fn remaining(&self) -> (Option<u64>, Option<u64>) {
+        let elapsed = self.synced_at.elapsed().as_millis() as u64;
+        let tick = |side: TlcsSide, ms: Option<u64>| {
+            if self.running == Some(side) {
+                ms.map(|ms| ms.saturating_sub(elapsed))
+            } else {
+                ms
+            }
+        };
+        (
+            tick(TlcsSide::White, self.white_ms),
+            tick(TlcsSide::Black, self.black_ms),
+        )
+    }
+
+    /// Updates the simulation after a `clock` or `fen` line.
+    fn sync(&mut self, state: &TlcsGameState, line: &str) {
+        let line = line.trim();
+        if line.starts_with("clock ") {
+            self.white_ms = state.white_clock_ms;
+            self.black_ms = state.black_clock_ms;
+        } else if line.starts_with("fen ") {
+            let (white_ms, black_ms) = self.remaining();
+            self.white_ms = white_ms;
+            self.black_ms = black_ms;
+            let moved = self.running;
+            self.running = state
+                .fen
+                .as_deref()
+                .and_then(|fen| fen.split_whitespace().nth(1))
+                .and_then(|turn| match turn {
+                    "w" => Some(TlcsSide::White),
+                    "b" => Some(TlcsSide::Black),
+                    _ => None,
+                });
+            if let (Some(config), Some(moved)) = (self.config, moved) {
+                if self.running != Some(moved) {
+                    self.credit(moved, config);
+                }
+            }
+        } else {
+            return;
+        }
+        self.synced_at = tokio::time::Instant::now();
+        self.flagged = false;
+    }
+
+    /// Adds the increment and the delay refund the side that just moved
+    /// earns once its move is made.
+    fn credit(&mut self, side: TlcsSide, config: TlcsClockConfig) {
+        let spent = self.synced_at.elapsed().as_millis() as u64;
+        let credit = spent.min(config.delay_ms) + config.increment_ms;
+        let ms = match side {
+            TlcsSide::White => &mut self.white_ms,
+            TlcsSide::Black => &mut self.black_ms,
+        };
+        if let Some(ms) = ms.as_mut().filter(|ms| **ms > 0) {
+            *ms += credit;
+        }
+    }
+
+    fn tick(&mut self, events: &TlcsEmitter) {
+        let Some(running) = self.running else {
+            return;
+        };
+        let (white_clock_ms, black_clock_ms) = self.remaining();
+        events.emit(
+            "tlcs-clock",
+            TlcsClockEvent {
+                white_clock_ms,
+                black_clock_ms,
+                running: Some(running),
+            },
+        );
+
+        let running_ms = match running {
+            TlcsSide::White => white_clock_ms,
+            TlcsSide::Black => black_clock_ms,
+        };
+        if running_ms == Some(0) && !self.flagged {
+            self.flagged = true;
+            events.emit("tlcs-flag", TlcsFlagEvent { side: running });
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Type, Default)]
+pub struct TlcsGameState {
+    pub fen: Option<String>,
+    pub white_clock_ms: Option<u64>,
+    pub black_clock_ms: Option<u64>,
+    pub status: Option<String>,
+    pub last_move: Option<String>,
+    pub can_offer_draw: bool,
+    pub can_accept_draw: bool,
+    pub can_resign: bool,
+    /// Times the current position occurred in the game.
+    pub repetition_count: u32,
+    /// Plies since the last capture or pawn move, from the FEN.
+    pub halfmove_clock: u32,
+    pub material: Option<TlcsMaterial>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsConnectArgs {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub auto_reconnect: bool,
+    pub reconnect_interval_ms: u64,
+    /// Replaces `reconnect_interval_ms` when set.
+    #[serde(default)]
+    pub reconnect_policy: Option<ReconnectPolicy>,
+    /// Treat the connection as dead when nothing is received for this long.
+    pub stale_timeout_ms: Option<u64>,
+    /// `socks5://` or `http://` proxy to reach the TLCS server through.
+    pub proxy_url: Option<String>,
+    /// File to append every line sent to and received from the server to.
+    pub capture_path: Option<String>,
+    /// Allow the arbiter commands (results, clocks, aborts) on this
+    /// connection.
+    #[serde(default)]
+    pub arbiter: bool,
+    /// Name of the registered line parser to read the server with. TLCS
+    /// when unset.
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Time control used to run the clocks locally between server updates.
+    #[serde(default)]
+    pub clock: Option<TlcsClockConfig>,
+    /// Spaces out the commands sent to the server.
+    #[serde(default)]
+    pub rate_limit: Option<TlcsRateLimit>,
+    /// Character set of the server's lines. UTF-8 when unset.
+    #[serde(default)]
+    pub encoding: Option<TlcsInputEncoding>,
+    /// Patterns masked in the raw lines of game events, on top of the
+    /// credentials.
+    #[serde(default)]
+    pub redact: Vec<TlcsRedactRule>,
+    /// Hold resignations, draw offers, draw acceptances, draw claims, aborts
+    /// and adjournments this long before sending them, so
+    /// `cancel_pending_action` can take back a misclick.
+    #[serde(default)]
+    pub confirm_window_ms: Option<u64>,
+    /// Emit the connection's events as `tlcs-game/{session id}` and so on,
+    /// instead of under their plain names.
+    #[serde(default)]
+    pub scope_events: bool,
+    /// Commands sent on their own on connect, after login or when a new
+    /// game starts.
+    #[serde(default)]
+    pub scripts: Vec<TlcsScript>,
+}
+
+impl std::fmt::Debug for TlcsConnectArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlcsConnectArgs")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &"***")
+            .field("auto_reconnect", &self.auto_reconnect)
+            .field("reconnect_interval_ms", &self.reconnect_interval_ms)
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("stale_timeout_ms", &self.stale_timeout_ms)
+            .field("proxy_url", &self.proxy_url.as_ref().map(|_| "***"))
+            .field("capture_path", &self.capture_path)
+            .field("arbiter", &self.arbiter)
+            .field("protocol", &self.protocol)
+            .field("clock", &self.clock)
+            .field("rate_limit", &self.rate_limit)
+            .field("encoding", &self.encoding)
+            .field("redact", &self.redact)
+            .field("confirm_window_ms", &self.confirm_window_ms)
+            .field("scope_events", &self.scope_events)
+            .field("scripts", &self.scripts)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+pub enum TlcsUserAction {
+    AcceptOffer,
+    OfferDraw,
+    Resign,
+    DeclineDraw,
+    RequestReconnect,
+    /// End the game without a result, usually only allowed before both
+    /// sides have moved.
+    Abort,
+    Adjourn,
+    ClaimDraw(TlcsDrawClaim),
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsPendingActionState {
+    Queued,
+    Sent,
+    Cancelled,
+}
+
+/// Emitted when an action is held back for the confirmation window, and
+/// when it is then sent or cancelled.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPendingActionEvent {
+    pub action: TlcsUserAction,
+    pub state: TlcsPendingActionState,
+    /// Time left before the action is sent, while it is queued.
+    pub delay_ms: Option<u64>,
+}
+
+/// An action waiting out the confirmation window.
+struct PendingAction {
+    action: TlcsUserAction,
+    task: tokio::task::JoinHandle<()>,
+}
+
+enum TlcsControl {
+    Send(String),
+    Disconnect,
+    Reconnect,
+}
+
+pub struct TlcsManager {
+    handle: Mutex<Option<TlcsConnectionHandle>>,
+    last_options: Mutex<Option<TlcsConnectArgs>>,
+    parsers: Mutex<TlcsParserRegistry>,
+    /// Every line read by the connection, for attached recorders.
+    lines: broadcast::Sender<String>,
+    pending: Arc<Mutex<Option<PendingAction>>>,
+}
+
+impl Default for TlcsManager {
+    fn default() -> Self {
+        Self {
+            handle: Mutex::new(None),
+            last_options: Mutex::new(None),
+            parsers: Mutex::new(TlcsParserRegistry::default()),
+            lines: broadcast::channel(ATTACHED_LINES_CAPACITY).0,
+            pending: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+/// Lines an attached recorder may fall behind by before it loses some.
+const ATTACHED_LINES_CAPACITY: usize = 1024;
+
+/// Where a connection passes the lines it reads, besides its events.
+struct LineConsumers {
+    parser: Arc<dyn TlcsLineParser>,
+    recorders: broadcast::Sender<String>,
+}
+
+/// Asks the server to restate the move list of every game. Sent after a
+/// reconnect, so the moves missed meanwhile are backfilled.
+const BACKFILL_REQUEST: &str = "MOVES";
+
+/// How often the connection metrics are sampled and emitted.
+const METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+struct TlcsConnectionHandle {
+    control: mpsc::UnboundedSender<TlcsControl>,
+    join: tokio::task::JoinHandle<()>,
+    metrics: Arc<TlcsMetrics>,
+    events: TlcsEmitter,
+}
+
+impl TlcsConnectionHandle {
+    async fn shutdown(self) {
+        let _ = self.control.send(TlcsControl::Disconnect);
+        let _ = self.join.await;
+    }
+}
+
+impl TlcsManager {
+    /// Opens a connection, replacing the current one, and returns the id its
+    /// events carry.
+    pub async fn connect(&self, options: TlcsConnectArgs, app: AppHandle) -> String {
+        let session_id = format!("connection-{}", Utc::now().format("%Y%m%dT%H%M%S%3fZ"));
+        let events = TlcsEmitter::new(app, session_id.clone(), options.scope_events);
+        {
+            let mut last_options = self.last_options.lock().await;
+            *last_options = Some(options.clone());
+        }
+
+        if let Some(handle) = self.replace_running(None).await {
+            handle.shutdown().await;
+        }
+
+        let protocol = options.protocol.as_deref().unwrap_or(DEFAULT_PROTOCOL);
+        let Some(parser) = self.parsers.lock().await.get(protocol) else {
+            emit_status(
+                &events,
+                TlcsConnectionStatus::Error,
+                Some(format!("Unknown TLCS protocol: {protocol}")),
+            );
+            return session_id;
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let metrics = TlcsMetrics::new();
+        let consumers = LineConsumers {
+            parser,
+            recorders: self.lines.clone(),
+        };
+        let join = tokio::spawn(run_connection(
+            options,
+            events.clone(),
+            rx,
+            metrics.clone(),
+            consumers,
+        ));
+
+        self.replace_running(Some(TlcsConnectionHandle {
+            control: tx,
+            join,
+            metrics,
+            events,
+        }))
+        .await;
+        session_id
+    }
+
+    /// Receives every line the connection reads from now on, across
+    /// reconnects.
+    async fn subscribe_lines(&self) -> Result<broadcast::Receiver<String>, Error> {
+        if self.handle.lock().await.is_none() {
+            return Err(Error::TlcsNotConnected);
+        }
+        Ok(self.lines.subscribe())
+    }
+
+    pub async fn metrics(&self) -> Option<TlcsMetricsEvent> {
+        let handle = self.handle.lock().await;
+        handle.as_ref().map(|handle| handle.metrics.snapshot())
+    }
+
+    pub async fn disconnect(&self) {
+        if let Some(handle) = self.replace_running(None).await {
+            handle.shutdown().await;
+        }
+    }
+
+    pub async fn send_action(&self, action: TlcsUserAction) -> Result<(), String> {
+        let handle = self.handle.lock().await;
+        let Some(handle) = &*handle else {
+            return Err("Not connected".into());
+        };
+
+        let payload = match action {
+            TlcsUserAction::AcceptOffer => "ACCEPT".to_string(),
+            TlcsUserAction::OfferDraw => "DRAW".to_string(),
+            TlcsUserAction::Resign => "RESIGN".to_string(),
+            TlcsUserAction::DeclineDraw => "DECLINE".to_string(),
+            TlcsUserAction::Abort => "ABORT".to_string(),
+            TlcsUserAction::Adjourn => "ADJOURN".to_string(),
+            TlcsUserAction::ClaimDraw(TlcsDrawClaim::ThreefoldRepetition) => {
+                "CLAIM REPETITION".to_string()
+            }
+            TlcsUserAction::ClaimDraw(TlcsDrawClaim::FiftyMoves) => "CLAIM FIFTY".to_string(),
+            TlcsUserAction::RequestReconnect => {
+                handle
+                    .control
+                    .send(TlcsControl::Reconnect)
+                    .map_err(|e| e.to_string())?;
+                return Ok(());
+            }
+        };
+
+        let confirm_window = self
+            .last_options
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|options| options.confirm_window_ms)
+            .filter(|_| {
+                matches!(
+                    action,
+                    TlcsUserAction::AcceptOffer
+                        | TlcsUserAction::OfferDraw
+                        | TlcsUserAction::Resign
+                        | TlcsUserAction::Abort
+                        | TlcsUserAction::Adjourn
+                        | TlcsUserAction::ClaimDraw(_)
+                )
+            });
+        let Some(confirm_window) = confirm_window else {
+            return handle
+                .control
+                .send(TlcsControl::Send(payload))
+                .map_err(|e| e.to_string());
+        };
+
+        let mut pending = self.pending.lock().await;
+        if let Some(waiting) = pending.as_ref() {
+            return Err(format!("{:?} is waiting to be sent", waiting.action));
+        }
+        let control = handle.control.clone();
+        let queued = self.pending.clone();
+        let events = handle.events.clone();
+        let task_action = action.clone();
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(confirm_window)).await;
+            // Cancelled meanwhile.
+            if queued.lock().await.take().is_none() {
+                return;
+            }
+            let state = match control.send(TlcsControl::Send(payload)) {
+                Ok(()) => TlcsPendingActionState::Sent,
+                Err(_) => TlcsPendingActionState::Cancelled,
+            };
+            events.emit(
+                "tlcs-pending-action",
+                TlcsPendingActionEvent {
+                    action: task_action,
+                    state,
+                    delay_ms: None,
+                },
+            );
+        });
+        *pending = Some(PendingAction {
+            action: action.clone(),
+            task,
+        });
+        handle.events.emit(
+            "tlcs-pending-action",
+            TlcsPendingActionEvent {
+                action,
+                state: TlcsPendingActionState::Queued,
+                delay_ms: Some(confirm_window),
+            },
+        );
+        Ok(())
+    }
+
+    /// Drops the action waiting out the confirmation window, returning
+    /// whether there was one.
+    pub async fn cancel_pending_action(&self) -> bool {
+        let Some(pending) = self.pending.lock().await.take() else {
+            return false;
+        };
+        pending.task.abort();
+        if let Some(handle) = &*self.handle.lock().await {
+            handle.events.emit(
+                "tlcs-pending-action",
+                TlcsPendingActionEvent {
+                    action: pending.action,
+                    state: TlcsPendingActionState::Cancelled,
+                    delay_ms: None,
+                },
+            );
+        }
+        true
+    }
+
+    /// Sends an arbiter command, if the connection was opened with arbiter
+    /// rights.
+    async fn send_arbiter(&self, payload: String) -> Result<(), Error> {
+        let allowed = self
+            .last_options
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|options| options.arbiter);
+        if !allowed {
+            return Err(Error::TlcsArbiterDisabled);
+        }
+        let handle = self.handle.lock().await;
+        let handle = handle.as_ref().ok_or(Error::TlcsNotConnected)?;
+        handle
+            .control
+            .send(TlcsControl::Send(payload))
+            .map_err(|_| Error::TlcsNotConnected)
+    }
+
+    pub async fn reconnect(&self, app: AppHandle) -> Result<String, String> {
+        let options = {
+            let last = self.last_options.lock().await;
+            last.clone()
+                .ok_or_else(|| "No previous connection".to_string())?
+        };
+        Ok(self.connect(options, app).await)
+    }
+
+    async fn replace_running(
+        &self,
+        next: Option<TlcsConnectionHandle>,
+    ) -> Option<TlcsConnectionHandle> {
+        // An action held for the old connection must not reach a new one.
+        if let Some(pending) = self.pending.lock().await.take() {
+            pending.task.abort();
+        }
+        let mut handle = self.handle.lock().await;
+        std::mem::replace(&mut *handle, next)
+    }
+}
+
+async fn run_connection(
+    options: TlcsConnectArgs,
+    events: TlcsEmitter,
+    mut control_rx: mpsc::UnboundedReceiver<TlcsControl>,
+    metrics: Arc<TlcsMetrics>,
+    consumers: LineConsumers,
+) {
+    let mut opts = options.clone();
+    let capture = match opts
+        .capture_path
+        .as_deref()
+        .map(Path::new)
+        .map(TlcsCapture::open)
+    {
+        Some(Ok(capture)) => Some(capture),
+        Some(Err(err)) => {
+            error!("Failed to open TLCS capture file: {err}");
+            None
+        }
+        None => None,
+    };
+
+    let policy = opts
+        .reconnect_policy
+        .clone()
+        .unwrap_or_else(|| ReconnectPolicy::fixed(opts.reconnect_interval_ms.max(500)));
+    let mut reconnector = Reconnector::new(policy);
+    let mut first_attempt = true;
+    let mut failed_attempts = 0;
+    let app = events.app();
+    let state = app.state::<AppState>();
+    let notifier = &state.tlcs_notifier;
+
+    loop {
+        if !first_attempt {
+            metrics.reconnected();
+        }
+        first_attempt = false;
+        emit_status(
+            &events,
+            TlcsConnectionStatus::Connecting,
+            Some("Opening TLCS socket".into()),
+        );
+
+        match connect_tcp(&opts.host, opts.port, opts.proxy_url.as_deref()).await {
+            Ok(stream) => {
+                reconnector.reset();
+                failed_attempts = 0;
+                emit_status(
+                    &events,
+                    TlcsConnectionStatus::Connected,
+                    stream
+                        .peer_addr()
+                        .ok()
+                        .map(|address| format!("Connected to {address}")),
+                );
+                metrics.set_connected(true);
+                let disconnected = handle_stream(
+                    stream,
+                    &events,
+                    &mut control_rx,
+                    &opts,
+                    capture.as_ref(),
+                    &metrics,
+                    &consumers,
+                )
+                .await;
+                metrics.set_connected(false);
+                if !disconnected {
+                    notifier.disconnected(app, "Connection closed");
+                    emit_status(
+                        &events,
+                        TlcsConnectionStatus::Error,
+                        Some("Connection closed".into()),
+                    );
+                }
+            }
+            Err(err) => {
+                error!("Failed to connect to TLCS server: {err}");
+                failed_attempts += 1;
+                notifier.reconnect_failed(app, failed_attempts);
+                emit_status(&events, TlcsConnectionStatus::Error, Some(err.to_string()));
+            }
+        }
+
+        if !opts.auto_reconnect {
+            emit_status(
+                &events,
+                TlcsConnectionStatus::Disconnected,
+                Some("Connection stopped".into()),
+            );
+            break;
+        }
+
+        let Some(retry) = reconnector.next_attempt() else {
+            let status = match reconnector.give_up() {
+                ReconnectGiveUp::Disconnect => TlcsConnectionStatus::Disconnected,
+                ReconnectGiveUp::Error => TlcsConnectionStatus::Error,
+            };
+            emit_status(
+                &events,
+                status,
+                Some(format!(
+                    "Gave up after {} reconnect attempts",
+                    reconnector.failures()
+                )),
+            );
+            break;
+        };
+        events.emit(
+            "tlcs-connection",
+            TlcsConnectionEvent {
+                status: TlcsConnectionStatus::Connecting,
+                message: Some(retry.describe()),
+                retry: Some(retry),
+            },
+        );
+        tokio::time::sleep(retry.delay()).await;
+    }
+}
+
+async fn handle_stream(
+    stream: TcpStream,
+    events: &TlcsEmitter,
+    control_rx: &mut mpsc::UnboundedReceiver<TlcsControl>,
+    options: &TlcsConnectArgs,
+    capture: Option<&TlcsCapture>,
+    metrics: &Arc<TlcsMetrics>,
+    consumers: &LineConsumers,
+) -> bool {
+    let (reader, mut writer) = stream.into_split();
+    let reader = CountingReader::new(
+        CaptureReader::new(reader, capture.cloned()),
+        metrics.clone(),
+    );
+    let mut lines = DecodedLines::new(BufReader::new(reader), options.encoding.unwrap_or_default());
+    let mut metrics_ticker = tokio::time::interval(METRICS_INTERVAL);
+    let mut game_state = TlcsGameState::default();
+    let stale_timeout = options.stale_timeout_ms.map(Duration::from_millis);
+    let mut last_received = tokio::time::Instant::now();
+    let mut clocks = ClockSimulation::new(options.clock);
+    let rate_limit = options.rate_limit.map(FrameLimiter::new);
+    // Checked by `connect_tlcs`.
+    let redactor = TlcsRedactor::new(&options.redact).unwrap_or_default();
+    let mut clock_ticker = tokio::time::interval(CLOCK_TICK);
+    let mut repetitions = RepetitionTracker::default();
+    let (script_tx, mut script_rx) = mpsc::unbounded_channel();
+    let mut scripts = ScriptRunner::new(&options.scripts, script_tx);
+    scripts.run(TlcsScriptTrigger::Connect);
+
+    if !options.username.is_empty() {
+        let login = format!("USER {} {}\r\n", options.username, options.password);
+        if let Some(capture) = capture {
+            capture.sent(login.as_bytes());
+        }
+        metrics.line_sent(login.len());
+        if let Err(err) = writer.write_all(login.as_bytes()).await {
+            error!("Failed to send credentials: {err}");
+            emit_status(events, TlcsConnectionStatus::Error, Some(err.to_string()));
+            return false;
+        }
+        scripts.run(TlcsScriptTrigger::Login);
+    }
+
+    // Attached recorders missed the moves played while the connection was
+    // down.
+    if metrics.snapshot().reconnects > 0 && consumers.recorders.receiver_count() > 0 {
+        let request = format!("{BACKFILL_REQUEST}\r\n");
+        if let Some(capture) = capture {
+            capture.sent(request.as_bytes());
+        }
+        metrics.line_sent(request.len());
+        if let Err(err) = writer.write_all(request.as_bytes()).await {
+            error!("Failed to request the move lists: {err}");
+            emit_status(events, TlcsConnectionStatus::Error, Some(err.to_string()));
+            return false;
+        }
+    }
+
+    loop {
+        select! {
+            line = lines.next_line() => {
+                match line {
+                    Ok(Some(line)) => {
+                        last_received = tokio::time::Instant::now();
+                        metrics.line_received();
+                        if announces_new_game(&line) {
+                            scripts.run(TlcsScriptTrigger::NewGame);
+                        }
+                        consumers.parser.apply(&mut game_state, &line);
+                        if let Some(fen) = &game_state.fen {
+                            let status = repetitions.update(fen);
+                            game_state.repetition_count = status.repetition_count;
+                            game_state.halfmove_clock = status.halfmove_clock;
+                            game_state.material = material(fen);
+                        }
+                        // Fails only while no recorder is attached.
+                        let _ = consumers.recorders.send(line.clone());
+                        clocks.sync(&game_state, &line);
+                        emit_game(events, &game_state, Some(redactor.redact(&line)));
+                    }
+                    Ok(None) => {
+                        return false;
+                    }
+                    Err(err) => {
+                        error!("Failed to read from TLCS stream: {err}");
+                        emit_status(events, TlcsConnectionStatus::Error, Some(err.to_string()));
+                        return false;
+                    }
+                }
+            }
+            _ = clock_ticker.tick() => {
+                clocks.tick(events);
+            }
+            _ = metrics_ticker.tick() => {
+                events.emit("tlcs-metrics", metrics.sample());
+            }
+            _ = tokio::time::sleep_until(last_received + stale_timeout.unwrap_or_default()), if stale_timeout.is_some() => {
+                error!("No data from TLCS server, closing stale connection");
+                emit_status(events, TlcsConnectionStatus::Error, Some("stale connection".into()));
+                return false;
+            }
+            control = control_rx.recv() => {
+                match control {
+                    Some(TlcsControl::Send(cmd)) => {
+                        if let Some(rate_limit) = &rate_limit {
+                            rate_limit.throttle(&cmd, |event| events.emit("tlcs-rate-limited", event)).await;
+                        }
+                        if let Err(err) = send_command(&mut writer, &cmd, capture, metrics).await {
+                            error!("Failed to send TLCS command: {err}");
+                            emit_status(events, TlcsConnectionStatus::Error, Some(err.to_string()));
+                            return false;
+                        }
+                    }
+                    Some(TlcsControl::Disconnect) => {
+                        emit_status(events, TlcsConnectionStatus::Disconnected, Some("Disconnected by user".into()));
+                        return true;
+                    }
+                    Some(TlcsControl::Reconnect) => {
+                        emit_status(events, TlcsConnectionStatus::Connecting, Some("Manual reconnect".into()));
+                        return false;
+                    }
+                    None => return false,
+                }
+            }
+            Some(cmd) = script_rx.recv() => {
+                if let Some(rate_limit) = &rate_limit {
+                    rate_limit.throttle(&cmd, |event| events.emit("tlcs-rate-limited", event)).await;
+                }
+                if let Err(err) = send_command(&mut writer, &cmd, capture, metrics).await {
+                    error!("Failed to send scripted TLCS command: {err}");
+                    emit_status(events, TlcsConnectionStatus::Error, Some(err.to_string()));
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Writes one command line to the server, through the capture and metrics.
+async fn send_command(
+    writer: &mut OwnedWriteHalf,
+    cmd: &str,
+    capture: Option<&TlcsCapture>,
+    metrics: &TlcsMetrics,
+) -> std::io::Result<()> {
+    let cmd = format!("{cmd}\r\n");
+    if let Some(capture) = capture {
+        capture.sent(cmd.as_bytes());
+    }
+    metrics.line_sent(cmd.len());
+    writer.write_all(cmd.as_bytes()).await
+}
+
+fn emit_status(events: &TlcsEmitter, status: TlcsConnectionStatus, message: Option<String>) {
+    events.emit(
+        "tlcs-connection",
+        TlcsConnectionEvent {
+            status,
+            message,
+            retry: None,
+        },
+    );
+}
+
+/// Emits the game state with the line that changed it, which must already
+/// be redacted.
+fn emit_game(events: &TlcsEmitter, state: &TlcsGameState, raw: Option<String>) {
+    events.emit(
+        "tlcs-game",
+        TlcsGameEvent {
+            state: state.clone(),
+            raw,
+        },
+    );
+}
+
+pub type SharedTlcs = Arc<TlcsManager>;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_tlcs(
+    options: Option<TlcsConnectArgs>,
+    profile: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let options = match (options, profile) {
+        (Some(options), _) => options,
+        (None, Some(profile)) => {
+            crate::tlcs_profiles::load_profile(&app, &profile).map_err(|e| e.to_string())?
+        }
+        (None, None) => return Err("Missing TLCS connection options or profile".into()),
+    };
+    TlcsRedactor::new(&options.redact).map_err(|e| e.to_string())?;
+    Ok(state.tlcs.connect(options, app).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn disconnect_tlcs(state: tauri::State<'_, crate::AppState>) -> Result<(), String> {
+    state.tlcs.disconnect().await;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn send_tlcs_action(
+    action: TlcsUserAction,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<(), String> {
+    state.tlcs.send_action(action).await
+}
+
+/// Cancels a resignation or draw message still inside the confirmation
+/// window. Returns `false` when none is waiting.
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_pending_action(
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<bool, String> {
+    Ok(state.tlcs.cancel_pending_action().await)
+}
+
+/// Returns the traffic counters of the current connection session, or `None`
+/// when no connection was opened.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_connection_metrics(
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<Option<TlcsMetricsEvent>, Error> {
+    Ok(state.tlcs.metrics().await)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn reconnect_tlcs(
+    state: tauri::State<'_, crate::AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    state.tlcs.reconnect(app).await
+}
+
+/// Applies an arbiter decision to the recording of `game_id`. Game ids that
+/// name a recorded board select it; any other id selects the default board.
+/// Nothing happens when no recording is running.
+async fn adjust_recorder(
+    state: &AppState,
+    game_id: &str,
+    adjust: impl FnOnce(&mut TlcsRecorder) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return Ok(());
+    };
+    let mut recorder = handle.recorder.write().await;
+    let board = game_id
+        .parse::<u32>()
+        .ok()
+        .filter(|board| recorder.recorder(Some(*board)).is_some());
+    adjust(recorder.recorder_mut(board)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_set_result(
+    game_id: String,
+    result: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    if !is_result_token(&result) || result == "*" {
+        return Err(Error::TlcsInvalidResult(result));
+    }
+    state
+        .tlcs
+        .send_arbiter(format!("RESULT {game_id} {result}"))
+        .await?;
+    adjust_recorder(&state, &game_id, |recorder| {
+        recorder.adjudicate(&result, "adjudication")
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_adjust_clock(
+    game_id: String,
+    color: TlcsSide,
+    ms: u64,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let side = match color {
+        TlcsSide::White => "white",
+        TlcsSide::Black => "black",
+    };
+    state
+        .tlcs
+        .send_arbiter(format!("CLOCK {game_id} {side} {ms}"))
+        .await?;
+    adjust_recorder(&state, &game_id, |recorder| {
+        match color {
+            TlcsSide::White => recorder.white_clock_ms = Some(ms),
+            TlcsSide::Black => recorder.black_clock_ms = Some(ms),
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_abort_game(
+    game_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    state.tlcs.send_arbiter(format!("ABORT {game_id}")).await?;
+    adjust_recorder(&state, &game_id, |recorder| {
+        recorder.adjudicate("*", "abandoned")
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_status(state: tauri::State<'_, AppState>) -> Result<TlcsStatus, Error> {
+    let guard = state.tlcs_handle.read().await;
+    if let Some(handle) = guard.as_ref() {
+        let recorder = handle.recorder.read().await;
+        return Ok(TlcsStatus {
+            recording: true,
+            pgn_path: Some(recorder.default.pgn_path().to_string_lossy().to_string()),
+            moves_recorded: recorder.default.moves_recorded(),
+            boards: recorder.board_statuses(),
+        });
+    }
+
+    Ok(TlcsStatus {
+        recording: false,
+        pgn_path: None,
+        moves_recorded: 0,
+        boards: Vec::new(),
+    })
+}
+
+/// The movetext of `board` (the default board when `None`) after
+/// `since_ply`, for viewers catching up with a game they follow through
+/// `TlcsPgnAppendedEvent`.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_pgn_delta(
+    session: Option<String>,
+    board: Option<u32>,
+    since_ply: usize,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsPgnAppendedEvent, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = running_session(&guard, session.as_deref()).await?;
+    let recorder = handle.recorder.read().await;
+    let board_recorder = recorder.recorder(board).ok_or(Error::TlcsNotRecording)?;
+    Ok(board_recorder.pgn_delta(board, since_ply))
+}
+
+/// Changes the least severe entries the running session writes to its log,
+/// e.g. to stop logging raw lines during a bullet round.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_log_level(
+    level: TlcsLogLevel,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let guard = state.tlcs_handle.read().await;
+    let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+    handle.log.set_level(level);
+    handle.log.info(&format!("Log level set to {level:?}"));
+    Ok(())
+}
+
+/// Chooses which connection and game milestones raise desktop
+/// notifications.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_notifications(
+    options: TlcsNotificationOptions,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    state.tlcs_notifier.set_options(options);
+    Ok(())
+}
+
+/// Reports the periodic PGN uploads of the running session, or `None` when
+/// no session uploads.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_upload_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<TlcsUploadStatus>, Error> {
+    let guard = state.tlcs_handle.read().await;
+    match guard.as_ref().and_then(|handle| handle.upload.as_ref()) {
+        Some(upload) => Ok(Some(upload.status().await)),
+        None => Ok(None),
+    }
+}
+
+/// Lists the games recorded so far on `board` (the default board when
+/// `None`), or `None` when nothing is being recorded.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_tournament_status(
+    board: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<TlcsTournamentStatus>, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return Ok(None);
+    };
+    let recorder = handle.recorder.read().await;
+    Ok(recorder
+        .recorder(board)
+        .map(|recorder| TlcsTournamentStatus {
+            round: recorder
+                .headers
+                .get("Round")
+                .cloned()
+                .unwrap_or_else(|| "1".into()),
+            games: recorder.completed.clone(),
+        }))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_analysis_options(
+    board: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<AnalysisOptions>, Error> {
+    let guard = state.tlcs_handle.read().await;
+    if let Some(handle) = guard.as_ref() {
+        let recorder = handle.recorder.read().await;
+        return Ok(recorder.recorder(board).map(|r| r.analysis_options()));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_prefix_is_split() {
+        assert_eq!(split_board_prefix("board 3: e4 e5"), (Some(3), "e4 e5"));
+        assert_eq!(split_board_prefix("Board12:Nf3"), (Some(12), "Nf3"));
+    }
+
+    #[test]
+    fn takeback_commands_are_parsed() {
+        assert_eq!(parse_takeback("takeback 2"), Some(2));
+        assert_eq!(parse_takeback("UNDO"), Some(1));
+        assert_eq!(parse_takeback("takeback all"), None);
+        assert_eq!(parse_takeback("1. e4 e5"), None);
+    }
+
+    #[test]
+    fn unprefixed_lines_use_default_board() {
+        assert_eq!(split_board_prefix("1. e4 e5"), (None, "1. e4 e5"));
+        assert_eq!(split_board_prefix("boardroom: hi"), (None, "boardroom: hi"));
+    }
+
+    #[test]
+    fn status_lines_map_to_results() {
+        let position = shakmaty::Chess::default();
+        assert_eq!(
+            result_from_status("White resigns", &position),
+            Some(("0-1", "Normal"))
+        );
+        assert_eq!(
+            result_from_status("black wins on time", &position),
+            Some(("0-1", "Time forfeit"))
+        );
+        assert_eq!(
+            result_from_status("Draw agreed", &position),
+            Some(("1/2-1/2", "Normal"))
+        );
+        assert_eq!(result_from_status("draw offered", &position), None);
+        assert_eq!(result_from_status("in progress", &position), None);
+    }
+
+    #[test]
+    fn comments_and_nags_are_kept_apart_from_moves() {
+        assert_eq!(
+            parse_movetext("12. Nf3!? {a new idea} Nc6 $2 13. O-O"),
+            [
+                MovetextToken::MoveNumber(22),
+                MovetextToken::Move("Nf3".into()),
+                MovetextToken::Nag(5),
+                MovetextToken::Comment("a new idea".into()),
+                MovetextToken::Move("Nc6".into()),
+                MovetextToken::Nag(2),
+                MovetextToken::MoveNumber(24),
+                MovetextToken::Move("O-O".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn movetext_edge_tokens_are_kept_whole() {
+        assert_eq!(
+            parse_movetext("12.Nf3 12...O-O-O (12...Nc6 13.d4) 13. e8=Q+ 0-0 exd6 e.p. 1-0 ; end"),
+            [
+                MovetextToken::MoveNumber(22),
+                MovetextToken::Move("Nf3".into()),
+                MovetextToken::MoveNumber(23),
+                MovetextToken::Move("O-O-O".into()),
+                MovetextToken::MoveNumber(24),
+                MovetextToken::Move("e8=Q+".into()),
+                MovetextToken::Move("O-O".into()),
+                MovetextToken::Move("exd6".into()),
+                MovetextToken::Move("1-0".into()),
+            ]
+        );
+        assert_eq!(
+            parse_movetext("1. e2e4 e7e5"),
+            [
+                MovetextToken::MoveNumber(0),
+                MovetextToken::Move("e2e4".into()),
+                MovetextToken::Move("e7e5".into()),
+            ]
+        );
+        assert_eq!(
+            parse_movetext("7. ... Bb4"),
+            [
+                MovetextToken::MoveNumber(13),
+                MovetextToken::Move("Bb4".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pgn_headers_are_parsed() {
+        assert_eq!(
+            parse_header("[White \"Carlsen\"]"),
+            Some(("White", "Carlsen".to_string()))
+        );
+        assert_eq!(
+            parse_header(r#"[Event "\"Open\" A\\B"]"#),
+            Some(("Event", r#""Open" A\B"#.to_string()))
+        );
+        assert_eq!(parse_header("1. e4 e5"), None);
+    }
+
+    /// A recorder writing to `game.pgn` in `dir`, with `options` over a
+    /// plain TCP session.
+    fn test_recorder(dir: &Path, options: serde_json::Value) -> TlcsRecorder {
+        let mut merged = serde_json::json!({ "host": "localhost", "port": 16001 });
+        if let (Some(merged), Some(options)) = (merged.as_object_mut(), options.as_object()) {
+            merged.extend(options.clone());
+        }
+        let options: TlcsConnectOptions = serde_json::from_value(merged).unwrap();
+        let log = RotatingLog::new(dir.join(LOG_FILE), &TlcsLogConfig::default(), None).unwrap();
+        let writer = PgnWriter::spawn(log.clone());
+        TlcsRecorder::new(dir.join("game.pgn"), &options, None, log, writer).unwrap()
+    }
+
+    /// A demux over `test_recorder`.
+    fn test_demux(dir: &Path) -> TlcsDemux {
+        let recorder = test_recorder(dir, serde_json::json!({}));
+        let options =
+            serde_json::from_value(serde_json::json!({ "host": "localhost", "port": 16001 }))
+                .unwrap();
+        let log = recorder.log.clone();
+        TlcsDemux::new(recorder, options, log, false)
+    }
+
+    fn feed(recorder: &mut TlcsRecorder, lines: &[&str]) {
+        for line in lines {
+            recorder.append_moves_from_line(line).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn takebacks_drop_the_last_plies() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({}));
+        feed(&mut recorder, &["1. e4 e5 2. Nf3 Nc6", "takeback 2"]);
+        assert_eq!(recorder.sans, ["e4", "e5"]);
+
+        feed(&mut recorder, &["undo"]);
+        assert_eq!(recorder.sans, ["e4"]);
+        feed(&mut recorder, &["c5"]);
+        assert_eq!(recorder.sans, ["e4", "c5"]);
+    }
+
+    #[tokio::test]
+    async fn restated_move_lists_only_replace_diverging_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({}));
+        feed(&mut recorder, &["1. e4 e5 2. Nf3 Nc6 3. Bb5"]);
+        recorder.backfill = None;
+
+        // Wrapped, partial or echoed lists stop short without diverging.
+        feed(&mut recorder, &["1. e4 e5 2. Nf3", "1. e4 e5"]);
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert!(recorder.backfill.is_none());
+
+        feed(&mut recorder, &["1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4"]);
+        assert_eq!(
+            recorder.sans,
+            ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"]
+        );
+        assert_eq!(recorder.backfill, Some((5, 2, 0)));
+
+        feed(&mut recorder, &["1. e4 e5 2. Nf3 Nf6"]);
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3", "Nf6"]);
+        assert_eq!(recorder.backfill, Some((3, 1, 4)));
+    }
+
+    #[tokio::test]
+    async fn fen_lines_repair_gaps_and_takebacks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({}));
+        feed(
+            &mut recorder,
+            &[
+                "1. e4 e5",
+                "fen r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            ],
+        );
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3", "Nc6"]);
+        assert!(matches!(
+            recorder.resync,
+            Some((TlcsResyncAction::InsertedMoves, _))
+        ));
+
+        feed(
+            &mut recorder,
+            &["fen rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"],
+        );
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3"]);
+        assert!(matches!(
+            recorder.resync,
+            Some((TlcsResyncAction::TookBack, _))
+        ));
+
+        feed(&mut recorder, &["fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1"]);
+        assert!(recorder.sans.is_empty());
+        assert!(recorder.archived.contains("Nf3"));
+        assert!(matches!(
+            recorder.resync,
+            Some((TlcsResyncAction::Restarted, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn resent_moves_are_recorded_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({}));
+        feed(
+            &mut recorder,
+            &["1. e4 e5", "2. Nf3 Nc6", "2. Nf3 Nc6 3. Bb5", "3. Bb5 a6"],
+        );
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+    }
+
+    #[tokio::test]
+    async fn restatements_backfill_missed_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({}));
+        feed(&mut recorder, &["e4", "e5"]);
+        assert!(recorder.backfill.is_none());
+
+        feed(&mut recorder, &["1. e4 e5 2. Nf3 Nc6 3. Bb5"]);
+        assert_eq!(recorder.sans, ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(recorder.backfill, Some((2, 3, 0)));
+    }
+
+    #[tokio::test]
+    async fn tournament_games_become_rounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = test_recorder(dir.path(), serde_json::json!({ "tournament": true }));
+        feed(&mut recorder, &["1. e4 e5 1-0", "start", "1. d4 d5"]);
+        assert_eq!(recorder.sans, ["d4", "d5"]);
+        assert_eq!(recorder.headers.get("Round").map(String::as_str), Some("2"));
+        assert_eq!(recorder.completed.len(), 1);
+        assert_eq!(recorder.completed[0].result, "1-0");
+
+        feed(
+            &mut recorder,
+            &["fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"],
+        );
+        assert!(recorder.sans.is_empty());
+        assert_eq!(recorder.headers.get("Round").map(String::as_str), Some("3"));
+        assert_eq!(recorder.completed.len(), 2);
+        assert!(recorder.archived.contains("[Round \"1\"]"));
+        assert!(recorder.archived.contains("[Round \"2\"]"));
+    }
+
+    #[tokio::test]
+    async fn idle_finished_games_reopen_on_a_later_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut demux = test_demux(dir.path());
+        demux.append_line("1. e4 e5").unwrap();
+        assert_eq!(demux.finish_idle().len(), 1);
+        assert_eq!(demux.default.result.as_deref(), Some("*"));
+
+        let outcome = demux.append_line("Nf3").unwrap();
+        assert!(outcome.moved);
+        assert_eq!(demux.default.result, None);
+
+        let outcome = demux.append_line("1-0").unwrap();
+        assert_eq!(
+            outcome.finished.map(|game| game.result).as_deref(),
+            Some("1-0")
+        );
+        assert_eq!(
+            demux.default.headers.get("Result").map(String::as_str),
+            Some("1-0")
+        );
+    }
+
+    #[tokio::test]
+    async fn corrected_moves_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut demux = test_demux(dir.path());
+        demux.append_line("1. e4 e5 2. Nf3 Nc6").unwrap();
+
+        let outcome = demux.append_line("1. e4 e5 2. Nf3 Nf6").unwrap();
+        assert!(outcome.moved);
+        assert_eq!(outcome.recorded.len(), 1);
+        let appended = outcome.appended.unwrap();
+        assert_eq!((appended.since_ply, appended.plies), (3, 4));
+        assert!(appended.movetext.contains("Nf6"));
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::RwLock;
+
+use serde::Deserialize;
+use specta::Type;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use super::webhook::TlcsFinishedGame;
+
+/// Milestones that raise a desktop notification, for operators who keep
+/// the app minimized during an event. Everything is off by default.
+#[derive(Clone, Debug, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsNotificationOptions {
+    /// Notify when the connection to the server drops.
+    #[serde(default)]
+    pub disconnect: bool,
+    /// Notify once this many reconnect attempts in a row have failed.
+    pub failed_reconnects: Option<u32>,
+    /// Notify when a recorded game ends.
+    #[serde(default)]
+    pub game_end: bool,
+    /// Notify when a recorded game of one of these players starts. Names
+    /// match case-insensitively anywhere in the White and Black tags.
+    #[serde(default)]
+    pub players: Vec<String>,
+}
+
+/// Shows the notifications enabled by the current `TlcsNotificationOptions`.
+#[derive(Default)]
+pub struct TlcsNotifier {
+    options: RwLock<TlcsNotificationOptions>,
+}
+
+impl TlcsNotifier {
+    pub fn set_options(&self, options: TlcsNotificationOptions) {
+        if let Ok(mut current) = self.options.write() {
+            *current = options;
+        }
+    }
+
+    fn options(&self) -> TlcsNotificationOptions {
+        self.options
+            .read()
+            .map(|options| options.clone())
+            .unwrap_or_default()
+    }
+
+    pub(super) fn disconnected(&self, app: &AppHandle, reason: &str) {
+        if self.options().disconnect {
+            show(app, "TLCS connection lost", reason);
+        }
+    }
+
+    /// Called after every failed attempt with the failures in a row, so the
+    /// notification is shown once per outage.
+    pub(super) fn reconnect_failed(&self, app: &AppHandle, failures: u32) {
+        if self.options().failed_reconnects == Some(failures) {
+            show(
+                app,
+                "TLCS reconnect failing",
+                &format!("{failures} reconnect attempts in a row have failed"),
+            );
+        }
+    }
+
+    pub(super) fn game_finished(&self, app: &AppHandle, game: &TlcsFinishedGame) {
+        if self.options().game_end {
+            show(
+                app,
+                &format!("{} {}", board_label(game.board), game.result),
+                &players_label(game.white.as_deref(), game.black.as_deref()),
+            );
+        }
+    }
+
+    pub(super) fn game_started(
+        &self,
+        app: &AppHandle,
+        board: Option<u32>,
+        white: Option<&str>,
+        black: Option<&str>,
+    ) {
+        let options = self.options();
+        if let Some(player) = followed_player(&options.players, [white, black]) {
+            show(
+                app,
+                &format!("{player} started on {}", board_label(board).to_lowercase()),
+                &players_label(white, black),
+            );
+        }
+    }
+}
+
+fn board_label(board: Option<u32>) -> String {
+    board.map_or_else(|| "Game".to_string(), |board| format!("Board {board}"))
+}
+
+fn players_label(white: Option<&str>, black: Option<&str>) -> String {
+    format!("{} - {}", white.unwrap_or("?"), black.unwrap_or("?"))
+}
+
+/// The first of `names` that contains one of the followed `players`.
+fn followed_player<'a>(players: &[String], names: [Option<&'a str>; 2]) -> Option<&'a str> {
+    names.into_iter().flatten().find(|name| {
+        let name = name.to_lowercase();
+        players
+            .iter()
+            .map(|player| player.trim().to_lowercase())
+            .any(|player| !player.is_empty() && name.contains(&player))
+    })
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) {
+    if let Err(err) = app.notification().builder().title(title).body(body).show() {
+        log::error!("Failed to show notification: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn followed_players_match_part_of_a_name() {
+        let players = vec!["carlsen".to_string(), " ".to_string()];
+        assert_eq!(
+            followed_player(
+                &players,
+                [Some("Caruana, Fabiano"), Some("Carlsen, Magnus")]
+            ),
+            Some("Carlsen, Magnus")
+        );
+        assert_eq!(
+            followed_player(&players, [Some("Caruana, Fabiano"), None]),
+            None
+        );
+        assert_eq!(followed_player(&[], [Some("Carlsen, Magnus"), None]), None);
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use specta::Type;
+use tauri::Manager;
+use tauri_specta::Event;
+use tokio::sync::mpsc;
+
+use crate::db::{is_position_in_db, GameQueryJs, PositionQueryJs};
+use crate::AppState;
+
+use super::{RotatingLog, TlcsEmitter};
+
+/// Emitted the first time a recorded game reaches a position that does not
+/// occur in the reference database.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsNoveltyEvent {
+    pub board: Option<u32>,
+    pub ply: usize,
+    pub san: String,
+    pub fen: String,
+}
+
+struct NoveltyRequest {
+    board: Option<u32>,
+    ply: usize,
+    san: String,
+    fen: String,
+}
+
+/// Looks up every new live position in a reference database until each game
+/// leaves known theory. Positions are checked in order, one at a time.
+pub struct TlcsNoveltyWatch {
+    requests: mpsc::UnboundedSender<NoveltyRequest>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsNoveltyWatch {
+    pub fn spawn(reference: PathBuf, events: TlcsEmitter, log: RotatingLog) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_novelty_watch(reference, events, log, rx));
+        Self { requests, task }
+    }
+
+    pub fn check(&self, board: Option<u32>, ply: usize, san: String, fen: String) {
+        let _ = self.requests.send(NoveltyRequest {
+            board,
+            ply,
+            san,
+            fen,
+        });
+    }
+
+    pub async fn stop(self) {
+        drop(self.requests);
+        let _ = self.task.await;
+    }
+}
+
+async fn run_novelty_watch(
+    reference: PathBuf,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    mut rx: mpsc::UnboundedReceiver<NoveltyRequest>,
+) {
+    // Ply of the novelty found on each board.
+    let mut novelties: HashMap<Option<u32>, usize> = HashMap::new();
+
+    while let Some(request) = rx.recv().await {
+        if let Some(&ply) = novelties.get(&request.board) {
+            if request.ply > ply {
+                continue;
+            }
+            // A takeback or a new game went back into theory.
+            novelties.remove(&request.board);
+        }
+
+        let query = GameQueryJs::new().position(PositionQueryJs {
+            fen: request.fen.clone(),
+            type_: "exact".to_string(),
+        });
+        match is_position_in_db(reference.clone(), query, events.app().state::<AppState>()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                log.info(&format!("Novelty at ply {}: {}", request.ply, request.san));
+                novelties.insert(request.board, request.ply);
+                events.emit(
+                    "tlcs-novelty",
+                    TlcsNoveltyEvent {
+                        board: request.board,
+                        ply: request.ply,
+                        san: request.san,
+                        fen: request.fen,
+                    },
+                );
+            }
+            Err(err) => {
+                log.error(&format!(
+                    "Failed to look up position in reference database: {err}"
+                ));
+            }
+        }
+    }
+}
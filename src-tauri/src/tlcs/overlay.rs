@@ -0,0 +1,219 @@
+use std::fmt::Write as _;
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use shakmaty::{uci::UciMove, Color, Position, Role, Square};
+use specta::Type;
+
+use crate::error::Error;
+
+use super::TlcsRecorder;
+
+const SQUARE_SIZE: u32 = 45;
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const HIGHLIGHT: &str = "#cdd26a";
+
+/// Files kept up to date with the live position, for OBS text and browser
+/// sources. Unset paths are not written.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOverlayOptions {
+    /// Board to follow. Defaults to the unprefixed board.
+    pub board: Option<u32>,
+    pub json_path: Option<String>,
+    pub text_path: Option<String>,
+    pub svg_path: Option<String>,
+}
+
+/// Contents of the JSON overlay file.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOverlayState {
+    pub fen: String,
+    pub white: String,
+    pub black: String,
+    pub white_clock_ms: Option<u64>,
+    pub black_clock_ms: Option<u64>,
+    pub last_move: Option<String>,
+    pub ply: usize,
+    pub result: Option<String>,
+}
+
+pub struct TlcsOverlay {
+    pub board: Option<u32>,
+    json_path: Option<PathBuf>,
+    text_path: Option<PathBuf>,
+    svg_path: Option<PathBuf>,
+}
+
+impl TlcsOverlay {
+    pub fn new(options: &TlcsOverlayOptions) -> Self {
+        Self {
+            board: options.board,
+            json_path: options.json_path.as_ref().map(PathBuf::from),
+            text_path: options.text_path.as_ref().map(PathBuf::from),
+            svg_path: options.svg_path.as_ref().map(PathBuf::from),
+        }
+    }
+
+    /// Rewrites every configured file from the recorder's current state.
+    pub fn write(&self, recorder: &TlcsRecorder) -> Result<(), Error> {
+        let state = overlay_state(recorder);
+        if let Some(path) = &self.json_path {
+            write_atomically(path, &serde_json::to_string_pretty(&state)?)?;
+        }
+        if let Some(path) = &self.text_path {
+            write_atomically(path, &overlay_text(&state))?;
+        }
+        if let Some(path) = &self.svg_path {
+            write_atomically(path, &board_svg(recorder))?;
+        }
+        Ok(())
+    }
+}
+
+fn overlay_state(recorder: &TlcsRecorder) -> TlcsOverlayState {
+    let header = |name: &str| recorder.headers.get(name).cloned().unwrap_or_default();
+    let ply = recorder.moves.len();
+    TlcsOverlayState {
+        fen: recorder.fen(),
+        white: header("White"),
+        black: header("Black"),
+        white_clock_ms: recorder.white_clock_ms,
+        black_clock_ms: recorder.black_clock_ms,
+        last_move: recorder.sans.last().map(|san| {
+            let number = recorder.start_position.fullmoves().get() as usize
+                + (ply - 1 + usize::from(recorder.start_position.turn() == Color::Black)) / 2;
+            let white_moved = (ply % 2 == 1) == (recorder.start_position.turn() == Color::White);
+            if white_moved {
+                format!("{number}. {san}")
+            } else {
+                format!("{number}... {san}")
+            }
+        }),
+        ply,
+        result: recorder.result.clone(),
+    }
+}
+
+/// Formats milliseconds as `h:mm:ss`, or `m:ss` under an hour.
+fn format_clock(ms: u64) -> String {
+    let seconds = ms / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn overlay_text(state: &TlcsOverlayState) -> String {
+    let player = |name: &str, clock: Option<u64>| match clock {
+        Some(ms) => format!("{name} ({})", format_clock(ms)),
+        None => name.to_string(),
+    };
+    let mut text = String::new();
+    let _ = writeln!(text, "{}", player(&state.white, state.white_clock_ms));
+    let _ = writeln!(text, "{}", player(&state.black, state.black_clock_ms));
+    let _ = writeln!(text, "{}", state.last_move.as_deref().unwrap_or(""));
+    if let Some(result) = &state.result {
+        let _ = writeln!(text, "{result}");
+    }
+    let _ = writeln!(text, "{}", state.fen);
+    text
+}
+
+fn piece_glyph(color: Color, role: Role) -> char {
+    match (color, role) {
+        (Color::White, Role::King) => '\u{2654}',
+        (Color::White, Role::Queen) => '\u{2655}',
+        (Color::White, Role::Rook) => '\u{2656}',
+        (Color::White, Role::Bishop) => '\u{2657}',
+        (Color::White, Role::Knight) => '\u{2658}',
+        (Color::White, Role::Pawn) => '\u{2659}',
+        (Color::Black, Role::King) => '\u{265a}',
+        (Color::Black, Role::Queen) => '\u{265b}',
+        (Color::Black, Role::Rook) => '\u{265c}',
+        (Color::Black, Role::Bishop) => '\u{265d}',
+        (Color::Black, Role::Knight) => '\u{265e}',
+        (Color::Black, Role::Pawn) => '\u{265f}',
+    }
+}
+
+/// Draws the position from White's side, highlighting the last move.
+fn board_svg(recorder: &TlcsRecorder) -> String {
+    let highlighted: Vec<Square> = match recorder
+        .moves
+        .last()
+        .and_then(|uci| UciMove::from_ascii(uci.as_bytes()).ok())
+    {
+        Some(UciMove::Normal { from, to, .. }) => vec![from, to],
+        Some(UciMove::Put { to, .. }) => vec![to],
+        _ => Vec::new(),
+    };
+
+    let size = SQUARE_SIZE * 8;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n"
+    );
+    let board = recorder.position.board();
+    for index in 0..64u32 {
+        let square = Square::new(index);
+        let x = square.file() as u32 * SQUARE_SIZE;
+        let y = (7 - square.rank() as u32) * SQUARE_SIZE;
+        let fill = if highlighted.contains(&square) {
+            HIGHLIGHT
+        } else if square.is_light() {
+            LIGHT_SQUARE
+        } else {
+            DARK_SQUARE
+        };
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE_SIZE}\" height=\"{SQUARE_SIZE}\" fill=\"{fill}\"/>"
+        );
+        if let Some(piece) = board.piece_at(square) {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                x + SQUARE_SIZE / 2,
+                y + SQUARE_SIZE / 2,
+                SQUARE_SIZE * 4 / 5,
+                piece_glyph(piece.color, piece.role)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Replaces `path` in one step, so a source polling the file never reads a
+/// partial write.
+pub(super) fn write_atomically(path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clocks_are_formatted() {
+        assert_eq!(format_clock(59_999), "0:59");
+        assert_eq!(format_clock(605_000), "10:05");
+        assert_eq!(format_clock(5_400_000), "1:30:00");
+    }
+}
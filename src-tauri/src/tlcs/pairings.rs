@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{error::Error, AppState};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPairingPlayer {
+    pub name: String,
+    pub rating: Option<u32>,
+    pub federation: Option<String>,
+}
+
+/// The players expected on a board, for relays that only send board numbers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPairing {
+    pub board: u32,
+    pub round: Option<String>,
+    pub white: TlcsPairingPlayer,
+    pub black: TlcsPairingPlayer,
+}
+
+impl TlcsPairing {
+    /// The PGN tags the pairing fills in.
+    pub(super) fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("White", self.white.name.clone()),
+            ("Black", self.black.name.clone()),
+        ];
+        if let Some(round) = &self.round {
+            headers.push(("Round", round.clone()));
+        }
+        for (tag, value) in [
+            (
+                "WhiteElo",
+                self.white.rating.map(|rating| rating.to_string()),
+            ),
+            (
+                "BlackElo",
+                self.black.rating.map(|rating| rating.to_string()),
+            ),
+            ("WhiteFed", self.white.federation.clone()),
+            ("BlackFed", self.black.federation.clone()),
+        ] {
+            if let Some(value) = value {
+                headers.push((tag, value));
+            }
+        }
+        headers
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::TlcsInvalidPairings(message.into())
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Reads a CSV with a header row naming its columns: `White` and `Black`,
+/// and optionally `Board`, `Round`, `WhiteElo`, `BlackElo`, `WhiteFed` and
+/// `BlackFed`. Case, spaces and underscores in the names are ignored. Rows
+/// without a board are numbered in file order.
+pub(super) fn parse_csv(text: &str) -> Result<Vec<TlcsPairing>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let columns: HashMap<String, usize> = reader
+        .headers()
+        .map_err(|err| invalid(err.to_string()))?
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let name: String = name.chars().filter(char::is_ascii_alphanumeric).collect();
+            (name.to_lowercase(), index)
+        })
+        .collect();
+    for required in ["white", "black"] {
+        if !columns.contains_key(required) {
+            return Err(invalid(format!("missing the {required} column")));
+        }
+    }
+
+    let mut pairings = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| invalid(err.to_string()))?;
+        let column = |name: &str| non_empty(columns.get(name).and_then(|&index| record.get(index)));
+        let number = |name: &str| -> Result<Option<u32>, Error> {
+            column(name)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| invalid(format!("row {}: bad {name} {value:?}", row + 1)))
+                })
+                .transpose()
+        };
+        pairings.push(TlcsPairing {
+            board: number("board")?.unwrap_or(row as u32 + 1),
+            round: column("round"),
+            white: TlcsPairingPlayer {
+                name: column("white").unwrap_or_default(),
+                rating: number("whiteelo")?,
+                federation: column("whitefed"),
+            },
+            black: TlcsPairingPlayer {
+                name: column("black").unwrap_or_default(),
+                rating: number("blackelo")?,
+                federation: column("blackfed"),
+            },
+        });
+    }
+    Ok(pairings)
+}
+
+/// A player record (`001` line) of a FIDE TRF16 file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct TrfPlayer {
+    pub(super) number: u32,
+    pub(super) player: TlcsPairingPlayer,
+    /// Opponent number, colour (`w` or `b`) and result code of each round.
+    pub(super) rounds: Vec<(u32, char, char)>,
+}
+
+/// The text between the 1-based, inclusive columns `from` and `to`.
+fn trf_column(line: &[char], from: usize, to: usize) -> String {
+    line.get(from - 1..to.min(line.len()))
+        .unwrap_or_default()
+        .iter()
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+pub(super) fn parse_trf_players(text: &str) -> Vec<TrfPlayer> {
+    text.lines()
+        .filter(|line| line.starts_with("001"))
+        .filter_map(|line| {
+            let line: Vec<char> = line.chars().collect();
+            let number = trf_column(&line, 5, 8).parse().ok()?;
+            let rounds = (0..)
+                .map(|round| 92 + round * 10)
+                .take_while(|&start| start <= line.len())
+                .map(|start| {
+                    let at = |column: usize| {
+                        line.get(column - 1)
+                            .copied()
+                            .unwrap_or(' ')
+                            .to_ascii_lowercase()
+                    };
+                    let opponent = trf_column(&line, start, start + 3).parse().unwrap_or(0);
+                    (opponent, at(start + 5), at(start + 7))
+                })
+                .collect();
+            Some(TrfPlayer {
+                number,
+                player: TlcsPairingPlayer {
+                    name: trf_column(&line, 15, 47),
+                    rating: trf_column(&line, 49, 52).parse().ok().filter(|&r| r > 0),
+                    federation: non_empty(Some(&trf_column(&line, 54, 56))),
+                },
+                rounds,
+            })
+        })
+        .collect()
+}
+
+/// The pairings of `round` (1-based), or of the last round with any, of a
+/// TRF16 file. TRF has no board numbers, so boards are numbered by the best
+/// starting rank of their two players.
+pub(super) fn parse_trf(text: &str, round: Option<u32>) -> Result<Vec<TlcsPairing>, Error> {
+    let players = parse_trf_players(text);
+    let paired = |round: usize| {
+        players.iter().any(|player| {
+            player
+                .rounds
+                .get(round)
+                .is_some_and(|&(opponent, color, _)| opponent > 0 && color == 'w')
+        })
+    };
+    let index = match round {
+        Some(round) => round
+            .checked_sub(1)
+            .ok_or_else(|| invalid("rounds start at 1"))? as usize,
+        None => (0..players
+            .iter()
+            .map(|player| player.rounds.len())
+            .max()
+            .unwrap_or(0))
+            .rev()
+            .find(|&round| paired(round))
+            .ok_or_else(|| invalid("no paired round"))?,
+    };
+
+    let by_number: HashMap<u32, &TrfPlayer> = players
+        .iter()
+        .map(|player| (player.number, player))
+        .collect();
+    let mut games: Vec<(u32, &TrfPlayer, &TrfPlayer)> = players
+        .iter()
+        .filter_map(|white| {
+            let &(opponent, color, _) = white.rounds.get(index)?;
+            let black = by_number.get(&opponent).filter(|_| color == 'w')?;
+            Some((white.number.min(black.number), white, *black))
+        })
+        .collect();
+    games.sort_by_key(|(rank, _, _)| *rank);
+    Ok(games
+        .into_iter()
+        .enumerate()
+        .map(|(board, (_, white, black))| TlcsPairing {
+            board: board as u32 + 1,
+            round: Some((index + 1).to_string()),
+            white: white.player.clone(),
+            black: black.player.clone(),
+        })
+        .collect())
+}
+
+/// Loads the pairings of a CSV or TRF16 file (`.trf` or `.txt`) into the
+/// running session. Boards already recorded and those that appear later get
+/// the players' names, ratings and federations.
+#[tauri::command]
+#[specta::specta]
+pub async fn load_tlcs_pairings(
+    path: String,
+    round: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TlcsPairing>, Error> {
+    let text = std::fs::read_to_string(&path)?;
+    let is_trf = Path::new(&path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("trf") || ext.eq_ignore_ascii_case("txt"));
+    let pairings = if is_trf {
+        parse_trf(&text, round)?
+    } else {
+        parse_csv(&text)?
+    };
+
+    let guard = state.tlcs_handle.read().await;
+    let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+    handle
+        .recorder
+        .write()
+        .await
+        .set_pairings(pairings.clone())?;
+    Ok(pairings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_and_trf_pairings() {
+        let csv = "Board,White,White Elo,Black,black_fed\n2,Carlsen,2830,Caruana,USA\n";
+        let pairings = parse_csv(csv).unwrap();
+        assert_eq!(pairings[0].board, 2);
+        assert_eq!(pairings[0].white.rating, Some(2830));
+        assert_eq!(pairings[0].black.federation.as_deref(), Some("USA"));
+        assert!(parse_csv("Board,White\n1,Carlsen\n").is_err());
+
+        let trf = [
+            "001    1 m GM Carlsen, Magnus                   2830 NOR     1503014 1990/11/30  1.0    1     3 w 1     2 b  ",
+            "001    2 m GM Caruana, Fabiano                  2805 USA     2020009 1992/07/30  0.0    3     4 b 1     1 w  ",
+            "001    3 m GM Nepomniachtchi, Ian               2790 FID     4168119 1990/07/14  0.0    4     1 b 0     4 w  ",
+            "001    4 m GM Ding, Liren                       2780 CHN     8603677 1992/10/24  1.0    2     2 w 0     3 b  ",
+        ]
+        .join("\n");
+        let pairings = parse_trf(&trf, None).unwrap();
+        assert_eq!(pairings.len(), 2);
+        assert_eq!(pairings[0].round.as_deref(), Some("2"));
+        assert_eq!(pairings[0].white.name, "Caruana, Fabiano");
+        assert_eq!(pairings[0].black.name, "Carlsen, Magnus");
+        assert_eq!(pairings[1].white.federation.as_deref(), Some("FID"));
+        assert_eq!(
+            parse_trf(&trf, Some(1)).unwrap()[0].white.rating,
+            Some(2830)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{parse_clocks, TlcsGameState};
+
+/// Protocol used when the connect options force none.
+pub const DEFAULT_PROTOCOL: &str = "tlcs";
+
+/// Applies the lines of one relay dialect to the game state of a connection.
+pub trait TlcsLineParser: Send + Sync {
+    fn apply(&self, state: &mut TlcsGameState, line: &str);
+}
+
+/// The `fen`, `status`, `move`, `clock` and `offer` lines of a TLCS server.
+struct TlcsParser;
+
+impl TlcsLineParser for TlcsParser {
+    fn apply(&self, state: &mut TlcsGameState, line: &str) {
+        let normalized = line.trim();
+        if let Some(fen) = normalized.strip_prefix("fen ") {
+            state.fen = Some(fen.trim().to_string());
+        }
+
+        if let Some(status) = normalized.strip_prefix("status ") {
+            state.status = Some(status.trim().to_string());
+        }
+
+        if let Some(last_move) = normalized.strip_prefix("move ") {
+            state.last_move = Some(last_move.trim().to_string());
+        }
+
+        if let Some(clock_line) = normalized.strip_prefix("clock ") {
+            let (white, black) = parse_clocks(clock_line);
+            state.white_clock_ms = white.or(state.white_clock_ms);
+            state.black_clock_ms = black.or(state.black_clock_ms);
+        }
+
+        if normalized.eq_ignore_ascii_case("offer draw") {
+            state.can_accept_draw = true;
+        }
+
+        if normalized.eq_ignore_ascii_case("offer cancel") {
+            state.can_accept_draw = false;
+        }
+
+        state.can_offer_draw = true;
+        state.can_resign = true;
+    }
+}
+
+/// Line parsers by protocol name. New dialects are registered in `default`
+/// and picked with the `protocol` connect option.
+pub struct TlcsParserRegistry {
+    parsers: HashMap<String, Arc<dyn TlcsLineParser>>,
+}
+
+impl Default for TlcsParserRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            parsers: HashMap::new(),
+        };
+        registry.register(DEFAULT_PROTOCOL, TlcsParser);
+        registry
+    }
+}
+
+impl TlcsParserRegistry {
+    /// Adds a parser, replacing any registered under the same name.
+    pub fn register(&mut self, protocol: &str, parser: impl TlcsLineParser + 'static) {
+        self.parsers.insert(protocol.to_string(), Arc::new(parser));
+    }
+
+    pub fn get(&self, protocol: &str) -> Option<Arc<dyn TlcsLineParser>> {
+        self.parsers.get(protocol).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl TlcsLineParser for Upper {
+        fn apply(&self, state: &mut TlcsGameState, line: &str) {
+            state.last_move = line.strip_prefix("MV ").map(str::to_string);
+        }
+    }
+
+    #[test]
+    fn parsers_are_picked_by_protocol() {
+        let mut registry = TlcsParserRegistry::default();
+        registry.register("upper", Upper);
+
+        let mut state = TlcsGameState::default();
+        registry
+            .get(DEFAULT_PROTOCOL)
+            .unwrap()
+            .apply(&mut state, "move e2e4");
+        assert_eq!(state.last_move.as_deref(), Some("e2e4"));
+
+        registry.get("upper").unwrap().apply(&mut state, "MV e7e5");
+        assert_eq!(state.last_move.as_deref(), Some("e7e5"));
+        assert!(registry.get("fics").is_none());
+    }
+}
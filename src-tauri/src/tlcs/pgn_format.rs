@@ -0,0 +1,375 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use super::headers::{escape_tag_value, PgnHeaders};
+
+/// The line width of PGN export format.
+const DEFAULT_LINE_WIDTH: usize = 80;
+
+/// Layout of the movetext in recorded PGNs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPgnFormat {
+    /// Longest movetext line, 80 when unset. 0 keeps the movetext on a
+    /// single line.
+    pub line_width: Option<usize>,
+    /// Starts a new line after this many full moves.
+    pub moves_per_line: Option<usize>,
+    /// Leave out the `12...` that otherwise resumes Black's move after a
+    /// comment.
+    #[serde(default)]
+    pub omit_black_move_numbers: bool,
+}
+
+/// Lays out movetext tokens into lines.
+pub(super) struct MovetextWriter<'a> {
+    format: &'a TlcsPgnFormat,
+    text: String,
+    line_len: usize,
+    after_comment: bool,
+}
+
+impl<'a> MovetextWriter<'a> {
+    pub(super) fn new(format: &'a TlcsPgnFormat) -> Self {
+        Self {
+            format,
+            text: String::new(),
+            line_len: 0,
+            after_comment: false,
+        }
+    }
+
+    /// Continues movetext written elsewhere, so a first Black move gets its
+    /// `12...` like one after a comment.
+    pub(super) fn resumed(format: &'a TlcsPgnFormat) -> Self {
+        Self {
+            after_comment: true,
+            ..Self::new(format)
+        }
+    }
+
+    /// Adds a token, starting a new line first when it would not fit.
+    fn token(&mut self, token: &str) {
+        let width = self.format.line_width.unwrap_or(DEFAULT_LINE_WIDTH);
+        if self.line_len > 0 {
+            if width > 0 && self.line_len + 1 + token.len() > width {
+                self.line_break();
+            } else {
+                self.text.push(' ');
+                self.line_len += 1;
+            }
+        }
+        self.text.push_str(token);
+        self.line_len += token.len();
+    }
+
+    fn line_break(&mut self) {
+        if self.line_len > 0 {
+            self.text.push('\n');
+            self.line_len = 0;
+        }
+    }
+
+    /// Adds the move at `ply` (counted from 0) with its move number, which
+    /// is kept on the same line.
+    pub(super) fn san(&mut self, ply: usize, san: &str) {
+        let move_number = ply / 2 + 1;
+        if ply % 2 == 0 {
+            self.token(&format!("{move_number}. {san}"));
+        } else if self.after_comment && !self.format.omit_black_move_numbers {
+            self.token(&format!("{move_number}... {san}"));
+        } else {
+            self.token(san);
+        }
+        self.after_comment = false;
+        if ply % 2 == 1
+            && self
+                .format
+                .moves_per_line
+                .is_some_and(|moves| moves > 0 && move_number % moves == 0)
+        {
+            self.line_break();
+        }
+    }
+
+    pub(super) fn nag(&mut self, nag: u8) {
+        self.token(&format!("${nag}"));
+    }
+
+    /// Adds a brace comment, wrapped between its words.
+    pub(super) fn comment(&mut self, comment: &str) {
+        let words: Vec<_> = comment.split_whitespace().collect();
+        for (i, word) in words.iter().enumerate() {
+            let open = if i == 0 { "{" } else { "" };
+            let close = if i + 1 == words.len() { "}" } else { "" };
+            self.token(&format!("{open}{word}{close}"));
+        }
+        self.after_comment = !words.is_empty();
+    }
+
+    /// Ends the movetext with the game termination marker.
+    pub(super) fn finish(mut self, result: &str) -> String {
+        self.token(result);
+        self.text.push('\n');
+        self.text
+    }
+
+    /// The movetext so far, without a termination marker.
+    pub(super) fn into_text(self) -> String {
+        self.text
+    }
+}
+
+/// What the PGN of a game is rendered from: borrowed from its recorder, or
+/// owned by the PGN writer, which renders it away from the stream task.
+#[derive(Clone)]
+pub(super) struct PgnSnapshot<'a> {
+    pub(super) headers: Cow<'a, PgnHeaders>,
+    pub(super) setup_fen: Cow<'a, Option<String>>,
+    pub(super) sans: Cow<'a, [String]>,
+    pub(super) comments: Cow<'a, BTreeMap<usize, String>>,
+    pub(super) nags: Cow<'a, BTreeMap<usize, Vec<u8>>>,
+    /// Clock of the side that made the move at each ply.
+    pub(super) clocks: Cow<'a, BTreeMap<usize, u64>>,
+    pub(super) initial_clock_ms: Option<u64>,
+    pub(super) annotate_clock: bool,
+    pub(super) annotate_emt: bool,
+    pub(super) result: Cow<'a, Option<String>>,
+    pub(super) format: Cow<'a, TlcsPgnFormat>,
+}
+
+impl PgnSnapshot<'_> {
+    pub(super) fn into_owned(self) -> PgnSnapshot<'static> {
+        PgnSnapshot {
+            headers: Cow::Owned(self.headers.into_owned()),
+            setup_fen: Cow::Owned(self.setup_fen.into_owned()),
+            sans: Cow::Owned(self.sans.into_owned()),
+            comments: Cow::Owned(self.comments.into_owned()),
+            nags: Cow::Owned(self.nags.into_owned()),
+            clocks: Cow::Owned(self.clocks.into_owned()),
+            initial_clock_ms: self.initial_clock_ms,
+            annotate_clock: self.annotate_clock,
+            annotate_emt: self.annotate_emt,
+            result: Cow::Owned(self.result.into_owned()),
+            format: Cow::Owned(self.format.into_owned()),
+        }
+    }
+
+    /// Serializes the full game, headers included.
+    pub(super) fn render(&self) -> String {
+        let mut pgn = String::new();
+        for (key, value) in self.headers.iter() {
+            pgn.push_str(&format!("[{key} \"{}\"]\n", escape_tag_value(value)));
+        }
+        if self.headers.get("TimeControl").is_none() {
+            if let Some(time_control) = self.time_control() {
+                pgn.push_str(&format!("[TimeControl \"{time_control}\"]\n"));
+            }
+        }
+        if let Some(fen) = &*self.setup_fen {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{fen}\"]\n"));
+        }
+        pgn.push('\n');
+
+        let mut movetext = MovetextWriter::new(&self.format);
+        for ply in 0..self.sans.len() {
+            self.write_ply(&mut movetext, ply);
+        }
+        pgn.push_str(&movetext.finish(self.result.as_deref().unwrap_or("*")));
+        pgn
+    }
+
+    /// Adds the move at `ply` (counted from 0) with its NAGs and comment.
+    pub(super) fn write_ply(&self, movetext: &mut MovetextWriter, ply: usize) {
+        movetext.san(ply, &self.sans[ply]);
+        for nag in self.nags.get(&(ply + 1)).into_iter().flatten() {
+            movetext.nag(*nag);
+        }
+        let mut comment = Vec::new();
+        if let Some(clock) = self.clocks.get(&(ply + 1)).filter(|_| self.annotate_clock) {
+            comment.push(format!("[%clk {}]", clock_value(*clock)));
+        }
+        if let Some(elapsed) = self.elapsed_ms(ply + 1).filter(|_| self.annotate_emt) {
+            comment.push(format!("[%emt {}]", clock_value(elapsed)));
+        }
+        comment.extend(self.comments.get(&(ply + 1)).cloned());
+        if !comment.is_empty() {
+            movetext.comment(&comment.join(" "));
+        }
+    }
+
+    /// Time spent on the move at `ply` (1-based): the drop in its side's
+    /// clock since that side's previous move, or since the start for its
+    /// first move, plus the increment of the `TimeControl` header.
+    fn elapsed_ms(&self, ply: usize) -> Option<u64> {
+        let clock = *self.clocks.get(&ply)?;
+        let time_control = self
+            .time_control()
+            .and_then(|time_control| parse_time_control(&time_control));
+        let increment = time_control.map_or(0, |(_, increment)| increment);
+        let previous = if ply > 2 {
+            *self.clocks.get(&(ply - 2))?
+        } else {
+            time_control?.0
+        };
+        Some((previous + increment).saturating_sub(clock))
+    }
+
+    /// The `TimeControl` header, or one inferred from the clocks when the
+    /// options gave none and the game was followed from its first move.
+    fn time_control(&self) -> Option<String> {
+        match self.headers.get("TimeControl") {
+            Some(time_control) => Some(time_control.clone()),
+            None => Some(infer_time_control(self.initial_clock_ms?, &self.clocks)),
+        }
+    }
+}
+
+/// Formats milliseconds as the `h:mm:ss` of `[%clk]` and `[%emt]`.
+pub(super) fn clock_value(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Parses an `h:mm:ss` clock value, seconds possibly with a fraction.
+fn parse_clock_value(value: &str) -> Option<u64> {
+    let mut seconds = 0.0;
+    for part in value.split(':') {
+        seconds = seconds * 60.0 + part.trim().parse::<f64>().ok()?;
+    }
+    (seconds >= 0.0).then(|| (seconds * 1000.0).round() as u64)
+}
+
+/// Takes a `[%command value]` out of a comment. Returns its value in
+/// milliseconds, if present and valid, and the rest of the comment.
+pub(super) fn take_clock_command(comment: &str, command: &str) -> (Option<u64>, String) {
+    let open = format!("[%{command} ");
+    let Some(start) = comment.find(&open) else {
+        return (None, comment.to_string());
+    };
+    let Some(len) = comment[start..].find(']') else {
+        return (None, comment.to_string());
+    };
+    let value = parse_clock_value(&comment[start + open.len()..start + len]);
+    let rest = format!("{} {}", &comment[..start], &comment[start + len + 1..]);
+    (value, rest.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Base time and increment, in milliseconds, of a `TimeControl` header of
+/// the `5400+30` or `300` form.
+pub(super) fn parse_time_control(time_control: &str) -> Option<(u64, u64)> {
+    let (base, increment) = time_control.split_once('+').unwrap_or((time_control, "0"));
+    Some((
+        base.trim().parse::<u64>().ok()? * 1000,
+        increment.trim().parse::<u64>().ok()? * 1000,
+    ))
+}
+
+/// A `TimeControl` header for a game whose clocks showed `initial_ms`
+/// before the first move, `clocks` holding the clock of the side that moved
+/// after each ply. The increment is the largest gain of a side between two
+/// of its moves, since no move takes less than no time.
+pub(super) fn infer_time_control(initial_ms: u64, clocks: &BTreeMap<usize, u64>) -> String {
+    let increment_ms = clocks
+        .iter()
+        .filter_map(|(&ply, &clock)| {
+            let previous = if ply > 2 {
+                *clocks.get(&(ply - 2))?
+            } else {
+                initial_ms
+            };
+            clock.checked_sub(previous)
+        })
+        .max()
+        .unwrap_or(0);
+    // The first clock seen may already have run for a moment.
+    let mut base = (initial_ms + 500) / 1000;
+    if base >= 60 {
+        base = (base + 30) / 60 * 60;
+    }
+    match (increment_ms + 500) / 1000 {
+        0 => base.to_string(),
+        increment => format!("{base}+{increment}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_commands_round_trip() {
+        assert_eq!(clock_value(5_395_400), "1:29:55");
+        assert_eq!(
+            take_clock_command("[%clk 1:29:55] a new idea", "clk"),
+            (Some(5_395_000), "a new idea".into())
+        );
+        assert_eq!(
+            take_clock_command("fast [%emt 0:00:02.5]", "emt"),
+            (Some(2_500), "fast".into())
+        );
+        assert_eq!(
+            take_clock_command("[%eval 0.31]", "clk"),
+            (None, "[%eval 0.31]".into())
+        );
+        assert_eq!(parse_time_control("5400+30"), Some((5_400_000, 30_000)));
+        assert_eq!(parse_time_control("300"), Some((300_000, 0)));
+        assert_eq!(parse_time_control("40/7200:3600"), None);
+    }
+
+    #[test]
+    fn time_controls_are_inferred_from_clocks() {
+        // 90 minutes plus 30 seconds, the first clock seen after 0.8s.
+        let clocks = BTreeMap::from([
+            (1, 5_427_000),
+            (2, 5_429_000),
+            (3, 5_300_000),
+            (4, 5_458_000),
+        ]);
+        assert_eq!(infer_time_control(5_399_200, &clocks), "5400+30");
+        assert_eq!(
+            infer_time_control(300_000, &BTreeMap::from([(1, 297_000), (2, 295_500)])),
+            "300"
+        );
+        assert_eq!(infer_time_control(30_000, &BTreeMap::new()), "30");
+    }
+
+    #[test]
+    fn movetext_is_wrapped() {
+        let format = TlcsPgnFormat {
+            line_width: Some(20),
+            moves_per_line: Some(2),
+            omit_black_move_numbers: false,
+        };
+        let mut movetext = MovetextWriter::new(&format);
+        movetext.san(0, "e4");
+        movetext.comment("best by test");
+        movetext.san(1, "e5");
+        movetext.san(2, "Nf3");
+        movetext.san(3, "Nc6");
+        movetext.san(4, "Bb5");
+        movetext.nag(1);
+        assert_eq!(
+            movetext.finish("*"),
+            "1. e4 {best by test}\n1... e5 2. Nf3 Nc6\n3. Bb5 $1 *\n"
+        );
+
+        let single_line = TlcsPgnFormat {
+            line_width: Some(0),
+            ..TlcsPgnFormat::default()
+        };
+        let mut delta = MovetextWriter::resumed(&single_line);
+        delta.san(41, "Kg7");
+        delta.san(42, "Rd7+");
+        assert_eq!(delta.into_text(), "21... Kg7 22. Rd7+");
+    }
+}
@@ -0,0 +1,81 @@
+use std::num::NonZeroU32;
+
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+
+/// How fast frames may be sent to a server, which may otherwise kick the
+/// client for flooding.
+#[derive(Clone, Copy, Debug, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsRateLimit {
+    /// Frames that may be sent back to back.
+    pub burst: u32,
+    /// Frames per second once the burst is used up.
+    pub per_second: u32,
+}
+
+/// Emitted when a frame is held back by the rate limit.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsRateLimitedEvent {
+    pub frame: String,
+    pub delay_ms: u64,
+}
+
+fn quota(limit: TlcsRateLimit) -> Quota {
+    let per_second = NonZeroU32::new(limit.per_second).unwrap_or(NonZeroU32::MIN);
+    let burst = NonZeroU32::new(limit.burst).unwrap_or(NonZeroU32::MIN);
+    Quota::per_second(per_second).allow_burst(burst)
+}
+
+/// Holds frames back to keep a connection within its `TlcsRateLimit`.
+pub(crate) struct FrameLimiter {
+    limiter: DefaultDirectRateLimiter,
+}
+
+impl FrameLimiter {
+    pub(crate) fn new(limit: TlcsRateLimit) -> Self {
+        Self {
+            limiter: RateLimiter::direct(quota(limit)),
+        }
+    }
+
+    /// Waits until `frame` may be sent, announcing the wait to `notify`.
+    pub(crate) async fn throttle(&self, frame: &str, notify: impl FnOnce(TlcsRateLimitedEvent)) {
+        let Err(not_until) = self.limiter.check() else {
+            return;
+        };
+        let delay = not_until.wait_time_from(DefaultClock::default().now());
+        notify(TlcsRateLimitedEvent {
+            frame: frame.trim_end().to_string(),
+            delay_ms: delay.as_millis() as u64,
+        });
+        self.limiter.until_ready().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn limits_become_quotas() {
+        let quota = quota(TlcsRateLimit {
+            burst: 3,
+            per_second: 10,
+        });
+        assert_eq!(quota.burst_size().get(), 3);
+        assert_eq!(quota.replenish_interval(), Duration::from_millis(100));
+
+        let unset = super::quota(TlcsRateLimit {
+            burst: 0,
+            per_second: 0,
+        });
+        assert_eq!(unset.burst_size().get(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// What a client does once `max_attempts` reconnects in a row have failed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum ReconnectGiveUp {
+    /// Stop and report the connection as disconnected.
+    #[default]
+    Disconnect,
+    /// Stop and report an error, so the operator is alerted.
+    Error,
+}
+
+/// How a dropped TLCS connection is retried. The delay starts at
+/// `initial_delay_ms` and doubles after every failed attempt, up to
+/// `max_delay_ms`. `jitter` is the fraction of the delay, from 0 to 1, it may
+/// be shortened or lengthened by at random, so that clients dropped together
+/// do not reconnect in lockstep.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectPolicy {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    #[serde(default)]
+    pub jitter: f64,
+    /// Failed attempts in a row before giving up. Unlimited when unset.
+    pub max_attempts: Option<u32>,
+    #[serde(default)]
+    pub give_up: ReconnectGiveUp,
+}
+
+impl ReconnectPolicy {
+    /// Unlimited attempts, `delay_ms` apart.
+    pub fn fixed(delay_ms: u64) -> Self {
+        Self::backoff(delay_ms, delay_ms)
+    }
+
+    /// Unlimited attempts, doubling the delay from `initial_ms` up to `max_ms`.
+    pub fn backoff(initial_ms: u64, max_ms: u64) -> Self {
+        Self {
+            initial_delay_ms: initial_ms,
+            max_delay_ms: max_ms,
+            jitter: 0.0,
+            max_attempts: None,
+            give_up: ReconnectGiveUp::default(),
+        }
+    }
+
+    /// Delay before retry number `attempt`, counting from 1, without jitter.
+    fn base_delay_ms(&self, attempt: u32) -> u64 {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms.max(self.initial_delay_ms))
+    }
+
+    fn delay_ms(&self, attempt: u32) -> u64 {
+        let base = self.base_delay_ms(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return base;
+        }
+        let spread = rand::thread_rng().gen_range(-jitter..=jitter);
+        (base as f64 * (1.0 + spread)).round() as u64
+    }
+}
+
+/// Progress of the retries after a connection was lost, reported with the
+/// connection status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsRetryInfo {
+    /// The attempt about to be made, counting from 1.
+    pub attempt: u32,
+    pub max_attempts: Option<u32>,
+    /// Time until the attempt.
+    pub delay_ms: u64,
+}
+
+/// Counts failed attempts against a policy.
+pub(crate) struct Reconnector {
+    policy: ReconnectPolicy,
+    failures: u32,
+}
+
+impl Reconnector {
+    pub(crate) fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            policy,
+            failures: 0,
+        }
+    }
+
+    pub(crate) fn give_up(&self) -> ReconnectGiveUp {
+        self.policy.give_up
+    }
+
+    /// Attempts made since the last established connection.
+    pub(crate) fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Forgets earlier failures, once a connection was established.
+    pub(crate) fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Returns the next attempt after a failure, or `None` when the policy
+    /// gives up.
+    pub(crate) fn next_attempt(&mut self) -> Option<TlcsRetryInfo> {
+        if self
+            .policy
+            .max_attempts
+            .is_some_and(|max| self.failures >= max)
+        {
+            return None;
+        }
+        self.failures += 1;
+        Some(TlcsRetryInfo {
+            attempt: self.failures,
+            max_attempts: self.policy.max_attempts,
+            delay_ms: self.policy.delay_ms(self.failures),
+        })
+    }
+}
+
+impl TlcsRetryInfo {
+    pub(crate) fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        let seconds = (self.delay_ms as f64 / 1000.0).ceil() as u64;
+        match self.max_attempts {
+            Some(max) => format!("Retrying in {seconds}s (attempt {}/{max})", self.attempt),
+            None => format!("Retrying in {seconds}s (attempt {})", self.attempt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let policy = ReconnectPolicy::backoff(1000, 5000);
+        let delays: Vec<_> = (1..=5).map(|attempt| policy.delay_ms(attempt)).collect();
+        assert_eq!(delays, [1000, 2000, 4000, 5000, 5000]);
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = ReconnectPolicy {
+            jitter: 0.25,
+            ..ReconnectPolicy::fixed(8000)
+        };
+        for _ in 0..100 {
+            assert!((6000..=10000).contains(&policy.delay_ms(1)));
+        }
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let mut reconnector = Reconnector::new(ReconnectPolicy {
+            max_attempts: Some(2),
+            ..ReconnectPolicy::fixed(100)
+        });
+        assert_eq!(
+            reconnector.next_attempt().map(|retry| retry.attempt),
+            Some(1)
+        );
+        assert_eq!(
+            reconnector.next_attempt().map(|retry| retry.describe()),
+            Some("Retrying in 1s (attempt 2/2)".to_string())
+        );
+        assert_eq!(reconnector.next_attempt(), None);
+
+        reconnector.reset();
+        assert_eq!(
+            reconnector.next_attempt().map(|retry| retry.attempt),
+            Some(1)
+        );
+    }
+}
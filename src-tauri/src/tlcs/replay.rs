@@ -0,0 +1,146 @@
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tauri::{path::BaseDirectory, Manager};
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio::select;
+use tokio::sync::watch;
+
+use crate::error::Error;
+use crate::AppState;
+
+use super::logging::{open_log, parse_time, TlcsLogDirection, TlcsLogEntry};
+use super::{
+    session_log, spawn_tlcs_stream, PgnWriter, TlcsConnectOptions, TlcsDemux, TlcsRecorder,
+    TlcsSessionStarted, TlcsSource,
+};
+
+const REPLAY_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Lines received during a logged session, fed back to the recorder in
+/// place of a server connection.
+pub struct TlcsReplay {
+    lines: Vec<(Option<DateTime<Utc>>, String)>,
+    speed: f64,
+}
+
+impl TlcsReplay {
+    /// Reads the received lines of `path`, keeping only those of `session`
+    /// when one is given. Lines from logs older than the JSON format, written
+    /// as `... RX: <line>`, are replayed without pauses.
+    fn read(path: &Path, session: Option<&str>, speed: f64) -> Result<Self, Error> {
+        let mut lines = Vec::new();
+        for line in open_log(path)?.lines() {
+            let line = line?;
+            match serde_json::from_str::<TlcsLogEntry>(&line) {
+                Ok(entry) => {
+                    if entry.direction != Some(TlcsLogDirection::Rx)
+                        || session.is_some_and(|session| entry.session.as_deref() != Some(session))
+                    {
+                        continue;
+                    }
+                    lines.push((parse_time(&entry.timestamp).ok(), entry.payload));
+                }
+                Err(_) => {
+                    if let Some((_, payload)) = line.split_once("RX: ") {
+                        lines.push((None, payload.to_string()));
+                    }
+                }
+            }
+        }
+        Ok(Self { lines, speed })
+    }
+
+    /// Writes the lines into a pipe with the logged delays between them,
+    /// divided by the replay speed. A speed of zero or less replays without
+    /// pauses. The pipe is closed after the last line, which ends the session
+    /// like a server disconnect.
+    pub fn spawn(self, mut shutdown: watch::Receiver<bool>) -> DuplexStream {
+        let (reader, mut writer) = tokio::io::duplex(REPLAY_BUFFER_BYTES);
+        tokio::spawn(async move {
+            let mut previous: Option<DateTime<Utc>> = None;
+            for (timestamp, line) in self.lines {
+                let delay = previous
+                    .zip(timestamp)
+                    .filter(|_| self.speed > 0.0)
+                    .and_then(|(previous, timestamp)| (timestamp - previous).to_std().ok())
+                    .map(|gap| gap.div_f64(self.speed))
+                    .unwrap_or(Duration::ZERO);
+                previous = timestamp.or(previous);
+                if !delay.is_zero() {
+                    select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.changed() => return,
+                    }
+                }
+                if writer
+                    .write_all(format!("{line}\r\n").as_bytes())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+        reader
+    }
+}
+
+/// Records a new session from the lines received in a TLCS log, emitting the
+/// same events as a live connection. `speed` scales the logged pace: `1` is
+/// real time, `10` ten times faster and `0` as fast as possible.
+/// `options.host` and `options.port` are ignored.
+#[tauri::command]
+#[specta::specta]
+pub async fn replay_tlcs_log(
+    log_path: String,
+    speed: f64,
+    session: Option<String>,
+    options: TlcsConnectOptions,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<TlcsSessionStarted, Error> {
+    let replay = TlcsReplay::read(Path::new(&log_path), session.as_deref(), speed)?;
+
+    let tlcs_dir = app.path().resolve("tlcs", BaseDirectory::AppData)?;
+    std::fs::create_dir_all(&tlcs_dir)?;
+
+    let pgn_path = options
+        .pgn_path
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            tlcs_dir.join(format!(
+                "tlcs-replay-{}.pgn",
+                Utc::now().format("%Y%m%dT%H%M%SZ")
+            ))
+        });
+
+    let log = session_log(&tlcs_dir, &options, &pgn_path)?;
+    log.info(&format!(
+        "Replaying {} lines from {log_path} at speed {speed} -> {}",
+        replay.lines.len(),
+        pgn_path.to_string_lossy()
+    ));
+
+    let writer = PgnWriter::spawn(log.clone());
+    let recorder = TlcsRecorder::new(pgn_path.clone(), &options, None, log.clone(), writer)?;
+    let recorder = TlcsDemux::new(recorder, options.clone(), log.clone(), false);
+    let session_id = spawn_tlcs_stream(
+        recorder,
+        options,
+        TlcsSource::Replay(replay),
+        log,
+        app,
+        &state.tlcs_handle,
+    )
+    .await?;
+
+    Ok(TlcsSessionStarted {
+        session_id,
+        pgn_path: pgn_path.to_string_lossy().to_string(),
+    })
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// When a script runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsScriptTrigger {
+    /// Every time the socket opens, reconnects included.
+    Connect,
+    /// Once the credentials are sent. Never runs without a username.
+    Login,
+    /// When the server announces a new game with a `start` or `new game`
+    /// line.
+    NewGame,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsScriptStep {
+    pub command: String,
+    /// Wait before sending the command, counted from the previous step.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Commands sent on their own at a point of the connection, like
+/// `SET STYLE 12` then `OBSERVE 5` after logging in.
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsScript {
+    pub trigger: TlcsScriptTrigger,
+    pub steps: Vec<TlcsScriptStep>,
+}
+
+/// Whether `line` announces a new game.
+pub(super) fn announces_new_game(line: &str) -> bool {
+    let line = line.trim();
+    line.eq_ignore_ascii_case("start")
+        || line.eq_ignore_ascii_case("new game")
+        || line.starts_with("start ")
+}
+
+/// Runs the scripts of one connection, handing their commands to `commands`
+/// as their delays elapse. Scripts still waiting are stopped when the runner
+/// is dropped with the connection.
+pub(super) struct ScriptRunner {
+    scripts: Vec<TlcsScript>,
+    commands: mpsc::UnboundedSender<String>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ScriptRunner {
+    pub(super) fn new(scripts: &[TlcsScript], commands: mpsc::UnboundedSender<String>) -> Self {
+        Self {
+            scripts: scripts.to_vec(),
+            commands,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Starts every script of `trigger`.
+    pub(super) fn run(&mut self, trigger: TlcsScriptTrigger) {
+        self.tasks.retain(|task| !task.is_finished());
+        for script in self
+            .scripts
+            .iter()
+            .filter(|script| script.trigger == trigger)
+        {
+            let steps = script.steps.clone();
+            let commands = self.commands.clone();
+            self.tasks.push(tokio::spawn(async move {
+                for step in steps {
+                    if step.delay_ms > 0 {
+                        tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+                    }
+                    if commands.send(step.command).is_err() {
+                        return;
+                    }
+                }
+            }));
+        }
+    }
+}
+
+impl Drop for ScriptRunner {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_games_are_announced() {
+        assert!(announces_new_game("start"));
+        assert!(announces_new_game(" New Game "));
+        assert!(announces_new_game(
+            "start rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        ));
+        assert!(!announces_new_game("started"));
+        assert!(!announces_new_game("status playing"));
+    }
+
+    #[test]
+    fn scripts_are_read_from_profiles() {
+        let script: TlcsScript = serde_json::from_value(serde_json::json!({
+            "trigger": "login",
+            "steps": [
+                { "command": "SET STYLE 12" },
+                { "command": "OBSERVE 5", "delayMs": 500 },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(script.trigger, TlcsScriptTrigger::Login);
+        assert_eq!(script.steps[0].delay_ms, 0);
+        assert_eq!(script.steps[1].command, "OBSERVE 5");
+    }
+}
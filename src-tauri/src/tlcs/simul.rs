@@ -0,0 +1,88 @@
+use serde::Serialize;
+use specta::Type;
+
+use crate::error::Error;
+use crate::AppState;
+
+use super::live_analysis::TlcsEvalPoint;
+use super::{TlcsDemux, TlcsRecorder};
+
+/// Where the game of one board stands, for a wall of boards such as a
+/// simultaneous exhibition.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsBoardSummary {
+    pub board: Option<u32>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub fen: String,
+    pub plies: usize,
+    /// SAN of the last move recorded.
+    pub last_move: Option<String>,
+    /// The latest live evaluation, which may trail the last move while the
+    /// engine catches up. Without live analysis it is always `None`.
+    pub eval: Option<TlcsEvalPoint>,
+    pub white_clock_ms: Option<u64>,
+    pub black_clock_ms: Option<u64>,
+    pub result: Option<String>,
+}
+
+impl TlcsRecorder {
+    fn summary(&self, board: Option<u32>, eval: Option<TlcsEvalPoint>) -> TlcsBoardSummary {
+        TlcsBoardSummary {
+            board,
+            white: self.headers.get("White").cloned(),
+            black: self.headers.get("Black").cloned(),
+            fen: self.fen(),
+            plies: self.moves_recorded(),
+            last_move: self.sans.last().cloned(),
+            eval,
+            white_clock_ms: self.white_clock_ms,
+            black_clock_ms: self.black_clock_ms,
+            result: self.result.clone(),
+        }
+    }
+}
+
+impl TlcsDemux {
+    /// The boards of the session. The default board only counts while no
+    /// numbered board is open or when it has moves of its own.
+    fn boards_in_play(&self) -> Vec<(Option<u32>, &TlcsRecorder)> {
+        let default = (self.boards.is_empty() || self.default.moves_recorded() > 0)
+            .then_some((None, &self.default));
+        default
+            .into_iter()
+            .chain(
+                self.boards
+                    .iter()
+                    .map(|(board, recorder)| (Some(*board), recorder)),
+            )
+            .collect()
+    }
+}
+
+/// A summary of every board of the running session in one call, so a wall
+/// of boards is drawn without asking for each board in turn. Empty when no
+/// session is running.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_simul_overview(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TlcsBoardSummary>, Error> {
+    let guard = state.tlcs_handle.read().await;
+    let Some(handle) = guard.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let recorder = handle.recorder.read().await;
+    Ok(recorder
+        .boards_in_play()
+        .into_iter()
+        .map(|(board, board_recorder)| {
+            let eval = handle
+                .analysis
+                .as_ref()
+                .and_then(|analysis| analysis.latest(board));
+            board_recorder.summary(board, eval)
+        })
+        .collect())
+}
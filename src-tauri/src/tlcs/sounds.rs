@@ -0,0 +1,107 @@
+use serde::Serialize;
+use specta::Type;
+use tauri_specta::Event;
+
+use super::TlcsSide;
+
+/// Clocks under this many milliseconds are low when the options set no
+/// threshold.
+pub(super) const DEFAULT_LOW_TIME_MS: u64 = 30_000;
+
+/// The sound effect an event calls for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsSoundCue {
+    Move,
+    Capture,
+    /// Checks and mates, whether or not they capture.
+    Check,
+    LowTime,
+}
+
+/// Emitted for the last move a line recorded, and when a clock falls under
+/// `low_time_ms`, so the UI plays its sounds without parsing the moves.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsSoundEvent {
+    pub board: Option<u32>,
+    pub cue: TlcsSoundCue,
+    /// The side that moved, or whose clock runs low.
+    pub side: TlcsSide,
+    /// Plies of the game when the event was emitted.
+    pub ply: usize,
+}
+
+pub(super) fn move_cue(capture: bool, check: bool) -> TlcsSoundCue {
+    if check {
+        TlcsSoundCue::Check
+    } else if capture {
+        TlcsSoundCue::Capture
+    } else {
+        TlcsSoundCue::Move
+    }
+}
+
+/// Tells when the clock of each side falls under the low time threshold.
+pub(super) struct LowTimeWatch {
+    threshold_ms: u64,
+    white_low: bool,
+    black_low: bool,
+}
+
+impl LowTimeWatch {
+    pub(super) fn new(threshold_ms: u64) -> Self {
+        Self {
+            threshold_ms,
+            white_low: false,
+            black_low: false,
+        }
+    }
+
+    /// The sides whose clock just fell under the threshold. A clock given
+    /// time back above it warns again when it falls once more.
+    pub(super) fn update(&mut self, white: Option<u64>, black: Option<u64>) -> Vec<TlcsSide> {
+        let mut fallen = Vec::new();
+        for (side, clock, low) in [
+            (TlcsSide::White, white, &mut self.white_low),
+            (TlcsSide::Black, black, &mut self.black_low),
+        ] {
+            let Some(clock) = clock else {
+                continue;
+            };
+            let is_low = clock < self.threshold_ms;
+            if is_low && !*low {
+                fallen.push(side);
+            }
+            *low = is_low;
+        }
+        fallen
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.white_low = false;
+        self.black_low = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_time_warns_once_per_fall() {
+        let mut watch = LowTimeWatch::new(30_000);
+        assert!(watch.update(Some(45_000), Some(31_000)).is_empty());
+        assert_eq!(watch.update(Some(44_000), Some(29_500)), [TlcsSide::Black]);
+        assert!(watch.update(None, Some(12_000)).is_empty());
+        // An increment lifts black's clock back over the threshold.
+        assert_eq!(watch.update(Some(29_000), Some(40_000)), [TlcsSide::White]);
+        assert_eq!(watch.update(Some(28_000), Some(25_000)), [TlcsSide::Black]);
+        watch.reset();
+        assert_eq!(
+            watch.update(Some(5_000), Some(5_000)),
+            [TlcsSide::White, TlcsSide::Black]
+        );
+        assert_eq!(move_cue(true, true), TlcsSoundCue::Check);
+    }
+}
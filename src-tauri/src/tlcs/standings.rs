@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::error::Error;
+use crate::AppState;
+
+use super::{parse_header, TlcsSide};
+
+/// A finished game as needed for the standings.
+#[derive(Clone, Debug)]
+pub(super) struct ScoredGame {
+    pub(super) round: Option<String>,
+    pub(super) white: String,
+    pub(super) black: String,
+    /// White's score: 1, 0.5 or 0.
+    pub(super) white_score: f32,
+    /// Every tag of the game, for the ratings and federations.
+    pub(super) headers: BTreeMap<String, String>,
+}
+
+/// One game of a player in the crosstable.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsCrosstableEntry {
+    pub round: Option<String>,
+    pub opponent: String,
+    pub color: TlcsSide,
+    pub score: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsStanding {
+    pub rank: usize,
+    pub name: String,
+    pub points: f32,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Sum of the points of every opponent.
+    pub buchholz: f32,
+    /// Points of the beaten opponents plus half the points of those drawn.
+    pub sonneborn_berger: f32,
+    pub results: Vec<TlcsCrosstableEntry>,
+}
+
+/// Extracts the finished games from a multi-game PGN. Unfinished games and
+/// games without both player names are skipped.
+pub(super) fn scored_games(pgn: &str) -> Vec<ScoredGame> {
+    let mut games = Vec::new();
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+    let mut in_movetext = false;
+
+    let mut flush = |headers: &mut BTreeMap<String, String>| {
+        let white_score = match headers.get("Result").map(String::as_str) {
+            Some("1-0") => 1.0,
+            Some("0-1") => 0.0,
+            Some("1/2-1/2") => 0.5,
+            _ => {
+                headers.clear();
+                return;
+            }
+        };
+        let headers = std::mem::take(headers);
+        if let (Some(white), Some(black)) = (headers.get("White"), headers.get("Black")) {
+            games.push(ScoredGame {
+                round: headers.get("Round").cloned(),
+                white: white.clone(),
+                black: black.clone(),
+                white_score,
+                headers: headers.clone(),
+            });
+        }
+    };
+
+    for line in pgn.lines() {
+        match parse_header(line) {
+            Some((key, value)) => {
+                if in_movetext {
+                    flush(&mut headers);
+                    in_movetext = false;
+                }
+                headers.insert(key.to_string(), value);
+            }
+            None => in_movetext |= !line.trim().is_empty(),
+        }
+    }
+    flush(&mut headers);
+    games
+}
+
+/// Ranks the players by points, then Buchholz, then Sonneborn-Berger.
+pub(super) fn standings(games: &[ScoredGame]) -> Vec<TlcsStanding> {
+    let mut players: BTreeMap<&str, TlcsStanding> = BTreeMap::new();
+    for game in games {
+        for (name, opponent, color, score) in [
+            (&game.white, &game.black, TlcsSide::White, game.white_score),
+            (
+                &game.black,
+                &game.white,
+                TlcsSide::Black,
+                1.0 - game.white_score,
+            ),
+        ] {
+            let standing = players
+                .entry(name.as_str())
+                .or_insert_with(|| TlcsStanding {
+                    rank: 0,
+                    name: name.clone(),
+                    points: 0.0,
+                    games: 0,
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                    buchholz: 0.0,
+                    sonneborn_berger: 0.0,
+                    results: Vec::new(),
+                });
+            standing.points += score;
+            standing.games += 1;
+            match score {
+                s if s > 0.5 => standing.wins += 1,
+                s if s < 0.5 => standing.losses += 1,
+                _ => standing.draws += 1,
+            }
+            standing.results.push(TlcsCrosstableEntry {
+                round: game.round.clone(),
+                opponent: opponent.clone(),
+                color,
+                score,
+            });
+        }
+    }
+
+    let points: BTreeMap<String, f32> = players
+        .values()
+        .map(|standing| (standing.name.clone(), standing.points))
+        .collect();
+    let mut standings: Vec<TlcsStanding> = players.into_values().collect();
+    for standing in &mut standings {
+        for result in &standing.results {
+            let opponent_points = points[&result.opponent];
+            standing.buchholz += opponent_points;
+            standing.sonneborn_berger += result.score * opponent_points;
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        b.points
+            .total_cmp(&a.points)
+            .then(b.buchholz.total_cmp(&a.buchholz))
+            .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+            .then(a.name.cmp(&b.name))
+    });
+    for (index, standing) in standings.iter_mut().enumerate() {
+        standing.rank = index + 1;
+    }
+    standings
+}
+
+/// Computes the standings of the running session, or of every PGN file in
+/// `directory` when one is given.
+#[tauri::command]
+#[specta::specta]
+pub async fn compute_tlcs_standings(
+    directory: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TlcsStanding>, Error> {
+    let pgn = match directory {
+        Some(directory) => {
+            let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+                .collect();
+            paths.sort();
+            let mut pgn = String::new();
+            for path in paths {
+                pgn.push_str(&std::fs::read_to_string(path)?);
+                pgn.push('\n');
+            }
+            pgn
+        }
+        None => {
+            let guard = state.tlcs_handle.read().await;
+            let handle = guard.as_ref().ok_or(Error::TlcsNotRecording)?;
+            let recorder = handle.recorder.read().await;
+            recorder.session_pgn()
+        }
+    };
+    Ok(standings(&scored_games(&pgn)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standings_use_tiebreaks() {
+        let pgn = "[Round \"1\"]\n[White \"A\"]\n[Black \"B\"]\n[Result \"1-0\"]\n\n1. e4 1-0\n\n\
+                   [Round \"1\"]\n[White \"C\"]\n[Black \"D\"]\n[Result \"1/2-1/2\"]\n\n1. d4 1/2-1/2\n\n\
+                   [Round \"2\"]\n[White \"B\"]\n[Black \"C\"]\n[Result \"0-1\"]\n\n1. c4 0-1\n\n\
+                   [Round \"2\"]\n[White \"D\"]\n[Black \"A\"]\n[Result \"*\"]\n\n1. Nf3 *\n";
+        let games = scored_games(pgn);
+        assert_eq!(games.len(), 3);
+
+        let table = standings(&games);
+        let names: Vec<_> = table.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["C", "A", "D", "B"]);
+        assert_eq!(table[0].points, 1.5);
+        assert_eq!(table[0].buchholz, 0.5);
+        assert_eq!(table[1].sonneborn_berger, 0.0);
+    }
+}
@@ -0,0 +1,236 @@
+use serde::Serialize;
+use specta::Type;
+use tauri_specta::Event;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio::net::UdpSocket;
+use tokio::select;
+use tokio::sync::watch;
+
+use super::{RotatingLog, TlcsSide};
+
+const TLCV_LOGON: &str = "LOGONv15:En Croissant";
+const TLCV_BUFFER_BYTES: usize = 64 * 1024;
+const MAX_DATAGRAM_BYTES: usize = 4096;
+
+/// A principal variation a playing engine reported, from a TLCV broadcast
+/// or a `WPV:` or `BPV:` kibitz line of a TLCS relay.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsEnginePvEvent {
+    pub board: Option<u32>,
+    pub side: TlcsSide,
+    pub depth: u32,
+    /// From the engine's point of view.
+    pub score_cp: i32,
+    pub time_ms: u64,
+    pub nodes: u64,
+    pub pv: String,
+}
+
+/// The TLCV messages the recorder cares about.
+#[derive(Debug, PartialEq, Eq)]
+enum TlcvMessage {
+    Fen(String),
+    Move(String),
+    Clock(TlcsSide, u64),
+    /// Kept as sent, since the recorder reads the same lines.
+    Pv(String),
+}
+
+impl TlcvMessage {
+    /// The TLCS line with the same meaning.
+    fn line(&self) -> String {
+        match self {
+            Self::Fen(fen) => format!("fen {fen}"),
+            Self::Move(san) => san.clone(),
+            Self::Clock(TlcsSide::White, ms) => format!("clock w={ms}"),
+            Self::Clock(TlcsSide::Black, ms) => format!("clock b={ms}"),
+            Self::Pv(line) => line.clone(),
+        }
+    }
+}
+
+/// Splits the sequence number off a `<seq>:<message>` datagram line.
+/// Unnumbered lines need no acknowledgement.
+fn split_sequence(line: &str) -> (Option<u64>, &str) {
+    match line.split_once(':') {
+        Some((seq, message)) if !seq.is_empty() && seq.bytes().all(|b| b.is_ascii_digit()) => {
+            (seq.parse().ok(), message)
+        }
+        _ => (None, line),
+    }
+}
+
+fn parse_message(message: &str) -> Option<TlcvMessage> {
+    let (command, args) = message.split_once(':')?;
+    let args = args.trim();
+    let side = |prefix: char| match prefix {
+        'W' => Some(TlcsSide::White),
+        'B' => Some(TlcsSide::Black),
+        _ => None,
+    };
+    match command.trim() {
+        "FEN" => Some(TlcvMessage::Fen(args.to_string())),
+        // `WMOVE: 12. Nf3` or `BMOVE: 12. ... Nf6`
+        "WMOVE" | "BMOVE" => args
+            .split_whitespace()
+            .last()
+            .map(|san| TlcvMessage::Move(san.to_string())),
+        // Remaining time in centiseconds.
+        command @ ("WTIME" | "BTIME") => {
+            let centis: u64 = args.parse().ok()?;
+            Some(TlcvMessage::Clock(
+                side(command.chars().next()?)?,
+                centis * 10,
+            ))
+        }
+        "WPV" | "BPV" => parse_pv(message).map(|_| TlcvMessage::Pv(message.trim().to_string())),
+        _ => None,
+    }
+}
+
+/// Parses a `WPV: <depth> <score cp> <time cs> <nodes> <pv>` line, or its
+/// `BPV:` counterpart.
+pub(super) fn parse_pv(line: &str) -> Option<TlcsEnginePvEvent> {
+    let (command, args) = line.split_once(':')?;
+    let side = match command.trim() {
+        "WPV" => TlcsSide::White,
+        "BPV" => TlcsSide::Black,
+        _ => return None,
+    };
+    let mut fields = args.trim().splitn(5, ' ');
+    let mut number = || {
+        fields
+            .next()
+            .and_then(|field| field.trim().parse::<i64>().ok())
+    };
+    let (depth, score, time, nodes) = (number()?, number()?, number()?, number()?);
+    Some(TlcsEnginePvEvent {
+        board: None,
+        side,
+        depth: depth.try_into().ok()?,
+        score_cp: score.try_into().ok()?,
+        time_ms: u64::try_from(time).ok()? * 10,
+        nodes: nodes.try_into().ok()?,
+        pv: fields.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+/// The `{+0.35/21 e5 Nf3 Nc6}` comment TLCV viewers write after an engine's
+/// move: its score in pawns from its own point of view, the depth and the
+/// PV.
+pub(super) fn pv_comment(pv: &TlcsEnginePvEvent) -> String {
+    let sign = if pv.score_cp < 0 { '-' } else { '+' };
+    let score = pv.score_cp.unsigned_abs();
+    let mut comment = format!("{sign}{}.{:02}/{}", score / 100, score % 100, pv.depth);
+    if !pv.pv.is_empty() {
+        comment.push(' ');
+        comment.push_str(&pv.pv);
+    }
+    comment
+}
+
+/// Logs on to a TLCV server over UDP and returns a pipe carrying its
+/// messages as TLCS lines. Every numbered datagram is acknowledged, and
+/// retransmissions of acknowledged ones are dropped. The pipe is closed when
+/// `shutdown` fires or the socket fails.
+pub(super) async fn connect(
+    host: &str,
+    port: u16,
+    log: RotatingLog,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<DuplexStream> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).await?;
+    socket.connect((host, port)).await?;
+    socket.send(TLCV_LOGON.as_bytes()).await?;
+
+    let (reader, mut writer) = tokio::io::duplex(TLCV_BUFFER_BYTES);
+    tokio::spawn(async move {
+        let mut buffer = vec![0; MAX_DATAGRAM_BYTES];
+        let mut last_sequence = None;
+        loop {
+            let len = select! {
+                _ = shutdown.changed() => break,
+                received = socket.recv(&mut buffer) => match received {
+                    Ok(len) => len,
+                    Err(err) => {
+                        log.error(&format!("TLCV socket error: {err}"));
+                        break;
+                    }
+                },
+            };
+            let datagram = String::from_utf8_lossy(&buffer[..len]).to_string();
+            for line in datagram.lines() {
+                let (sequence, message) = split_sequence(line);
+                if let Some(sequence) = sequence {
+                    let _ = socket.send(format!("ACK: {sequence}").as_bytes()).await;
+                    if last_sequence.is_some_and(|last| sequence <= last) {
+                        continue;
+                    }
+                    last_sequence = Some(sequence);
+                }
+                let Some(message) = parse_message(message) else {
+                    continue;
+                };
+                if writer
+                    .write_all(format!("{}\r\n", message.line()).as_bytes())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn datagrams_are_translated() {
+        assert_eq!(
+            split_sequence("17:WMOVE: 12. Nf3"),
+            (Some(17), "WMOVE: 12. Nf3")
+        );
+        assert_eq!(split_sequence("PING"), (None, "PING"));
+        assert_eq!(
+            parse_message("BMOVE: 12. ... Nf6").map(|m| m.line()),
+            Some("Nf6".to_string())
+        );
+        assert_eq!(
+            parse_message("WTIME: 17812").map(|m| m.line()),
+            Some("clock w=178120".to_string())
+        );
+        assert_eq!(
+            parse_message("BPV: 21 -35 1250 98765432 e5 Nf3 Nc6").map(|m| m.line()),
+            Some("BPV: 21 -35 1250 98765432 e5 Nf3 Nc6".to_string())
+        );
+        assert_eq!(parse_message("BPV: deep"), None);
+        assert_eq!(parse_message("CT: hello"), None);
+    }
+
+    #[test]
+    fn engine_pvs_are_parsed() {
+        let pv = parse_pv("BPV: 21 -35 1250 98765432 e5 Nf3 Nc6").unwrap();
+        assert_eq!(
+            pv,
+            TlcsEnginePvEvent {
+                board: None,
+                side: TlcsSide::Black,
+                depth: 21,
+                score_cp: -35,
+                time_ms: 12500,
+                nodes: 98765432,
+                pv: "e5 Nf3 Nc6".to_string(),
+            }
+        );
+        assert_eq!(pv_comment(&pv), "-0.35/21 e5 Nf3 Nc6");
+        let mate_soon = parse_pv("WPV: 30 1205 300 1000").unwrap();
+        assert_eq!(pv_comment(&mate_soon), "+12.05/30");
+        assert_eq!(parse_pv("e4 e5"), None);
+    }
+}
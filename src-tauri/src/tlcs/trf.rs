@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use tauri::{path::BaseDirectory, Manager};
+
+use crate::{error::Error, AppState};
+
+use super::standings::{scored_games, standings, ScoredGame};
+use super::{session_id, TlcsSide};
+
+/// What the tags of the recorded games say about a player.
+#[derive(Default)]
+struct Entrant {
+    title: String,
+    rating: Option<u32>,
+    federation: String,
+    fide_id: String,
+}
+
+impl Entrant {
+    fn update(&mut self, game: &ScoredGame, side: TlcsSide) {
+        let tag = |name: &str| {
+            let prefix = match side {
+                TlcsSide::White => "White",
+                TlcsSide::Black => "Black",
+            };
+            game.headers
+                .get(&format!("{prefix}{name}"))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        if let Some(title) = tag("Title") {
+            self.title = title;
+        }
+        if let Some(rating) = tag("Elo").and_then(|elo| elo.parse().ok()) {
+            self.rating = Some(rating);
+        }
+        if let Some(federation) = tag("Fed") {
+            self.federation = federation;
+        }
+        if let Some(fide_id) = tag("FideId") {
+            self.fide_id = fide_id;
+        }
+    }
+}
+
+/// Orders round tags like `2`, `10` and `3.1` by their numbers.
+fn round_key(round: &str) -> (Vec<u32>, String) {
+    let numbers = round
+        .split('.')
+        .map(|part| part.trim().parse().unwrap_or(u32::MAX))
+        .collect();
+    (numbers, round.to_string())
+}
+
+fn trf_date(pgn_date: &str) -> Option<String> {
+    let date = pgn_date.replace('.', "/");
+    (date.len() == 10 && !date.contains('?')).then_some(date)
+}
+
+/// Writes the finished games of `pgn` as a FIDE TRF16 report. Players are
+/// numbered by rating, then name, and ranked like `compute_tlcs_standings`.
+/// Titles, ratings, federations and FIDE ids come from the `WhiteTitle`,
+/// `WhiteElo`, `WhiteFed` and `WhiteFideId` tags and their Black twins.
+pub(super) fn write_trf(pgn: &str) -> String {
+    let games = scored_games(pgn);
+    let table = standings(&games);
+
+    let mut entrants: BTreeMap<&str, Entrant> = BTreeMap::new();
+    for game in &games {
+        for (name, side) in [
+            (&game.white, TlcsSide::White),
+            (&game.black, TlcsSide::Black),
+        ] {
+            entrants.entry(name).or_default().update(game, side);
+        }
+    }
+    let mut order: Vec<&str> = entrants.keys().copied().collect();
+    order.sort_by_key(|name| (std::cmp::Reverse(entrants[name].rating), *name));
+    let numbers: BTreeMap<&str, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (*name, index + 1))
+        .collect();
+
+    let mut rounds: Vec<&str> = games
+        .iter()
+        .map(|game| game.round.as_deref().unwrap_or("?"))
+        .collect();
+    rounds.sort_by_key(|round| round_key(round));
+    rounds.dedup();
+
+    let header = |name: &str| games.iter().find_map(|game| game.headers.get(name));
+    let mut dates: Vec<String> = games
+        .iter()
+        .filter_map(|game| game.headers.get("Date").and_then(|date| trf_date(date)))
+        .collect();
+    dates.sort();
+
+    let mut trf = String::new();
+    if let Some(event) = header("Event") {
+        let _ = writeln!(trf, "012 {event}");
+    }
+    if let Some(site) = header("Site") {
+        let _ = writeln!(trf, "022 {site}");
+    }
+    if let (Some(first), Some(last)) = (dates.first(), dates.last()) {
+        let _ = writeln!(trf, "042 {first}");
+        let _ = writeln!(trf, "052 {last}");
+    }
+    let _ = writeln!(trf, "062 {}", order.len());
+    let _ = writeln!(
+        trf,
+        "072 {}",
+        entrants
+            .values()
+            .filter(|entrant| entrant.rating.is_some())
+            .count()
+    );
+
+    for name in &order {
+        let entrant = &entrants[name];
+        let Some(standing) = table.iter().find(|standing| standing.name == *name) else {
+            continue;
+        };
+        let _ = write!(
+            trf,
+            "001 {:>4}  {:>3.3} {:<33.33} {:>4} {:>3.3} {:>11.11} {:>10} {:>4.1} {:>4}",
+            numbers[name],
+            entrant.title,
+            name,
+            entrant
+                .rating
+                .map(|rating| rating.to_string())
+                .unwrap_or_default(),
+            entrant.federation,
+            entrant.fide_id,
+            "",
+            standing.points,
+            standing.rank,
+        );
+        for round in &rounds {
+            let game = games.iter().find(|game| {
+                game.round.as_deref().unwrap_or("?") == *round
+                    && (game.white == *name || game.black == *name)
+            });
+            let Some(game) = game else {
+                trf.push_str("          ");
+                continue;
+            };
+            let (opponent, color, score) = if game.white == *name {
+                (&game.black, 'w', game.white_score)
+            } else {
+                (&game.white, 'b', 1.0 - game.white_score)
+            };
+            let result = match score {
+                s if s > 0.5 => '1',
+                s if s < 0.5 => '0',
+                _ => '=',
+            };
+            let _ = write!(trf, "  {:>4} {color} {result}", numbers[opponent.as_str()]);
+        }
+        trf.push('\n');
+    }
+    trf
+}
+
+/// Writes a TRF16 report of the finished games of `session` next to its
+/// PGNs, for rating reports, and returns its path. The session may still be
+/// running.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_tlcs_trf(
+    session: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, Error> {
+    let tlcs_dir = app.path().resolve("tlcs", BaseDirectory::AppData)?;
+    let running = {
+        let guard = state.tlcs_handle.read().await;
+        match guard.as_ref() {
+            Some(handle) => {
+                let recorder = handle.recorder.read().await;
+                let pgn_path = recorder.default.pgn_path();
+                (session_id(&pgn_path).as_deref() == Some(session.as_str()))
+                    .then(|| (pgn_path, recorder.session_pgn()))
+            }
+            None => None,
+        }
+    };
+
+    let (pgn_path, pgn) = match running {
+        Some(running) => running,
+        None => {
+            let board_prefix = format!("{session}-board");
+            let mut paths: Vec<PathBuf> = std::fs::read_dir(&tlcs_dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension().is_some_and(|ext| ext == "pgn")
+                        && path.file_stem().is_some_and(|stem| {
+                            let stem = stem.to_string_lossy();
+                            stem == session.as_str() || stem.starts_with(&board_prefix)
+                        })
+                })
+                .collect();
+            if paths.is_empty() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No PGN recorded for session {session}"),
+                )
+                .into());
+            }
+            paths.sort();
+            let mut pgn = String::new();
+            for path in &paths {
+                pgn.push_str(&std::fs::read_to_string(path)?);
+                pgn.push('\n');
+            }
+            (tlcs_dir.join(format!("{session}.pgn")), pgn)
+        }
+    };
+
+    let trf_path = pgn_path.with_extension("trf");
+    std::fs::write(&trf_path, write_trf(&pgn))?;
+    Ok(trf_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::pairings::parse_trf_players;
+    use super::*;
+
+    #[test]
+    fn exported_reports_read_back_as_pairings() {
+        let pgn = "[Event \"Club\"]\n[Date \"2024.05.01\"]\n[Round \"1\"]\n[White \"A\"]\n\
+                   [Black \"B\"]\n[WhiteElo \"2100\"]\n[BlackFed \"ESP\"]\n[Result \"1-0\"]\n\n\
+                   1. e4 1-0\n\n\
+                   [Round \"2\"]\n[White \"B\"]\n[Black \"A\"]\n[Result \"1/2-1/2\"]\n\n1. d4 1/2-1/2\n";
+        let trf = write_trf(pgn);
+        assert!(trf.starts_with("012 Club\n042 2024/05/01\n052 2024/05/01\n062 2\n"));
+
+        let players = parse_trf_players(&trf);
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].player.name, "A");
+        assert_eq!(players[0].player.rating, Some(2100));
+        assert_eq!(players[0].rounds, [(2, 'w', '1'), (2, 'b', '=')]);
+        assert_eq!(players[1].player.federation.as_deref(), Some("ESP"));
+        assert_eq!(players[1].rounds, [(1, 'b', '0'), (1, 'w', '=')]);
+    }
+}
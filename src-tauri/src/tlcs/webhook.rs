@@ -0,0 +1,119 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+use tokio::sync::mpsc;
+
+use crate::error::Error;
+
+use super::{http_client, RotatingLog, TlcsEmitter};
+
+/// An HTTP endpoint told about every recorded game that finishes.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsWebhook {
+    pub url: String,
+    /// Sent as a bearer token when set.
+    pub token: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsWebhookEvent {
+    pub url: String,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+/// The JSON body posted to the webhooks when a game ends.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct TlcsFinishedGame {
+    pub(super) board: Option<u32>,
+    pub(super) event: Option<String>,
+    pub(super) round: Option<String>,
+    pub(super) white: Option<String>,
+    pub(super) black: Option<String>,
+    pub(super) result: String,
+    pub(super) plies: usize,
+    /// Seconds from the first recorded move to the result, unknown for
+    /// resumed games.
+    pub(super) duration_secs: Option<u64>,
+    pub(super) pgn: String,
+}
+
+/// Posts finished games to the configured webhooks, one at a time and in
+/// the order they finished.
+pub struct TlcsWebhooks {
+    games: mpsc::UnboundedSender<TlcsFinishedGame>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TlcsWebhooks {
+    pub fn spawn(webhooks: Vec<TlcsWebhook>, events: TlcsEmitter, log: RotatingLog) -> Self {
+        let (games, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_webhooks(webhooks, events, log, rx));
+        Self { games, task }
+    }
+
+    pub(super) fn notify(&self, game: TlcsFinishedGame) {
+        let _ = self.games.send(game);
+    }
+
+    /// Stops after the games already queued are delivered.
+    pub async fn stop(self) {
+        drop(self.games);
+        let _ = self.task.await;
+    }
+}
+
+async fn post_game(
+    client: &Client,
+    webhook: &TlcsWebhook,
+    game: &TlcsFinishedGame,
+) -> Result<(), Error> {
+    let mut request = client.post(&webhook.url).json(game);
+    if let Some(token) = &webhook.token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await?.error_for_status()?;
+    Ok(())
+}
+
+async fn run_webhooks(
+    webhooks: Vec<TlcsWebhook>,
+    events: TlcsEmitter,
+    log: RotatingLog,
+    mut rx: mpsc::UnboundedReceiver<TlcsFinishedGame>,
+) {
+    let client = http_client();
+    while let Some(game) = rx.recv().await {
+        for webhook in &webhooks {
+            let result = post_game(&client, webhook, &game).await;
+            let message = match &result {
+                Ok(()) => {
+                    log.info(&format!(
+                        "Posted {} result {} to {}",
+                        game.board
+                            .map_or_else(|| "game".to_string(), |board| format!("board {board}")),
+                        game.result,
+                        webhook.url
+                    ));
+                    None
+                }
+                Err(err) => {
+                    log.error(&format!("Webhook {} failed: {err}", webhook.url));
+                    Some(err.to_string())
+                }
+            };
+            events.emit(
+                "tlcs-webhook",
+                TlcsWebhookEvent {
+                    url: webhook.url.clone(),
+                    success: result.is_ok(),
+                    message,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, oneshot};
+
+use super::overlay::write_atomically;
+use super::pgn_format::PgnSnapshot;
+use super::RotatingLog;
+
+enum WriteRequest {
+    Write(PathBuf, Arc<String>, PgnSnapshot<'static>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Renders and writes PGN snapshots on a task of its own, so the stream task
+/// neither renders the games nor waits for the disk. Snapshots of a file
+/// queued while a write is in progress are coalesced; only the latest one is
+/// written.
+#[derive(Clone)]
+pub struct PgnWriter {
+    requests: mpsc::UnboundedSender<WriteRequest>,
+}
+
+impl PgnWriter {
+    pub fn spawn(log: RotatingLog) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_pgn_writer(log, rx));
+        Self { requests }
+    }
+
+    /// Queues `game` for writing to `path`, after the `archived` games.
+    pub fn write(&self, path: PathBuf, archived: Arc<String>, game: PgnSnapshot<'static>) {
+        let _ = self
+            .requests
+            .send(WriteRequest::Write(path, archived, game));
+    }
+
+    /// Waits until every snapshot queued so far is on disk.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.requests.send(WriteRequest::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+async fn run_pgn_writer(log: RotatingLog, mut rx: mpsc::UnboundedReceiver<WriteRequest>) {
+    while let Some(request) = rx.recv().await {
+        let mut pending = BTreeMap::new();
+        let mut flushed = Vec::new();
+        let mut next = Some(request);
+        while let Some(request) = next {
+            match request {
+                WriteRequest::Write(path, archived, game) => {
+                    pending.insert(path, (archived, game));
+                }
+                WriteRequest::Flush(done) => flushed.push(done),
+            }
+            next = rx.try_recv().ok();
+        }
+
+        let written = tokio::task::spawn_blocking(move || {
+            pending
+                .into_iter()
+                .filter_map(|(path, (archived, game))| {
+                    write_atomically(&path, &format!("{archived}{}", game.render()))
+                        .err()
+                        .map(|err| (path, err))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await;
+        for (path, err) in written.unwrap_or_default() {
+            log.error(&format!(
+                "Failed to write PGN {}: {err}",
+                path.to_string_lossy()
+            ));
+        }
+        for done in flushed {
+            let _ = done.send(());
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+
+use crate::error::Error;
+use crate::tlcs::{TlcsConnectOptions, TlcsRecorder};
+use crate::tlcs_client::{TlcsGameListing, TrackedGame};
+
+/// Rules picking games from the server's game list to subscribe to and record
+/// without choosing them by hand.
+#[derive(Clone, Debug, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsAutoSubscribeRules {
+    /// Regular expression matched against both player names; a game matches
+    /// when either player does.
+    pub player: Option<String>,
+    /// Both players must be rated at least this much.
+    pub min_rating: Option<u32>,
+    pub first_board: Option<u32>,
+    pub last_board: Option<u32>,
+    /// Most games followed at once. Further matches wait for a slot.
+    pub max_sessions: usize,
+    /// Directory the PGN of each game is written to. Defaults to the app's
+    /// `tlcs` directory.
+    pub pgn_dir: Option<String>,
+}
+
+/// Emitted when the rules subscribe to a game or drop it because the server
+/// no longer lists it.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsAutoSubscribeEvent {
+    pub game: TlcsGameListing,
+    pub subscribed: bool,
+    pub pgn_path: String,
+}
+
+struct AutoSession {
+    game: TlcsGameListing,
+    pgn_path: PathBuf,
+    recorder: TlcsRecorder,
+}
+
+/// Applies the rules to every game list the server sends.
+pub(crate) struct AutoSubscriber {
+    rules: TlcsAutoSubscribeRules,
+    player: Option<Regex>,
+    /// Where the recorders log, the app's `tlcs` directory.
+    tlcs_dir: PathBuf,
+    pgn_dir: PathBuf,
+    /// Followed games, by game id.
+    sessions: HashMap<String, AutoSession>,
+}
+
+impl AutoSubscriber {
+    pub(crate) fn new(rules: TlcsAutoSubscribeRules, tlcs_dir: PathBuf) -> Result<Self, Error> {
+        let player = rules.player.as_deref().map(Regex::new).transpose()?;
+        let pgn_dir = rules
+            .pgn_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| tlcs_dir.clone());
+        std::fs::create_dir_all(&pgn_dir)?;
+        Ok(Self {
+            rules,
+            player,
+            tlcs_dir,
+            pgn_dir,
+            sessions: HashMap::new(),
+        })
+    }
+
+    fn matches(&self, game: &TlcsGameListing) -> bool {
+        let underrated = |rating: Option<u32>| {
+            self.rules
+                .min_rating
+                .is_some_and(|min| rating.unwrap_or(0) < min)
+        };
+        let player_matches = match &self.player {
+            Some(player) => player.is_match(&game.white) || player.is_match(&game.black),
+            None => true,
+        };
+        player_matches
+            && !underrated(game.white_rating)
+            && !underrated(game.black_rating)
+            && !self
+                .rules
+                .first_board
+                .is_some_and(|first| game.board < first)
+            && !self.rules.last_board.is_some_and(|last| game.board > last)
+    }
+
+    /// Drops the games missing from `games`, then follows matching games up
+    /// to the session cap. Returns the events of both.
+    pub(crate) fn update(&mut self, games: &[TlcsGameListing]) -> Vec<TlcsAutoSubscribeEvent> {
+        let mut events = Vec::new();
+
+        let listed: Vec<_> = games.iter().map(|game| game.board.to_string()).collect();
+        let gone: Vec<_> = self
+            .sessions
+            .keys()
+            .filter(|game_id| !listed.contains(game_id))
+            .cloned()
+            .collect();
+        for game_id in gone {
+            if let Some(session) = self.sessions.remove(&game_id) {
+                info!("Auto-subscription dropped game {game_id}");
+                events.push(session.event(false));
+            }
+        }
+
+        for game in games {
+            if self.sessions.len() >= self.rules.max_sessions {
+                break;
+            }
+            let game_id = game.board.to_string();
+            if self.sessions.contains_key(&game_id) || !self.matches(game) {
+                continue;
+            }
+            let pgn_path = game_pgn_path(&self.pgn_dir, game);
+            let session = match AutoSession::new(&self.tlcs_dir, game, pgn_path) {
+                Ok(session) => session,
+                Err(err) => {
+                    warn!("Cannot record game {game_id}: {err}");
+                    continue;
+                }
+            };
+            info!("Auto-subscribed to game {game_id}");
+            events.push(session.event(true));
+            self.sessions.insert(game_id, session);
+        }
+        events
+    }
+
+    /// Records the moves of a followed game its recorder has not seen yet.
+    /// The recorder rewrites the PGN in full, so the `Result` header follows
+    /// the game's end.
+    pub(crate) fn record(&mut self, game_id: &str, tracked: &TrackedGame) {
+        let Some(session) = self.sessions.get_mut(game_id) else {
+            return;
+        };
+        if let Err(err) = session
+            .recorder
+            .follow_moves(&tracked.moves, tracked.result.as_deref())
+        {
+            warn!("Failed to record game {game_id}: {err}");
+        }
+    }
+}
+
+/// A PGN of its own for `game` in `pgn_dir`, named after its board, round and
+/// players, so the next game on the board does not overwrite it. A number is
+/// added when the file already exists, e.g. for a replayed pairing.
+fn game_pgn_path(pgn_dir: &Path, game: &TlcsGameListing) -> PathBuf {
+    let part = |text: &str| {
+        text.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("_")
+    };
+    let mut stem = format!("tlcs-board{}", game.board);
+    if let Some(round) = &game.round {
+        stem.push_str(&format!("-r{}", part(round)));
+    }
+    stem.push_str(&format!("-{}-{}", part(&game.white), part(&game.black)));
+
+    let mut path = pgn_dir.join(format!("{stem}.pgn"));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = pgn_dir.join(format!("{stem}-{copy}.pgn"));
+    }
+    path
+}
+
+impl AutoSession {
+    /// Starts recording `game` to `pgn_path`, with the players, ratings and
+    /// round of the server's game list.
+    fn new(tlcs_dir: &Path, game: &TlcsGameListing, pgn_path: PathBuf) -> Result<Self, Error> {
+        let mut headers = HashMap::new();
+        if let Some(round) = &game.round {
+            headers.insert("Round".to_string(), round.clone());
+        }
+        if let Some(rating) = game.white_rating {
+            headers.insert("WhiteElo".to_string(), rating.to_string());
+        }
+        if let Some(rating) = game.black_rating {
+            headers.insert("BlackElo".to_string(), rating.to_string());
+        }
+        let options = TlcsConnectOptions {
+            white: Some(game.white.clone()),
+            black: Some(game.black.clone()),
+            extra_headers: Some(headers),
+            ..Default::default()
+        };
+        let recorder =
+            TlcsRecorder::for_game(tlcs_dir, pgn_path.clone(), &options, Some(game.board))?;
+        Ok(Self {
+            game: game.clone(),
+            pgn_path,
+            recorder,
+        })
+    }
+
+    fn event(&self, subscribed: bool) -> TlcsAutoSubscribeEvent {
+        TlcsAutoSubscribeEvent {
+            game: self.game.clone(),
+            subscribed,
+            pgn_path: self.pgn_path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(player: Option<&str>, max_sessions: usize) -> TlcsAutoSubscribeRules {
+        TlcsAutoSubscribeRules {
+            player: player.map(str::to_string),
+            min_rating: Some(2400),
+            first_board: Some(1),
+            last_board: Some(10),
+            max_sessions,
+            pgn_dir: None,
+        }
+    }
+
+    fn game(board: u32, white: &str, black: &str, rating: u32) -> TlcsGameListing {
+        TlcsGameListing {
+            board,
+            round: Some("3.1".to_string()),
+            white: white.to_string(),
+            white_rating: Some(rating),
+            black: black.to_string(),
+            black_rating: Some(rating),
+        }
+    }
+
+    #[test]
+    fn rules_match_players_ratings_and_boards() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscriber =
+            AutoSubscriber::new(rules(Some("^Carlsen"), 4), dir.path().to_path_buf()).unwrap();
+        assert!(subscriber.matches(&game(1, "Carlsen, Magnus", "Caruana, Fabiano", 2800)));
+        assert!(subscriber.matches(&game(1, "Nepo", "Carlsen, Magnus", 2800)));
+        assert!(!subscriber.matches(&game(1, "Nepo", "Caruana, Fabiano", 2800)));
+        assert!(!subscriber.matches(&game(1, "Carlsen, Magnus", "Guest", 2000)));
+        assert!(!subscriber.matches(&game(11, "Carlsen, Magnus", "Caruana", 2800)));
+
+        let mut unrated = game(2, "Carlsen, Magnus", "Guest", 2800);
+        unrated.black_rating = None;
+        assert!(!subscriber.matches(&unrated));
+    }
+
+    #[tokio::test]
+    async fn updates_follow_matches_up_to_the_cap_and_drop_unlisted_games() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut subscriber = AutoSubscriber::new(rules(None, 1), dir.path().to_path_buf()).unwrap();
+        let first = game(1, "Carlsen, Magnus", "Caruana, Fabiano", 2800);
+        let weak = game(2, "Guest", "Guest", 1500);
+        let second = game(3, "Nepo", "Ding", 2800);
+
+        let events = subscriber.update(&[first.clone(), weak.clone(), second.clone()]);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].subscribed);
+        assert_eq!(events[0].game, first);
+
+        let events = subscriber.update(&[weak, second.clone()]);
+        assert_eq!(events.len(), 2);
+        assert!(!events[0].subscribed);
+        assert_eq!(events[0].game, first);
+        assert!(events[1].subscribed);
+        assert_eq!(events[1].game, second);
+        assert_ne!(events[0].pgn_path, events[1].pgn_path);
+    }
+
+    #[test]
+    fn games_on_a_board_get_pgns_of_their_own() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = game(1, "Carlsen, Magnus", "Caruana, Fabiano", 2800);
+        let path = game_pgn_path(dir.path(), &first);
+        assert_eq!(
+            path,
+            dir.path()
+                .join("tlcs-board1-r3_1-Carlsen_Magnus-Caruana_Fabiano.pgn")
+        );
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(
+            game_pgn_path(dir.path(), &first),
+            dir.path()
+                .join("tlcs-board1-r3_1-Carlsen_Magnus-Caruana_Fabiano-2.pgn")
+        );
+        let next = TlcsGameListing {
+            round: Some("4".to_string()),
+            ..first
+        };
+        assert_ne!(game_pgn_path(dir.path(), &next), path);
+    }
+}
@@ -1,24 +1,55 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use log::{error, info, warn};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use shakmaty::{
+    fen::Fen,
+    san::SanPlus,
+    uci::UciMove,
+    variant::{Variant, VariantPosition},
+    CastlingMode, Color, EnPassantMode, Move, Position,
+};
 use specta::Type;
-use tauri::AppHandle;
+use tauri::{path::BaseDirectory, AppHandle, Manager};
 use tauri_specta::Event;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::{tcp::OwnedWriteHalf, TcpStream},
-    sync::{watch, Mutex, RwLock},
+    net::tcp::OwnedWriteHalf,
+    sync::{watch, Mutex, Notify, RwLock},
     task::JoinHandle,
-    time::sleep,
+    time::{sleep, sleep_until, Instant},
 };
 
+use crate::chess::GoMode;
 use crate::error::Error;
+use crate::tlcs::{
+    connect_tcp, is_result_token, parse_move, FrameLimiter, ReconnectGiveUp, ReconnectPolicy,
+    Reconnector, TlcsInputEncoding, TlcsRateLimit, TlcsRetryInfo, TlcsSide,
+};
+use crate::tlcs_auto_subscribe::{AutoSubscriber, TlcsAutoSubscribeRules};
+use crate::tlcs_engine_seat::TlcsEngineSeat;
 use crate::AppState;
 
 const DEFAULT_KEEP_ALIVE_SECS: u64 = 30;
-const MAX_BACKOFF_SECS: u64 = 30;
-const MIN_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_MS: u64 = 30_000;
+const MIN_BACKOFF_MS: u64 = 1_000;
+/// PINGs that never got a PONG are dropped after this many are outstanding.
+const MAX_PENDING_PINGS: usize = 8;
+/// Unanswered PINGs after which the connection is taken for dead and
+/// dropped, unless the `keep_alive` command sets another limit.
+const DEFAULT_MAX_MISSED_PONGS: u32 = 3;
+const DEFAULT_SEAT_MOVETIME_MS: u32 = 1000;
+/// How often the game list is requested while auto-subscription rules are set.
+const AUTO_SUBSCRIBE_POLL_SECS: u64 = 30;
+/// How long the server may take to answer `RESUME` before the subscriptions
+/// are sent again.
+const RESUME_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Clone, Debug, Serialize, Type, Event)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +57,39 @@ pub struct TlcsStatusEvent {
     pub connected: bool,
     pub address: String,
     pub message: Option<String>,
+    /// Set while waiting to reconnect.
+    pub retry: Option<TlcsRetryInfo>,
+}
+
+/// A game the server offers for recording or subscription.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsGameListing {
+    /// Also the game id to subscribe with.
+    pub board: u32,
+    pub round: Option<String>,
+    pub white: String,
+    pub white_rating: Option<u32>,
+    pub black: String,
+    pub black_rating: Option<u32>,
+}
+
+/// The server's answer to `GAMES`: a `GAMES <count>` line followed by one
+/// `GAME <board>|<round>|<white>|<rating>|<black>|<rating>` line per game.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsGameListEvent {
+    pub games: Vec<TlcsGameListing>,
+}
+
+/// The audience of a game: sent for an `OBSERVERS <game> <count> [names...]`
+/// line, and again for each `JOIN <game> <name>` or `LEAVE <game> <name>`
+/// that changes it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPresenceEvent {
+    pub game_id: String,
+    pub observers: usize,
 }
 
 #[derive(Clone, Debug, Serialize, Type, Event)]
@@ -35,18 +99,391 @@ pub struct TlcsMessageEvent {
     pub payload: String,
 }
 
+/// A per-game frame from the server, parsed so the frontend does not have
+/// to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TlcsServerMessage {
+    /// `CLOCK <game> <white ms> <black ms>`
+    #[serde(rename_all = "camelCase")]
+    Clock { white_ms: u64, black_ms: u64 },
+    /// `RESULT <game> <1-0|0-1|1/2-1/2|*>`
+    Result { result: String },
+    /// `FEN <game> <fen>`
+    Fen { fen: String },
+    /// `NAMES <game> <white>|<black>`
+    Names { white: String, black: String },
+    /// `RESIGN <game> <white|black>`, naming the side that resigned.
+    Resign { side: TlcsSide },
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsServerMessageEvent {
+    pub game_id: String,
+    pub message: TlcsServerMessage,
+}
+
 #[derive(Clone, Debug, Serialize, Type, Event)]
 #[serde(rename_all = "camelCase")]
 pub struct TlcsErrorEvent {
     pub message: String,
 }
 
+/// A spectator or arbiter chat line, sent by the server as
+/// `CHAT <channel> <sender>: <text>`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsChatEvent {
+    pub sender: String,
+    pub channel: String,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsOfferKind {
+    /// Open to anyone on the server.
+    Seek,
+    /// Addressed to this client.
+    Challenge,
+}
+
+/// A game offered by the server, sent as
+/// `SEEK <id> <player> <rating|-> <minutes>+<increment> <rated|casual>` or
+/// the same with `CHALLENGE`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOfferEvent {
+    pub kind: TlcsOfferKind,
+    pub id: String,
+    pub player: String,
+    pub rating: Option<u32>,
+    pub minutes: u32,
+    /// Seconds added after every move.
+    pub increment: u32,
+    pub rated: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsLatencyEvent {
+    pub rtt_ms: u64,
+    pub jitter_ms: f64,
+}
+
+/// Pairs keep-alive PINGs with the server's PONGs, in order, to measure the
+/// round-trip time of the link.
+#[derive(Default)]
+struct LatencyTracker {
+    pending: VecDeque<Instant>,
+    last_rtt: Option<Duration>,
+    jitter_ms: f64,
+}
+
+impl LatencyTracker {
+    fn ping_sent(&mut self) {
+        if self.pending.len() >= MAX_PENDING_PINGS {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(Instant::now());
+    }
+
+    fn pong_received(&mut self) -> Option<TlcsLatencyEvent> {
+        let rtt = self.pending.pop_front()?.elapsed();
+        // Smoothed jitter as in RFC 3550.
+        if let Some(last) = self.last_rtt {
+            let delta = (rtt.as_secs_f64() - last.as_secs_f64()).abs() * 1000.0;
+            self.jitter_ms += (delta - self.jitter_ms) / 16.0;
+        }
+        self.last_rtt = Some(rtt);
+        Some(TlcsLatencyEvent {
+            rtt_ms: rtt.as_millis() as u64,
+            jitter_ms: self.jitter_ms,
+        })
+    }
+
+    /// PINGs sent since the last PONG.
+    fn unanswered(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether `max_missed` PINGs in a row went unanswered.
+    fn link_lost(&self, max_missed: Option<usize>) -> bool {
+        max_missed.is_some_and(|max| self.unanswered() >= max)
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsPremoveState {
+    Queued,
+    Fired,
+    Cancelled,
+}
+
+/// Emitted when a premove is queued, sent after the opponent's move, or
+/// dropped, either on request or because it became illegal.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsPremoveEvent {
+    pub game_id: String,
+    pub mv: String,
+    pub state: TlcsPremoveState,
+    pub reason: Option<String>,
+}
+
+/// Holds moves and chat messages sent while the connection is down, to be
+/// sent once it is restored.
+#[derive(Clone, Copy, Debug, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOutboundQueueConfig {
+    /// Commands that waited longer than this are dropped on reconnect
+    /// instead of sent.
+    pub max_age_ms: u64,
+    /// Most commands held at once. The oldest is dropped to make room.
+    pub capacity: usize,
+}
+
+/// Queued commands that were dropped without being sent.
+#[derive(Clone, Debug, Serialize, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsOutboundExpiredEvent {
+    pub commands: Vec<String>,
+}
+
+#[derive(Default)]
+struct OutboundQueue {
+    config: Option<TlcsOutboundQueueConfig>,
+    pending: VecDeque<(Instant, String)>,
+    /// Pushed out by newer commands, reported with the next flush.
+    dropped: Vec<String>,
+}
+
+impl OutboundQueue {
+    /// Holds `message` back. Returns false when queueing is off.
+    fn push(&mut self, message: &str) -> bool {
+        let Some(config) = self.config else {
+            return false;
+        };
+        if self.pending.len() >= config.capacity.max(1) {
+            if let Some((_, oldest)) = self.pending.pop_front() {
+                self.dropped.push(oldest);
+            }
+        }
+        self.pending
+            .push_back((Instant::now(), message.to_string()));
+        true
+    }
+
+    /// Empties the queue into the commands still to send, in order, and
+    /// those that expired.
+    fn drain(&mut self) -> (Vec<String>, Vec<String>) {
+        let max_age = self
+            .config
+            .map(|config| Duration::from_millis(config.max_age_ms));
+        let mut expired = std::mem::take(&mut self.dropped);
+        let mut ready = Vec::new();
+        for (queued_at, message) in self.pending.drain(..) {
+            if max_age.is_some_and(|max_age| queued_at.elapsed() > max_age) {
+                expired.push(message);
+            } else {
+                ready.push(message);
+            }
+        }
+        (ready, expired)
+    }
+}
+
+/// A game the client is subscribed to.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsSubscription {
+    pub game_id: String,
+    /// When the server last sent a move or result for the game, in RFC 3339.
+    pub last_activity: Option<String>,
+}
+
+/// How moves are written when sent to the server.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TlcsMoveNotation {
+    #[default]
+    Uci,
+    San,
+}
+
+/// A subscribed game, replayed from the moves the server reports for it.
+pub(crate) struct TrackedGame {
+    pub(crate) position: VariantPosition,
+    /// Moves since the initial position, in UCI.
+    pub(crate) moves: Vec<String>,
+    /// Set once the server sends a result or a move ends the game.
+    pub(crate) result: Option<String>,
+    last_activity: Option<DateTime<Utc>>,
+    /// Our last move, until the server echoes it back.
+    echo: Option<String>,
+    premove: Option<Premove>,
+}
+
+/// A move to send as soon as the opponent has moved.
+struct Premove {
+    mv: String,
+    color: Color,
+    notation: TlcsMoveNotation,
+}
+
+impl Default for TrackedGame {
+    fn default() -> Self {
+        Self {
+            position: VariantPosition::new(Variant::Chess),
+            moves: Vec::new(),
+            result: None,
+            last_activity: None,
+            echo: None,
+            premove: None,
+        }
+    }
+}
+
+impl TrackedGame {
+    /// Parses `mv`, in SAN or UCI, as a legal move in the current position.
+    fn legal_move(&self, mv: &str) -> Result<Move, Error> {
+        match parse_move(&self.position, mv) {
+            Ok(Some(legal)) => Ok(legal),
+            _ if SanPlus::from_ascii(mv.as_bytes()).is_ok()
+                || UciMove::from_ascii(mv.as_bytes()).is_ok() =>
+            {
+                Err(Error::TlcsIllegalMove(
+                    mv.to_string(),
+                    Fen::from_position(self.position.clone(), EnPassantMode::Legal).to_string(),
+                ))
+            }
+            _ => Err(Error::TlcsUnrecognizedMove(mv.to_string())),
+        }
+    }
+
+    /// Plays a legal move and returns it in UCI.
+    fn push(&mut self, mv: &Move) -> String {
+        let uci = mv.to_uci(CastlingMode::Standard).to_string();
+        self.position.play_unchecked(mv);
+        self.moves.push(uci.clone());
+        if let Some(outcome) = self.position.outcome() {
+            self.result = Some(outcome.to_string());
+        }
+        uci
+    }
+
+    /// Sends a legal move to the server, then plays it. Returns the move as
+    /// sent.
+    async fn send(
+        &mut self,
+        writer: &SharedWriter,
+        game_id: &str,
+        mv: &Move,
+        notation: TlcsMoveNotation,
+    ) -> Result<String, Error> {
+        let sent = match notation {
+            TlcsMoveNotation::Uci => mv.to_uci(CastlingMode::Standard).to_string(),
+            TlcsMoveNotation::San => SanPlus::from_move(self.position.clone(), mv).to_string(),
+        };
+        writer.send(&format!("MOVE {game_id} {sent}")).await?;
+        self.push(mv);
+        self.echo = Some(sent.clone());
+        Ok(sent)
+    }
+}
+
+pub(crate) type TrackedGames = Arc<RwLock<HashMap<String, TrackedGame>>>;
+pub(crate) type SharedWriter = Arc<FrameWriter>;
+type SharedAutoSubscriber = Arc<Mutex<Option<AutoSubscriber>>>;
+type SharedOutbound = Arc<Mutex<OutboundQueue>>;
+
+/// The write half of the connection, shared by everything that sends frames
+/// to the server, and the rate limit all of them wait on.
+#[derive(Default)]
+pub(crate) struct FrameWriter {
+    half: Mutex<Option<OwnedWriteHalf>>,
+    rate_limit: Mutex<Option<FrameLimiter>>,
+    /// Told when a frame has to wait for the rate limit.
+    app_handle: Mutex<Option<AppHandle>>,
+}
+
+impl FrameWriter {
+    /// Writes `message` once the rate limit allows, if a connection is open,
+    /// returning whether it was sent.
+    async fn write(&self, message: &str) -> Result<bool, Error> {
+        // Senders wait their turn on the limiter, so frames keep their order.
+        let rate_limit = self.rate_limit.lock().await;
+        if let Some(limiter) = rate_limit.as_ref() {
+            let app_handle = self.app_handle.lock().await.clone();
+            limiter
+                .throttle(message, |event| {
+                    if let Some(app_handle) = app_handle {
+                        let _ = app_handle.emit_all("tlcs://rate-limited", event);
+                    }
+                })
+                .await;
+        }
+        let mut guard = self.half.lock().await;
+        let Some(half) = guard.as_mut() else {
+            return Ok(false);
+        };
+        let mut framed = message.to_string();
+        if !framed.ends_with("\r\n") {
+            framed.push_str("\r\n");
+        }
+        half.write_all(framed.as_bytes()).await?;
+        half.flush().await?;
+        Ok(true)
+    }
+
+    /// Like `write`, failing when no connection is open.
+    async fn send(&self, message: &str) -> Result<(), Error> {
+        if self.write(message).await? {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "No active TLCS connection",
+            )
+            .into())
+        }
+    }
+}
+
+/// The manager state the connection task works on.
+struct ConnectionShared {
+    writer: SharedWriter,
+    subscriptions: Arc<RwLock<HashSet<String>>>,
+    games: TrackedGames,
+    seats: Arc<Mutex<HashMap<String, TlcsEngineSeat>>>,
+    latency: Arc<Mutex<LatencyTracker>>,
+    dead_link: Arc<Notify>,
+    auto_subscriber: SharedAutoSubscriber,
+    outbound: SharedOutbound,
+    encoding: Arc<RwLock<TlcsInputEncoding>>,
+}
+
 #[derive(Default)]
 pub struct TlcsManager {
-    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
+    writer: SharedWriter,
     subscriptions: Arc<RwLock<HashSet<String>>>,
+    games: TrackedGames,
+    seats: Arc<Mutex<HashMap<String, TlcsEngineSeat>>>,
+    latency: Arc<Mutex<LatencyTracker>>,
+    /// Woken by the keep-alive task when the server stopped answering PINGs.
+    dead_link: Arc<Notify>,
+    auto_subscriber: SharedAutoSubscriber,
+    outbound: SharedOutbound,
+    encoding: Arc<RwLock<TlcsInputEncoding>>,
     connection_task: Option<JoinHandle<()>>,
     keep_alive_task: Option<JoinHandle<()>>,
+    auto_poll_task: Option<JoinHandle<()>>,
     shutdown_tx: Option<watch::Sender<bool>>,
     reconnect: bool,
     address: Option<String>,
@@ -59,6 +496,8 @@ impl TlcsManager {
         host: String,
         port: u16,
         reconnect: bool,
+        policy: Option<ReconnectPolicy>,
+        proxy_url: Option<String>,
     ) -> Result<(), Error> {
         self.shutdown().await;
 
@@ -66,25 +505,47 @@ impl TlcsManager {
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
         self.address = Some(address.clone());
+        *self.writer.app_handle.lock().await = Some(app_handle.clone());
         self.reconnect = reconnect;
         self.shutdown_tx = Some(shutdown_tx);
+        // Commands queued for an earlier connection are not meant for this
+        // one.
+        self.outbound.lock().await.pending.clear();
 
-        let writer = self.writer.clone();
-        let subscriptions = self.subscriptions.clone();
+        let shared = ConnectionShared {
+            writer: self.writer.clone(),
+            subscriptions: self.subscriptions.clone(),
+            games: self.games.clone(),
+            seats: self.seats.clone(),
+            latency: self.latency.clone(),
+            dead_link: self.dead_link.clone(),
+            auto_subscriber: self.auto_subscriber.clone(),
+            outbound: self.outbound.clone(),
+            encoding: self.encoding.clone(),
+        };
 
         self.connection_task = Some(tokio::spawn(async move {
             run_connection(
-                address,
+                ConnectionTarget {
+                    host,
+                    port,
+                    proxy_url,
+                },
                 app_handle,
-                writer,
-                subscriptions,
+                shared,
                 shutdown_rx,
-                reconnect,
+                reconnect.then(|| {
+                    policy
+                        .unwrap_or_else(|| ReconnectPolicy::backoff(MIN_BACKOFF_MS, MAX_BACKOFF_MS))
+                }),
             )
             .await;
         }));
 
-        self.start_keep_alive(None, None).await;
+        self.start_keep_alive(None, None, None).await;
+        if self.auto_subscriber.lock().await.is_some() {
+            self.start_auto_poll();
+        }
         Ok(())
     }
 
@@ -94,6 +555,7 @@ impl TlcsManager {
         app_handle: AppHandle,
     ) -> Result<(), Error> {
         self.subscriptions.write().await.insert(game_id.clone());
+        self.games.write().await.entry(game_id.clone()).or_default();
         self.send_frame(format!("SUBSCRIBE {}", game_id).as_str())
             .await
             .map_err(|err| {
@@ -102,13 +564,43 @@ impl TlcsManager {
             })
     }
 
+    /// Stops following `game_id`: the server is told when connected, and the
+    /// game is no longer resubscribed on reconnect. Its engine seat, if any,
+    /// is stopped.
+    pub async fn unsubscribe_game(&self, game_id: String) -> Result<(), Error> {
+        if !self.subscriptions.write().await.remove(&game_id) {
+            return Err(Error::TlcsGameNotSubscribed(game_id));
+        }
+        self.games.write().await.remove(&game_id);
+        self.stop_engine_seats(Some(game_id.clone())).await;
+        self.writer.write(&format!("UNSUBSCRIBE {game_id}")).await?;
+        Ok(())
+    }
+
+    /// The subscribed games, by game id.
+    pub async fn list_subscriptions(&self) -> Vec<TlcsSubscription> {
+        let mut game_ids: Vec<String> = self.subscriptions.read().await.iter().cloned().collect();
+        game_ids.sort();
+        let games = self.games.read().await;
+        game_ids
+            .into_iter()
+            .map(|game_id| TlcsSubscription {
+                last_activity: games
+                    .get(&game_id)
+                    .and_then(|game| game.last_activity)
+                    .map(|at| at.to_rfc3339_opts(SecondsFormat::Millis, true)),
+                game_id,
+            })
+            .collect()
+    }
+
     pub async fn send_move(
         &self,
         game_id: String,
         mv: String,
         app_handle: AppHandle,
     ) -> Result<(), Error> {
-        self.send_frame(format!("MOVE {} {}", game_id, mv).as_str())
+        self.send_or_queue(format!("MOVE {} {}", game_id, mv).as_str())
             .await
             .map_err(|err| {
                 emit_error(&app_handle, &format!("Failed to send move: {err}"));
@@ -116,12 +608,178 @@ impl TlcsManager {
             })
     }
 
+    /// Asks the server for its game list, answered with a `tlcs://games`
+    /// event.
+    pub async fn request_game_list(&self) -> Result<(), Error> {
+        self.send_frame("GAMES").await
+    }
+
+    /// Replaces the auto-subscription rules, or clears them. Games already
+    /// followed stay subscribed. `pgn_dir` is used when the rules name no
+    /// directory.
+    pub async fn set_auto_subscribe(
+        &mut self,
+        rules: Option<TlcsAutoSubscribeRules>,
+        pgn_dir: PathBuf,
+    ) -> Result<(), Error> {
+        let subscriber = rules
+            .map(|rules| AutoSubscriber::new(rules, pgn_dir))
+            .transpose()?;
+        let enabled = subscriber.is_some();
+        *self.auto_subscriber.lock().await = subscriber;
+        if let Some(handle) = self.auto_poll_task.take() {
+            handle.abort();
+        }
+        if enabled && self.shutdown_tx.is_some() {
+            self.start_auto_poll();
+        }
+        Ok(())
+    }
+
+    /// Turns the outbound queue on with `config`, or off with `None`, which
+    /// drops the commands it holds.
+    pub async fn set_outbound_queue(&self, config: Option<TlcsOutboundQueueConfig>) {
+        let mut outbound = self.outbound.lock().await;
+        outbound.config = config;
+        if config.is_none() {
+            outbound.pending.clear();
+            outbound.dropped.clear();
+        }
+    }
+
+    /// Sets the character set the server's lines are decoded with.
+    pub async fn set_input_encoding(&self, encoding: TlcsInputEncoding) {
+        *self.encoding.write().await = encoding;
+    }
+
+    /// Spaces out every frame this client sends, subscriptions restored and
+    /// commands flushed after a reconnect included, or stops doing so with
+    /// `None`.
+    pub async fn set_rate_limit(&self, limit: Option<TlcsRateLimit>) {
+        *self.writer.rate_limit.lock().await = limit.map(FrameLimiter::new);
+    }
+
+    /// Requests the game list now and then every `AUTO_SUBSCRIBE_POLL_SECS`,
+    /// so the rules see new games as they appear.
+    fn start_auto_poll(&mut self) {
+        if let Some(handle) = self.auto_poll_task.take() {
+            handle.abort();
+        }
+        let writer = self.writer.clone();
+        let mut shutdown_rx = self
+            .shutdown_tx
+            .as_ref()
+            .map(|tx| tx.subscribe())
+            .unwrap_or_else(|| watch::channel(false).1);
+
+        self.auto_poll_task = Some(tokio::spawn(async move {
+            loop {
+                if let Err(err) = writer.write("GAMES").await {
+                    warn!("Game list request failed: {}", err);
+                }
+                tokio::select! {
+                    _ = shutdown_rx.changed() => {
+                        if *shutdown_rx.borrow() {
+                            break;
+                        }
+                    }
+                    _ = sleep(Duration::from_secs(AUTO_SUBSCRIBE_POLL_SECS)) => {}
+                }
+            }
+        }));
+    }
+
+    /// Posts `text` to a chat channel. Line breaks are sent as spaces, so the
+    /// text cannot smuggle in further commands.
+    pub async fn send_chat(&self, channel: &str, text: &str) -> Result<(), Error> {
+        let channel = channel.trim();
+        if channel.is_empty() || channel.contains(char::is_whitespace) {
+            return Err(Error::TlcsInvalidChannel(channel.to_string()));
+        }
+        let text = text.replace(['\r', '\n'], " ");
+        self.send_or_queue(&format!("CHAT {channel} {}", text.trim()))
+            .await
+    }
+
+    /// Offers a game to anyone on the server as
+    /// `SEEK <minutes> <increment> <rated|casual>`. Offers from others arrive
+    /// as `tlcs://offer` events.
+    pub async fn post_seek(&self, minutes: u32, increment: u32, rated: bool) -> Result<(), Error> {
+        if minutes == 0 && increment == 0 {
+            return Err(Error::TlcsInvalidTimeControl(minutes, increment));
+        }
+        let rated = if rated { "rated" } else { "casual" };
+        self.send_frame(&format!("SEEK {minutes} {increment} {rated}"))
+            .await
+    }
+
+    /// Accepts the seek or challenge `id` of a `tlcs://offer` event with
+    /// `ACCEPT <id>`. The server then starts the game as usual.
+    pub async fn accept_challenge(&self, id: &str) -> Result<(), Error> {
+        let id = id.trim();
+        if id.is_empty() || id.contains(char::is_whitespace) {
+            return Err(Error::TlcsInvalidChallenge(id.to_string()));
+        }
+        self.send_frame(&format!("ACCEPT {id}")).await
+    }
+
+    /// Seats `engine` at `game_id`, replacing any engine already seated there.
+    /// It replies with a move whenever `color` is to move.
+    pub async fn start_engine_seat(
+        &self,
+        game_id: String,
+        engine: PathBuf,
+        color: Color,
+        go: GoMode,
+        app_handle: AppHandle,
+    ) -> Result<(), Error> {
+        if !self.games.read().await.contains_key(&game_id) {
+            return Err(Error::TlcsGameNotSubscribed(game_id));
+        }
+        let seat = TlcsEngineSeat::spawn(
+            game_id.clone(),
+            engine,
+            color,
+            go,
+            self.games.clone(),
+            self.writer.clone(),
+            app_handle,
+        );
+        // It may already be the engine's turn.
+        seat.notify();
+        let previous = self.seats.lock().await.insert(game_id, seat);
+        if let Some(previous) = previous {
+            previous.stop().await;
+        }
+        Ok(())
+    }
+
+    /// Unseats the engine of `game_id`, or every engine when `None`. Moves
+    /// still being searched are dropped, not sent.
+    pub async fn stop_engine_seats(&self, game_id: Option<String>) {
+        let stopped: Vec<TlcsEngineSeat> = {
+            let mut seats = self.seats.lock().await;
+            match game_id {
+                Some(game_id) => seats.remove(&game_id).into_iter().collect(),
+                None => seats.drain().map(|(_, seat)| seat).collect(),
+            }
+        };
+        for seat in stopped {
+            seat.stop().await;
+        }
+    }
+
+    /// Restarts the keep-alive task. PINGs are expected to be answered with
+    /// PONGs, and after `max_missed` unanswered ones in a row the connection
+    /// is dropped and reconnected; `0` never drops it.
     pub async fn keep_alive(
         &mut self,
         interval_secs: Option<u64>,
         payload: Option<String>,
+        max_missed: Option<u32>,
     ) -> Result<(), Error> {
-        self.start_keep_alive(interval_secs, payload).await;
+        self.start_keep_alive(interval_secs, payload, max_missed)
+            .await;
         Ok(())
     }
 
@@ -132,6 +790,7 @@ impl TlcsManager {
     }
 
     async fn shutdown(&mut self) {
+        self.stop_engine_seats(None).await;
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(true);
         }
@@ -140,41 +799,53 @@ impl TlcsManager {
             handle.abort();
         }
 
+        if let Some(handle) = self.auto_poll_task.take() {
+            handle.abort();
+        }
+
         if let Some(handle) = self.connection_task.take() {
             let _ = handle.await;
         }
 
-        self.writer.lock().await.take();
+        self.writer.half.lock().await.take();
         self.address = None;
     }
 
     async fn send_frame(&self, message: &str) -> Result<(), Error> {
-        let mut guard = self.writer.lock().await;
-        let writer = guard.as_mut().ok_or_else(|| {
-            std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "No active TLCS connection",
-            )
-        })?;
+        self.writer.send(message).await
+    }
 
-        let mut framed = message.to_string();
-        if !framed.ends_with("\r\n") {
-            framed.push_str("\r\n");
+    /// Sends `message`, or holds it back until the connection is restored
+    /// when it is down and the outbound queue is on.
+    async fn send_or_queue(&self, message: &str) -> Result<(), Error> {
+        // The writer stays locked while queueing, so a reconnect cannot
+        // flush the queue in between.
+        let writer = self.writer.half.lock().await;
+        if writer.is_none() && self.outbound.lock().await.push(message) {
+            info!("Queued until reconnected: {message}");
+            return Ok(());
         }
-
-        writer.write_all(framed.as_bytes()).await?;
-        writer.flush().await?;
-        Ok(())
+        drop(writer);
+        self.send_frame(message).await
     }
 
-    async fn start_keep_alive(&mut self, interval_secs: Option<u64>, payload: Option<String>) {
+    async fn start_keep_alive(
+        &mut self,
+        interval_secs: Option<u64>,
+        payload: Option<String>,
+        max_missed: Option<u32>,
+    ) {
         if let Some(handle) = self.keep_alive_task.take() {
             handle.abort();
         }
 
         let writer = self.writer.clone();
+        let latency = self.latency.clone();
+        let dead_link = self.dead_link.clone();
         let interval = interval_secs.unwrap_or(DEFAULT_KEEP_ALIVE_SECS);
         let message = payload.unwrap_or_else(|| "PING".to_string());
+        let measure = message.starts_with("PING");
+        let max_missed = missed_pong_limit(max_missed, measure);
         let mut shutdown_rx = self
             .shutdown_tx
             .as_ref()
@@ -190,8 +861,21 @@ impl TlcsManager {
                         }
                     }
                     _ = sleep(Duration::from_secs(interval)) => {
-                        if let Err(err) = send_keep_alive(writer.clone(), &message).await {
-                            warn!("Keep-alive send failed: {}", err);
+                        // Holding the tracker while sending keeps a fast PONG
+                        // from being matched before its PING is recorded.
+                        let mut latency = latency.lock().await;
+                        if latency.link_lost(max_missed) {
+                            warn!("No PONG for {} keep-alives, dropping the connection", latency.unanswered());
+                            latency.reset();
+                            // Stores a permit, so a read loop busy with a line
+                            // still drops the connection once it gets back.
+                            dead_link.notify_one();
+                            continue;
+                        }
+                        match writer.write(&message).await {
+                            Ok(true) if measure => latency.ping_sent(),
+                            Ok(_) => {}
+                            Err(err) => warn!("Keep-alive send failed: {}", err),
                         }
                     }
                 }
@@ -200,18 +884,46 @@ impl TlcsManager {
     }
 }
 
+/// Unanswered PINGs after which the keep-alive drops the connection, `None`
+/// when it never does. Only PINGs get an answer to wait for, and the tracker
+/// forgets the oldest ones past `MAX_PENDING_PINGS`.
+fn missed_pong_limit(max_missed: Option<u32>, measure: bool) -> Option<usize> {
+    match max_missed.unwrap_or(DEFAULT_MAX_MISSED_PONGS) {
+        0 => None,
+        _ if !measure => None,
+        missed => Some((missed as usize).min(MAX_PENDING_PINGS)),
+    }
+}
+
+struct ConnectionTarget {
+    host: String,
+    port: u16,
+    proxy_url: Option<String>,
+}
+
 async fn run_connection(
-    address: String,
+    target: ConnectionTarget,
     app_handle: AppHandle,
-    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
-    subscriptions: Arc<RwLock<HashSet<String>>>,
+    shared: ConnectionShared,
     mut shutdown_rx: watch::Receiver<bool>,
-    reconnect: bool,
+    reconnect: Option<ReconnectPolicy>,
 ) {
-    let mut backoff = Duration::from_secs(MIN_BACKOFF_SECS);
+    let ConnectionShared {
+        writer,
+        subscriptions,
+        latency,
+        dead_link,
+        outbound,
+        encoding,
+        ..
+    } = &shared;
+    let address = format!("{}:{}", target.host, target.port);
+    let mut reconnector = reconnect.map(Reconnector::new);
+    // Kept across reconnects, for as long as the server accepts it.
+    let mut resume_token: Option<String> = None;
 
     loop {
-        let connect_future = TcpStream::connect(&address);
+        let connect_future = connect_tcp(&target.host, target.port, target.proxy_url.as_deref());
         let stream = tokio::select! {
             _ = shutdown_rx.changed() => {
                 break;
@@ -221,16 +933,24 @@ async fn run_connection(
 
         let stream = match stream {
             Ok(stream) => {
-                info!("Connected to TLCS server at {}", address);
+                // The address the host resolved to that answered first.
+                let peer = stream
+                    .peer_addr()
+                    .map(|peer| peer.to_string())
+                    .unwrap_or_else(|_| address.clone());
+                info!("Connected to TLCS server at {address} ({peer})");
                 let _ = app_handle.emit_all(
                     "tlcs://status",
                     TlcsStatusEvent {
                         connected: true,
                         address: address.clone(),
-                        message: Some("connected".to_string()),
+                        message: Some(format!("connected to {peer}")),
+                        retry: None,
                     },
                 );
-                backoff = Duration::from_secs(MIN_BACKOFF_SECS);
+                if let Some(reconnector) = reconnector.as_mut() {
+                    reconnector.reset();
+                }
                 stream
             }
             Err(err) => {
@@ -238,34 +958,59 @@ async fn run_connection(
                     &app_handle,
                     &format!("Connection to {} failed: {}", address, err),
                 );
-                if !reconnect {
+                if !wait_to_reconnect(
+                    &app_handle,
+                    &address,
+                    reconnector.as_mut(),
+                    &mut shutdown_rx,
+                )
+                .await
+                {
                     break;
                 }
-                wait_with_backoff(&mut shutdown_rx, backoff).await;
-                backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
                 continue;
             }
         };
 
         let (read_half, write_half) = stream.into_split();
-        writer.lock().await.replace(write_half);
+        writer.half.lock().await.replace(write_half);
+        latency.lock().await.reset();
 
-        if let Err(err) = resend_subscriptions(&writer, &subscriptions).await {
-            emit_error(
-                &app_handle,
-                &format!("Failed to restore subscriptions: {err}"),
-            );
+        // A resumed session keeps its subscriptions on the server.
+        let mut resume_deadline = None;
+        if let Some(token) = &resume_token {
+            match writer.write(&format!("RESUME {token}")).await {
+                Ok(true) => resume_deadline = Some(Instant::now() + RESUME_TIMEOUT),
+                Ok(false) => {}
+                Err(err) => warn!("Failed to resume TLCS session: {err}"),
+            }
+        }
+        if resume_deadline.is_none() {
+            restore_subscriptions(&app_handle, writer, subscriptions).await;
         }
+        flush_outbound(&app_handle, writer, outbound).await;
 
         let mut reader = BufReader::new(read_half);
         let mut buffer = Vec::new();
+        let mut game_list = GameList::default();
+        let mut presence = Presence::default();
 
         loop {
-            buffer.clear();
             let read_result = tokio::select! {
                 _ = shutdown_rx.changed() => {
                     break;
                 }
+                _ = dead_link.notified() => {
+                    emit_error(&app_handle, &format!("TLCS server at {address} stopped answering keep-alives"));
+                    break;
+                }
+                _ = sleep_until(resume_deadline.unwrap_or_else(Instant::now)), if resume_deadline.is_some() => {
+                    warn!("TLCS server did not answer RESUME, subscribing again");
+                    resume_deadline = None;
+                    resume_token = None;
+                    restore_subscriptions(&app_handle, writer, subscriptions).await;
+                    continue;
+                }
                 result = reader.read_until(b'\n', &mut buffer) => result
             };
 
@@ -275,10 +1020,53 @@ async fn run_connection(
                     break;
                 }
                 Ok(_) => {
-                    let line = String::from_utf8_lossy(&buffer)
-                        .trim_end_matches(['\r', '\n'])
-                        .to_string();
-                    handle_incoming_line(&app_handle, line);
+                    let line = encoding.read().await.decode(&buffer);
+                    let line = line.trim_end_matches(['\r', '\n']).to_string();
+                    // A read interrupted by the resume timeout keeps its
+                    // bytes, so the buffer is only cleared once a line is in.
+                    buffer.clear();
+                    if let Some(reply) = parse_resume_line(&line) {
+                        match reply {
+                            ResumeLine::Token(token) => resume_token = Some(token),
+                            ResumeLine::Accepted if resume_deadline.take().is_some() => {
+                                info!("Resumed TLCS session at {address}");
+                                let _ = app_handle.emit_all(
+                                    "tlcs://status",
+                                    TlcsStatusEvent {
+                                        connected: true,
+                                        address: address.clone(),
+                                        message: Some("resumed".to_string()),
+                                        retry: None,
+                                    },
+                                );
+                            }
+                            ResumeLine::Declined if resume_deadline.take().is_some() => {
+                                info!("TLCS server declined to resume, subscribing again");
+                                resume_token = None;
+                                restore_subscriptions(&app_handle, writer, subscriptions).await;
+                            }
+                            ResumeLine::Accepted | ResumeLine::Declined => {}
+                        }
+                        continue;
+                    }
+                    if line.starts_with("PONG") {
+                        if let Some(event) = latency.lock().await.pong_received() {
+                            let _ = app_handle.emit_all("tlcs://latency", event);
+                        }
+                    }
+                    if let Some(rest) = line.strip_prefix("MOVE ") {
+                        track_move(&app_handle, &shared, rest).await;
+                    }
+                    if line.starts_with("GAMES ") || line.starts_with("GAME ") {
+                        if let Some(event) = game_list.feed(&line) {
+                            auto_subscribe(&app_handle, &shared, &event).await;
+                            let _ = app_handle.emit_all("tlcs://games", event);
+                        }
+                    } else if let Some(event) = presence.feed(&line) {
+                        let _ = app_handle.emit_all("tlcs://presence", event);
+                    } else {
+                        handle_incoming_line(&app_handle, line);
+                    }
                 }
                 Err(err) => {
                     emit_error(&app_handle, &format!("Failed to read from TLCS: {err}"));
@@ -287,31 +1075,37 @@ async fn run_connection(
             }
         }
 
-        writer.lock().await.take();
+        writer.half.lock().await.take();
         let _ = app_handle.emit_all(
             "tlcs://status",
             TlcsStatusEvent {
                 connected: false,
                 address: address.clone(),
                 message: Some("disconnected".to_string()),
+                retry: None,
             },
         );
 
-        if !reconnect {
+        if !wait_to_reconnect(
+            &app_handle,
+            &address,
+            reconnector.as_mut(),
+            &mut shutdown_rx,
+        )
+        .await
+        {
             break;
         }
-
-        wait_with_backoff(&mut shutdown_rx, backoff).await;
-        backoff = (backoff * 2).min(Duration::from_secs(MAX_BACKOFF_SECS));
     }
 
-    writer.lock().await.take();
+    writer.half.lock().await.take();
     let _ = app_handle.emit_all(
         "tlcs://status",
         TlcsStatusEvent {
             connected: false,
             address,
             message: Some("stopped".to_string()),
+            retry: None,
         },
     );
 }
@@ -326,6 +1120,15 @@ fn handle_incoming_line(app_handle: &AppHandle, line: String) {
         let game_id = segments.next().map(|s| s.to_string());
         let payload = segments.next().unwrap_or("").to_string();
         let _ = app_handle.emit_all("tlcs://move", TlcsMessageEvent { game_id, payload });
+    } else if let Some(chat) = parse_chat(&line) {
+        let _ = app_handle.emit_all("tlcs://chat", chat);
+    } else if let Some(offer) = parse_offer(&line) {
+        let _ = app_handle.emit_all("tlcs://offer", offer);
+    } else if let Some((game_id, message)) = parse_server_message(&line) {
+        let _ = app_handle.emit_all(
+            "tlcs://server-message",
+            TlcsServerMessageEvent { game_id, message },
+        );
     } else {
         let _ = app_handle.emit_all(
             "tlcs://message",
@@ -337,43 +1140,452 @@ fn handle_incoming_line(app_handle: &AppHandle, line: String) {
     }
 }
 
-async fn resend_subscriptions(
-    writer: &Arc<Mutex<Option<OwnedWriteHalf>>>,
-    subscriptions: &Arc<RwLock<HashSet<String>>>,
-) -> Result<(), Error> {
-    let subs = subscriptions.read().await.clone();
-    for sub in subs {
-        send_keep_alive(writer.clone(), format!("SUBSCRIBE {}", sub).as_str()).await?;
-    }
-    Ok(())
+/// The session resumption handshake. Servers that can resume a dropped
+/// session send `SESSION <token>` once connected; after a reconnect the
+/// client sends `RESUME <token>`, answered with `RESUMED` or
+/// `RESUME DECLINED [reason]`.
+#[derive(Debug, PartialEq, Eq)]
+enum ResumeLine {
+    Token(String),
+    Accepted,
+    Declined,
 }
 
-async fn send_keep_alive(
-    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
-    message: &str,
-) -> Result<(), Error> {
-    let mut guard = writer.lock().await;
-    if let Some(writer) = guard.as_mut() {
-        let mut payload = message.to_string();
-        if !payload.ends_with("\r\n") {
-            payload.push_str("\r\n");
-        }
-        writer.write_all(payload.as_bytes()).await?;
-        writer.flush().await?;
+fn parse_resume_line(line: &str) -> Option<ResumeLine> {
+    let line = line.trim();
+    if line == "RESUMED" {
+        return Some(ResumeLine::Accepted);
     }
-    Ok(())
+    if line == "RESUME DECLINED" || line.starts_with("RESUME DECLINED ") {
+        return Some(ResumeLine::Declined);
+    }
+    let token = line.strip_prefix("SESSION ")?.trim();
+    (!token.is_empty() && !token.contains(char::is_whitespace))
+        .then(|| ResumeLine::Token(token.to_string()))
 }
 
-async fn wait_with_backoff(shutdown_rx: &mut watch::Receiver<bool>, backoff: Duration) {
-    let sleep_future = sleep(backoff);
-    tokio::select! {
-        _ = shutdown_rx.changed() => {}
-        _ = sleep_future => {}
+fn parse_chat(line: &str) -> Option<TlcsChatEvent> {
+    let rest = line.strip_prefix("CHAT ")?;
+    let (channel, rest) = rest.split_once(' ')?;
+    let (sender, text) = rest.split_once(':')?;
+    if sender.trim().is_empty() {
+        return None;
     }
+    Some(TlcsChatEvent {
+        sender: sender.trim().to_string(),
+        channel: channel.to_string(),
+        text: text.trim().to_string(),
+    })
 }
 
-fn emit_error(app_handle: &AppHandle, message: &str) {
-    error!("{}", message);
+fn parse_offer(line: &str) -> Option<TlcsOfferEvent> {
+    let (kind, rest) = line.trim().split_once(' ')?;
+    let kind = match kind {
+        "SEEK" => TlcsOfferKind::Seek,
+        "CHALLENGE" => TlcsOfferKind::Challenge,
+        _ => return None,
+    };
+    let fields: Vec<_> = rest.split_whitespace().collect();
+    let [id, player, rating, time_control, rated] = fields[..] else {
+        return None;
+    };
+    let (minutes, increment) = time_control.split_once('+')?;
+    Some(TlcsOfferEvent {
+        kind,
+        id: id.to_string(),
+        player: player.to_string(),
+        rating: rating.parse().ok(),
+        minutes: minutes.parse().ok()?,
+        increment: increment.parse().ok()?,
+        rated: match rated {
+            "rated" => true,
+            "casual" => false,
+            _ => return None,
+        },
+    })
+}
+
+/// Parses a `CLOCK`, `RESULT`, `FEN`, `NAMES` or `RESIGN` frame into its game
+/// id and message. Malformed frames give `None`.
+fn parse_server_message(line: &str) -> Option<(String, TlcsServerMessage)> {
+    let (command, rest) = line.trim().split_once(' ')?;
+    let (game_id, payload) = rest.trim_start().split_once(' ')?;
+    let payload = payload.trim();
+    let message = match command {
+        "CLOCK" => {
+            let (white, black) = payload.split_once(' ')?;
+            TlcsServerMessage::Clock {
+                white_ms: white.parse().ok()?,
+                black_ms: black.trim().parse().ok()?,
+            }
+        }
+        "RESULT" if is_result_token(payload) => TlcsServerMessage::Result {
+            result: payload.to_string(),
+        },
+        "FEN" => TlcsServerMessage::Fen {
+            fen: payload.to_string(),
+        },
+        "NAMES" => {
+            let (white, black) = payload.split_once('|')?;
+            TlcsServerMessage::Names {
+                white: white.trim().to_string(),
+                black: black.trim().to_string(),
+            }
+        }
+        "RESIGN" => TlcsServerMessage::Resign {
+            side: match payload.to_ascii_lowercase().as_str() {
+                "white" | "w" => TlcsSide::White,
+                "black" | "b" => TlcsSide::Black,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    };
+    Some((game_id.to_string(), message))
+}
+
+/// Collects the `GAME` lines that follow a `GAMES <count>` line.
+#[derive(Default)]
+struct GameList {
+    remaining: usize,
+    games: Vec<TlcsGameListing>,
+}
+
+impl GameList {
+    /// Returns the list once its last game arrived. `GAME` lines outside a
+    /// list are ignored.
+    fn feed(&mut self, line: &str) -> Option<TlcsGameListEvent> {
+        if let Some(count) = line.strip_prefix("GAMES ") {
+            let Ok(count) = count.trim().parse() else {
+                warn!("Ignoring malformed game list header: {line}");
+                return None;
+            };
+            self.remaining = count;
+            self.games.clear();
+        } else if self.remaining > 0 {
+            self.remaining -= 1;
+            match parse_game_listing(line) {
+                Some(game) => self.games.push(game),
+                None => warn!("Ignoring malformed game listing: {line}"),
+            }
+        } else {
+            return None;
+        }
+
+        (self.remaining == 0).then(|| TlcsGameListEvent {
+            games: std::mem::take(&mut self.games),
+        })
+    }
+}
+
+#[derive(Default)]
+struct GamePresence {
+    observers: usize,
+    /// The observers known by name. Some relays only send a count, or list
+    /// the first few names.
+    names: HashSet<String>,
+}
+
+/// The audience of each game, from the observer lists and the join and
+/// leave lines of the relay.
+#[derive(Default)]
+struct Presence {
+    games: HashMap<String, GamePresence>,
+}
+
+impl Presence {
+    /// Returns the audience of the game the line is about, unless the line
+    /// is not a presence line or a join or leave already counted.
+    fn feed(&mut self, line: &str) -> Option<TlcsPresenceEvent> {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        if !matches!(kind, "OBSERVERS" | "JOIN" | "LEAVE") {
+            return None;
+        }
+        let game_id = fields.next()?;
+        let game = self.games.entry(game_id.to_string()).or_default();
+        let changed = match kind {
+            "OBSERVERS" => {
+                let Some(count) = fields.next().and_then(|count| count.parse::<usize>().ok())
+                else {
+                    warn!("Ignoring malformed observer list: {line}");
+                    return None;
+                };
+                game.names = fields.map(str::to_string).collect();
+                game.observers = count.max(game.names.len());
+                true
+            }
+            "JOIN" => {
+                let name = fields.next()?;
+                let joined = game.names.insert(name.to_string());
+                game.observers += usize::from(joined);
+                joined
+            }
+            _ => {
+                let name = fields.next()?;
+                // Observers left out of the list may leave as well.
+                let left = game.names.remove(name) || game.observers > game.names.len();
+                game.observers -= usize::from(left);
+                left
+            }
+        };
+        changed.then(|| TlcsPresenceEvent {
+            game_id: game_id.to_string(),
+            observers: game.observers,
+        })
+    }
+}
+
+fn parse_game_listing(line: &str) -> Option<TlcsGameListing> {
+    let fields: Vec<_> = line
+        .strip_prefix("GAME ")?
+        .split('|')
+        .map(str::trim)
+        .collect();
+    let [board, round, white, white_rating, black, black_rating] = fields[..] else {
+        return None;
+    };
+    let known = |field: &str| (!field.is_empty() && field != "-").then(|| field.to_string());
+    Some(TlcsGameListing {
+        board: board.parse().ok()?,
+        round: known(round),
+        white: white.to_string(),
+        white_rating: white_rating.parse().ok(),
+        black: black.to_string(),
+        black_rating: black_rating.parse().ok(),
+    })
+}
+
+/// Applies a `MOVE <game> <move>` reported by the server to the tracked game,
+/// records it when the game was auto-subscribed, and wakes its engine seat,
+/// if any.
+async fn track_move(app_handle: &AppHandle, shared: &ConnectionShared, rest: &str) {
+    let Some((game_id, mv)) = rest.split_once(' ') else {
+        return;
+    };
+    let mv = mv.trim();
+    {
+        let mut games = shared.games.write().await;
+        let Some(game) = games.get_mut(game_id) else {
+            return;
+        };
+        game.last_activity = Some(Utc::now());
+        if is_result_token(mv) {
+            game.result = Some(mv.to_string());
+        } else {
+            if game.echo.as_deref() == Some(mv) {
+                game.echo = None;
+                return;
+            }
+            game.echo = None;
+            match game.legal_move(mv) {
+                Ok(mv) => {
+                    game.push(&mv);
+                }
+                Err(err) => {
+                    emit_error(
+                        app_handle,
+                        &format!("Lost track of game {game_id} at {mv}: {err}"),
+                    );
+                    return;
+                }
+            }
+            fire_premove(app_handle, &shared.writer, game_id, game).await;
+        }
+        if let Some(auto) = shared.auto_subscriber.lock().await.as_mut() {
+            auto.record(game_id, game);
+        }
+    }
+    if let Some(seat) = shared.seats.lock().await.get(game_id) {
+        seat.notify();
+    }
+}
+
+/// Applies the auto-subscription rules to a game list: subscribes to the
+/// games they pick and forgets the games they drop.
+async fn auto_subscribe(
+    app_handle: &AppHandle,
+    shared: &ConnectionShared,
+    list: &TlcsGameListEvent,
+) {
+    let events = match shared.auto_subscriber.lock().await.as_mut() {
+        Some(auto) => auto.update(&list.games),
+        None => return,
+    };
+    for event in events {
+        let game_id = event.game.board.to_string();
+        if event.subscribed {
+            shared.subscriptions.write().await.insert(game_id.clone());
+            shared
+                .games
+                .write()
+                .await
+                .entry(game_id.clone())
+                .or_default();
+            let subscribe = format!("SUBSCRIBE {game_id}");
+            if let Err(err) = shared.writer.write(&subscribe).await {
+                emit_error(
+                    app_handle,
+                    &format!("Failed to subscribe to {game_id}: {err}"),
+                );
+            }
+        } else {
+            shared.subscriptions.write().await.remove(&game_id);
+            shared.games.write().await.remove(&game_id);
+        }
+        let _ = app_handle.emit_all("tlcs://auto-subscribe", event);
+    }
+}
+
+/// Sends `mv` for `game_id` after checking it against the tracked position,
+/// then plays it there. Returns the move as sent.
+pub(crate) async fn send_tracked_move(
+    writer: &SharedWriter,
+    games: &TrackedGames,
+    game_id: &str,
+    mv: &str,
+    notation: TlcsMoveNotation,
+) -> Result<String, Error> {
+    let mut games = games.write().await;
+    let game = games
+        .get_mut(game_id)
+        .ok_or_else(|| Error::TlcsGameNotSubscribed(game_id.to_string()))?;
+    let mv = game.legal_move(mv)?;
+    game.send(writer, game_id, &mv, notation).await
+}
+
+fn emit_premove(
+    app_handle: &AppHandle,
+    game_id: &str,
+    mv: String,
+    state: TlcsPremoveState,
+    reason: Option<String>,
+) {
+    let _ = app_handle.emit_all(
+        "tlcs://premove",
+        TlcsPremoveEvent {
+            game_id: game_id.to_string(),
+            mv,
+            state,
+            reason,
+        },
+    );
+}
+
+/// Sends the premove of `game` if its side is now to move, or cancels it
+/// when it is no longer legal.
+async fn fire_premove(
+    app_handle: &AppHandle,
+    writer: &SharedWriter,
+    game_id: &str,
+    game: &mut TrackedGame,
+) {
+    let Some(premove) = game.premove.take() else {
+        return;
+    };
+    if game.position.turn() != premove.color {
+        game.premove = Some(premove);
+        return;
+    }
+    let sent = match game.legal_move(&premove.mv) {
+        Ok(mv) => game.send(writer, game_id, &mv, premove.notation).await,
+        Err(err) => Err(err),
+    };
+    match sent {
+        Ok(sent) => emit_premove(app_handle, game_id, sent, TlcsPremoveState::Fired, None),
+        Err(err) => emit_premove(
+            app_handle,
+            game_id,
+            premove.mv,
+            TlcsPremoveState::Cancelled,
+            Some(err.to_string()),
+        ),
+    }
+}
+
+async fn resend_subscriptions(
+    writer: &SharedWriter,
+    subscriptions: &Arc<RwLock<HashSet<String>>>,
+) -> Result<(), Error> {
+    let subs = subscriptions.read().await.clone();
+    for sub in subs {
+        writer.write(&format!("SUBSCRIBE {sub}")).await?;
+    }
+    Ok(())
+}
+
+async fn restore_subscriptions(
+    app_handle: &AppHandle,
+    writer: &SharedWriter,
+    subscriptions: &Arc<RwLock<HashSet<String>>>,
+) {
+    if let Err(err) = resend_subscriptions(writer, subscriptions).await {
+        emit_error(
+            app_handle,
+            &format!("Failed to restore subscriptions: {err}"),
+        );
+    }
+}
+
+/// Sends the commands queued while disconnected and reports those that
+/// expired.
+async fn flush_outbound(app_handle: &AppHandle, writer: &SharedWriter, outbound: &SharedOutbound) {
+    let (ready, expired) = outbound.lock().await.drain();
+    if !expired.is_empty() {
+        warn!("Dropped {} queued TLCS commands", expired.len());
+        let _ = app_handle.emit_all(
+            "tlcs://outbound-expired",
+            TlcsOutboundExpiredEvent { commands: expired },
+        );
+    }
+    for message in ready {
+        if let Err(err) = writer.write(&message).await {
+            emit_error(app_handle, &format!("Failed to send queued command: {err}"));
+            break;
+        }
+    }
+}
+
+/// Waits for the next attempt of the reconnect policy, reporting it in a
+/// status event. Returns false when there is no policy, the policy gives up
+/// or the client is shut down meanwhile.
+async fn wait_to_reconnect(
+    app_handle: &AppHandle,
+    address: &str,
+    reconnector: Option<&mut Reconnector>,
+    shutdown_rx: &mut watch::Receiver<bool>,
+) -> bool {
+    let Some(reconnector) = reconnector else {
+        return false;
+    };
+    let Some(retry) = reconnector.next_attempt() else {
+        let message = format!(
+            "Gave up after {} reconnect attempts",
+            reconnector.failures()
+        );
+        match reconnector.give_up() {
+            ReconnectGiveUp::Disconnect => warn!("{message}"),
+            ReconnectGiveUp::Error => emit_error(app_handle, &message),
+        }
+        return false;
+    };
+    let _ = app_handle.emit_all(
+        "tlcs://status",
+        TlcsStatusEvent {
+            connected: false,
+            address: address.to_string(),
+            message: Some(retry.describe()),
+            retry: Some(retry),
+        },
+    );
+    tokio::select! {
+        _ = shutdown_rx.changed() => false,
+        _ = sleep(retry.delay()) => true,
+    }
+}
+
+pub(crate) fn emit_error(app_handle: &AppHandle, message: &str) {
+    error!("{}", message);
     let _ = app_handle.emit_all(
         "tlcs://error",
         TlcsErrorEvent {
@@ -388,11 +1600,22 @@ pub async fn connect(
     host: String,
     port: u16,
     reconnect: bool,
+    reconnect_policy: Option<ReconnectPolicy>,
+    proxy_url: Option<String>,
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), Error> {
     let mut manager = state.tlcs_client.write().await;
-    manager.connect(app_handle, host, port, reconnect).await
+    manager
+        .connect(
+            app_handle,
+            host,
+            port,
+            reconnect,
+            reconnect_policy,
+            proxy_url,
+        )
+        .await
 }
 
 #[tauri::command]
@@ -406,6 +1629,25 @@ pub async fn subscribe_game(
     manager.subscribe_game(game_id, app_handle).await
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn unsubscribe_tlcs_game(
+    game_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.unsubscribe_game(game_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_tlcs_subscriptions(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TlcsSubscription>, Error> {
+    let manager = state.tlcs_client.read().await;
+    Ok(manager.list_subscriptions().await)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn send_move(
@@ -418,15 +1660,283 @@ pub async fn send_move(
     manager.send_move(game_id, mv, app_handle).await
 }
 
+/// Sends a move typed by the operator for the subscribed game `game_id`.
+/// Unlike `send_move`, the move is checked against the tracked position
+/// first and converted to `notation` (UCI by default). Returns the move as
+/// sent.
+#[tauri::command]
+#[specta::specta]
+pub async fn play_tlcs_move(
+    game_id: String,
+    san_or_uci: String,
+    notation: Option<TlcsMoveNotation>,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, Error> {
+    let manager = state.tlcs_client.read().await;
+    send_tracked_move(
+        &manager.writer,
+        &manager.games,
+        &game_id,
+        san_or_uci.trim(),
+        notation.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn request_tlcs_game_list(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.request_game_list().await.map_err(|err| {
+        emit_error(&app_handle, &format!("Failed to request game list: {err}"));
+        err
+    })
+}
+
+/// Sets the rules that subscribe to games from the server's game list, or
+/// clears them with `None`.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_auto_subscribe(
+    rules: Option<TlcsAutoSubscribeRules>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let pgn_dir = app_handle.path().resolve("tlcs", BaseDirectory::AppData)?;
+    let mut manager = state.tlcs_client.write().await;
+    manager.set_auto_subscribe(rules, pgn_dir).await
+}
+
+/// Sets the character set the server's lines are decoded with, for relays
+/// that do not send UTF-8.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_input_encoding(
+    encoding: TlcsInputEncoding,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.set_input_encoding(encoding).await;
+    Ok(())
+}
+
+/// Spaces out every frame the client sends, or stops doing so with `None`.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_rate_limit(
+    limit: Option<TlcsRateLimit>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.set_rate_limit(limit).await;
+    Ok(())
+}
+
+/// Holds moves and chat messages sent while disconnected until the
+/// connection is restored, or stops doing so with `None`.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tlcs_outbound_queue(
+    config: Option<TlcsOutboundQueueConfig>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.set_outbound_queue(config).await;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn send_tlcs_chat(
+    channel: String,
+    text: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.send_chat(&channel, &text).await.map_err(|err| {
+        emit_error(&app_handle, &format!("Failed to send chat message: {err}"));
+        err
+    })
+}
+
+/// Offers a game of `time` minutes plus `increment` seconds a move to anyone
+/// on the server.
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_post_seek(
+    time: u32,
+    increment: u32,
+    rated: bool,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager
+        .post_seek(time, increment, rated)
+        .await
+        .map_err(|err| {
+            emit_error(&app_handle, &format!("Failed to post seek: {err}"));
+            err
+        })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn tlcs_accept_challenge(
+    id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.accept_challenge(&id).await.map_err(|err| {
+        emit_error(&app_handle, &format!("Failed to accept challenge: {err}"));
+        err
+    })
+}
+
+/// Queues `san_or_uci` to be played for `color` in `game_id` as soon as the
+/// opponent has moved, replacing any earlier premove. When `color` is already
+/// to move, the move is sent right away and returned.
+#[tauri::command]
+#[specta::specta]
+pub async fn queue_tlcs_premove(
+    game_id: String,
+    san_or_uci: String,
+    color: TlcsSide,
+    notation: Option<TlcsMoveNotation>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<String>, Error> {
+    let manager = state.tlcs_client.read().await;
+    let mut games = manager.games.write().await;
+    let game = games
+        .get_mut(&game_id)
+        .ok_or_else(|| Error::TlcsGameNotSubscribed(game_id.clone()))?;
+    let premove = Premove {
+        mv: san_or_uci.trim().to_string(),
+        color: color.into(),
+        notation: notation.unwrap_or_default(),
+    };
+
+    if game.position.turn() == premove.color {
+        let mv = game.legal_move(&premove.mv)?;
+        let sent = game
+            .send(&manager.writer, &game_id, &mv, premove.notation)
+            .await?;
+        emit_premove(
+            &app_handle,
+            &game_id,
+            sent.clone(),
+            TlcsPremoveState::Fired,
+            None,
+        );
+        return Ok(Some(sent));
+    }
+
+    // Legality can only be checked once the opponent's move is known.
+    if SanPlus::from_ascii(premove.mv.as_bytes()).is_err()
+        && UciMove::from_ascii(premove.mv.as_bytes()).is_err()
+    {
+        return Err(Error::TlcsUnrecognizedMove(premove.mv));
+    }
+    if let Some(previous) = game.premove.take() {
+        emit_premove(
+            &app_handle,
+            &game_id,
+            previous.mv,
+            TlcsPremoveState::Cancelled,
+            Some("Replaced".to_string()),
+        );
+    }
+    emit_premove(
+        &app_handle,
+        &game_id,
+        premove.mv.clone(),
+        TlcsPremoveState::Queued,
+        None,
+    );
+    game.premove = Some(premove);
+    Ok(None)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_tlcs_premove(
+    game_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    let mut games = manager.games.write().await;
+    if let Some(premove) = games.get_mut(&game_id).and_then(|game| game.premove.take()) {
+        emit_premove(
+            &app_handle,
+            &game_id,
+            premove.mv,
+            TlcsPremoveState::Cancelled,
+            None,
+        );
+    }
+    Ok(())
+}
+
+/// Lets a UCI engine play `color` in the subscribed game `game_id`, searching
+/// for `movetime_ms` per move, or to `depth` when no time is given.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_tlcs_engine_seat(
+    game_id: String,
+    engine_path: String,
+    color: TlcsSide,
+    movetime_ms: Option<u32>,
+    depth: Option<u32>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let go = match (movetime_ms, depth) {
+        (Some(movetime), _) => GoMode::Time(movetime),
+        (None, Some(depth)) => GoMode::Depth(depth),
+        (None, None) => GoMode::Time(DEFAULT_SEAT_MOVETIME_MS),
+    };
+    let manager = state.tlcs_client.read().await;
+    manager
+        .start_engine_seat(
+            game_id,
+            PathBuf::from(engine_path),
+            color.into(),
+            go,
+            app_handle,
+        )
+        .await
+}
+
+/// Panic stop: kills the engine seated at `game_id`, or every seated engine
+/// when `None`, without sending the move it was searching.
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_tlcs_engine_seat(
+    game_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), Error> {
+    let manager = state.tlcs_client.read().await;
+    manager.stop_engine_seats(game_id).await;
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn keep_alive(
     interval_secs: Option<u64>,
     payload: Option<String>,
+    max_missed: Option<u32>,
     state: tauri::State<'_, AppState>,
 ) -> Result<(), Error> {
     let mut manager = state.tlcs_client.write().await;
-    manager.keep_alive(interval_secs, payload).await
+    manager.keep_alive(interval_secs, payload, max_missed).await
 }
 
 #[tauri::command]
@@ -435,3 +1945,222 @@ pub async fn disconnect(state: tauri::State<'_, AppState>) -> Result<(), Error>
     let mut manager = state.tlcs_client.write().await;
     manager.disconnect().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_lines_are_parsed() {
+        assert_eq!(
+            parse_chat("CHAT arbiters Smith: Round 3 starts at 15:00"),
+            Some(TlcsChatEvent {
+                sender: "Smith".into(),
+                channel: "arbiters".into(),
+                text: "Round 3 starts at 15:00".into(),
+            })
+        );
+        assert_eq!(parse_chat("CHAT public no sender"), None);
+        assert_eq!(parse_chat("MOVE 12 e4"), None);
+    }
+
+    #[test]
+    fn resume_handshake_lines() {
+        assert_eq!(
+            parse_resume_line("SESSION 9f2c41ab"),
+            Some(ResumeLine::Token("9f2c41ab".into()))
+        );
+        assert_eq!(parse_resume_line("RESUMED"), Some(ResumeLine::Accepted));
+        assert_eq!(
+            parse_resume_line("RESUME DECLINED token expired"),
+            Some(ResumeLine::Declined)
+        );
+        assert_eq!(parse_resume_line("SESSION "), None);
+        assert_eq!(parse_resume_line("SESSION a b"), None);
+        assert_eq!(parse_resume_line("MOVE 4 e2e4"), None);
+    }
+
+    #[test]
+    fn seeks_and_challenges_are_parsed() {
+        assert_eq!(
+            parse_offer("CHALLENGE 381 Hou_Yifan 2630 90+30 rated"),
+            Some(TlcsOfferEvent {
+                kind: TlcsOfferKind::Challenge,
+                id: "381".into(),
+                player: "Hou_Yifan".into(),
+                rating: Some(2630),
+                minutes: 90,
+                increment: 30,
+                rated: true,
+            })
+        );
+        let seek = parse_offer("SEEK 17 guest42 - 3+2 casual").unwrap();
+        assert_eq!(seek.kind, TlcsOfferKind::Seek);
+        assert_eq!((seek.rating, seek.rated), (None, false));
+        assert_eq!(parse_offer("SEEK 17 guest42 - 3 casual"), None);
+        assert_eq!(parse_offer("SEEK 3 2 rated"), None);
+    }
+
+    #[test]
+    fn server_frames_are_typed() {
+        assert_eq!(
+            parse_server_message("CLOCK 4 5395000 5400000"),
+            Some((
+                "4".into(),
+                TlcsServerMessage::Clock {
+                    white_ms: 5_395_000,
+                    black_ms: 5_400_000,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_server_message("NAMES 4 Carlsen, Magnus|Nakamura, Hikaru"),
+            Some((
+                "4".into(),
+                TlcsServerMessage::Names {
+                    white: "Carlsen, Magnus".into(),
+                    black: "Nakamura, Hikaru".into(),
+                }
+            ))
+        );
+        assert_eq!(
+            parse_server_message("RESIGN 4 black"),
+            Some((
+                "4".into(),
+                TlcsServerMessage::Resign {
+                    side: TlcsSide::Black
+                }
+            ))
+        );
+        assert_eq!(parse_server_message("RESULT 4 2-0"), None);
+        assert_eq!(parse_server_message("WELCOME to the server"), None);
+    }
+
+    #[test]
+    fn pongs_answer_the_oldest_ping() {
+        let mut latency = LatencyTracker::default();
+        latency.ping_sent();
+        latency.ping_sent();
+        assert_eq!(latency.unanswered(), 2);
+        assert!(latency.pong_received().is_some());
+        assert_eq!(latency.unanswered(), 1);
+        for _ in 0..MAX_PENDING_PINGS {
+            latency.ping_sent();
+        }
+        assert_eq!(latency.unanswered(), MAX_PENDING_PINGS);
+        latency.reset();
+        assert!(latency.pong_received().is_none());
+    }
+
+    #[tokio::test]
+    async fn missed_pongs_drop_the_link() {
+        assert_eq!(
+            missed_pong_limit(None, true),
+            Some(DEFAULT_MAX_MISSED_PONGS as usize)
+        );
+        assert_eq!(missed_pong_limit(Some(0), true), None);
+        assert_eq!(missed_pong_limit(Some(3), false), None);
+        assert_eq!(missed_pong_limit(Some(1000), true), Some(MAX_PENDING_PINGS));
+
+        let mut latency = LatencyTracker::default();
+        let max_missed = missed_pong_limit(Some(2), true);
+        latency.ping_sent();
+        assert!(!latency.link_lost(max_missed));
+        latency.ping_sent();
+        assert!(latency.link_lost(max_missed));
+        assert!(!latency.link_lost(None));
+        assert!(latency.pong_received().is_some());
+        assert!(!latency.link_lost(max_missed));
+
+        // Reported before the read loop waits for it, the dead link is not
+        // lost.
+        let dead_link = Notify::new();
+        dead_link.notify_one();
+        tokio::time::timeout(Duration::from_secs(1), dead_link.notified())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn outbound_queue_drops_the_oldest_when_full() {
+        let mut queue = OutboundQueue::default();
+        assert!(!queue.push("MOVE 1 e2e4"));
+
+        queue.config = Some(TlcsOutboundQueueConfig {
+            max_age_ms: 60_000,
+            capacity: 2,
+        });
+        for message in ["MOVE 1 e2e4", "CHAT public hi", "MOVE 1 g1f3"] {
+            assert!(queue.push(message));
+        }
+        assert_eq!(
+            queue.drain(),
+            (
+                vec!["CHAT public hi".to_string(), "MOVE 1 g1f3".to_string()],
+                vec!["MOVE 1 e2e4".to_string()],
+            )
+        );
+        assert_eq!(queue.drain(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn game_lists_are_collected() {
+        let mut list = GameList::default();
+        assert!(list.feed("GAME 1|1|Lone|2000|Line|2000").is_none());
+        assert!(list.feed("GAMES 2").is_none());
+        assert!(list
+            .feed("GAME 1|5|Carlsen, Magnus|2830|Nakamura, Hikaru|2780")
+            .is_none());
+        let event = list.feed("GAME 2|-|Anonymous||Guest|-").unwrap();
+        assert_eq!(
+            event.games,
+            [
+                TlcsGameListing {
+                    board: 1,
+                    round: Some("5".into()),
+                    white: "Carlsen, Magnus".into(),
+                    white_rating: Some(2830),
+                    black: "Nakamura, Hikaru".into(),
+                    black_rating: Some(2780),
+                },
+                TlcsGameListing {
+                    board: 2,
+                    round: None,
+                    white: "Anonymous".into(),
+                    white_rating: None,
+                    black: "Guest".into(),
+                    black_rating: None,
+                },
+            ]
+        );
+        assert!(list
+            .feed("GAMES 0")
+            .is_some_and(|event| event.games.is_empty()));
+    }
+
+    #[test]
+    fn observers_are_counted() {
+        let mut presence = Presence::default();
+        let observers = |event: Option<TlcsPresenceEvent>| event.map(|event| event.observers);
+        assert_eq!(
+            observers(presence.feed("OBSERVERS 7 12 alice bob")),
+            Some(12)
+        );
+        assert_eq!(observers(presence.feed("JOIN 7 carol")), Some(13));
+        assert_eq!(observers(presence.feed("JOIN 7 alice")), None);
+        assert_eq!(observers(presence.feed("LEAVE 7 bob")), Some(12));
+        // Someone who was only counted.
+        assert_eq!(observers(presence.feed("LEAVE 7 dave")), Some(11));
+        assert_eq!(observers(presence.feed("OBSERVERS 8 1 erin")), Some(1));
+        assert_eq!(observers(presence.feed("LEAVE 8 frank")), None);
+        assert_eq!(
+            presence.feed("LEAVE 8 erin"),
+            Some(TlcsPresenceEvent {
+                game_id: "8".into(),
+                observers: 0,
+            })
+        );
+        assert_eq!(observers(presence.feed("OBSERVERS 9 many")), None);
+        assert_eq!(observers(presence.feed("MOVE 7 e2e4")), None);
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use log::info;
+use shakmaty::{Color, Position};
+use tauri::AppHandle;
+use tokio::{
+    select,
+    sync::{mpsc, watch},
+    task::JoinHandle,
+};
+use vampirc_uci::{parse_one, UciMessage};
+
+use crate::chess::{EngineOptions, EngineProcess, GoMode};
+use crate::tlcs_client::{
+    emit_error, send_tracked_move, SharedWriter, TlcsMoveNotation, TrackedGames,
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// A UCI engine playing one side of a subscribed game: whenever the tracked
+/// position has that side to move, the engine's best move is sent to the
+/// server.
+pub struct TlcsEngineSeat {
+    wake: mpsc::UnboundedSender<()>,
+    halt: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl TlcsEngineSeat {
+    pub(crate) fn spawn(
+        game_id: String,
+        engine: PathBuf,
+        color: Color,
+        go: GoMode,
+        games: TrackedGames,
+        writer: SharedWriter,
+        app: AppHandle,
+    ) -> Self {
+        let (wake, wake_rx) = mpsc::unbounded_channel();
+        let (halt, halt_rx) = watch::channel(false);
+        let task = tokio::spawn(run_engine_seat(
+            SeatConfig {
+                game_id,
+                engine,
+                color,
+                go,
+            },
+            games,
+            writer,
+            app,
+            wake_rx,
+            halt_rx,
+        ));
+        Self { wake, halt, task }
+    }
+
+    /// Tells the seat that the tracked position changed.
+    pub(crate) fn notify(&self) {
+        let _ = self.wake.send(());
+    }
+
+    /// Kills the engine at once. A move still being searched is not sent.
+    pub(crate) async fn stop(self) {
+        let _ = self.halt.send(true);
+        let _ = self.task.await;
+    }
+}
+
+struct SeatConfig {
+    game_id: String,
+    engine: PathBuf,
+    color: Color,
+    go: GoMode,
+}
+
+async fn run_engine_seat(
+    config: SeatConfig,
+    games: TrackedGames,
+    writer: SharedWriter,
+    app: AppHandle,
+    mut wake: mpsc::UnboundedReceiver<()>,
+    mut halt: watch::Receiver<bool>,
+) {
+    let game_id = &config.game_id;
+    let (mut proc, mut reader) = match EngineProcess::new(config.engine.clone()).await {
+        Ok(engine) => engine,
+        Err(err) => {
+            emit_error(
+                &app,
+                &format!(
+                    "Unable to start engine {} for game {game_id}: {err}",
+                    config.engine.to_string_lossy()
+                ),
+            );
+            return;
+        }
+    };
+    info!(
+        "Engine {} seated as {:?} in game {game_id}",
+        config.engine.to_string_lossy(),
+        config.color
+    );
+
+    'positions: loop {
+        select! {
+            _ = halt.changed() => break,
+            next = wake.recv() => {
+                if next.is_none() {
+                    break;
+                }
+            }
+        }
+        while wake.try_recv().is_ok() {}
+
+        let moves = {
+            let games = games.read().await;
+            let Some(game) = games.get(game_id) else {
+                continue;
+            };
+            if game.position.turn() != config.color || game.position.is_game_over() {
+                continue;
+            }
+            game.moves.clone()
+        };
+
+        let options = EngineOptions {
+            fen: START_FEN.to_string(),
+            moves: moves.clone(),
+            extra_options: Vec::new(),
+        };
+        if let Err(err) = proc.set_options(options).await {
+            emit_error(&app, &format!("Seated engine rejected position: {err}"));
+            continue;
+        }
+        if let Err(err) = proc.go(&config.go).await {
+            emit_error(&app, &format!("Seated engine failed: {err}"));
+            break;
+        }
+
+        let best_move = loop {
+            select! {
+                _ = halt.changed() => break 'positions,
+                line = reader.next_line() => {
+                    let Ok(Some(line)) = line else {
+                        emit_error(&app, &format!("Engine seated in game {game_id} exited"));
+                        return;
+                    };
+                    if let UciMessage::BestMove { best_move, .. } = parse_one(&line) {
+                        break best_move.to_string();
+                    }
+                }
+            }
+        };
+
+        // The game may have moved on while the engine was thinking.
+        if games.read().await.get(game_id).map(|game| &game.moves) != Some(&moves) {
+            continue;
+        }
+        let sent =
+            send_tracked_move(&writer, &games, game_id, &best_move, TlcsMoveNotation::Uci).await;
+        match sent {
+            Ok(uci) => info!("Seated engine played {uci} in game {game_id}"),
+            Err(err) => emit_error(
+                &app,
+                &format!("Failed to send engine move {best_move} in game {game_id}: {err}"),
+            ),
+        }
+    }
+
+    let _ = proc.kill().await;
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use specta::Type;
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+
+use crate::error::Error;
+use crate::tlcs::TlcsConnectArgs;
+
+const PROFILES_FILE: &str = "tlcs/profiles.json";
+pub(crate) const KEYRING_SERVICE: &str = "en-croissant-tlcs";
+
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TlcsProfile {
+    pub name: String,
+    pub args: TlcsConnectArgs,
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, Error> {
+    Ok(app.path().resolve(PROFILES_FILE, BaseDirectory::AppData)?)
+}
+
+/// Reads every saved profile. A missing store is treated as empty.
+fn load_profiles(app: &AppHandle) -> Result<BTreeMap<String, TlcsConnectArgs>, Error> {
+    let path = profiles_path(app)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+fn store_profiles(
+    app: &AppHandle,
+    profiles: &BTreeMap<String, TlcsConnectArgs>,
+) -> Result<(), Error> {
+    let path = profiles_path(app)?;
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(serde_json::to_string_pretty(profiles)?.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// Moves the password of a profile into the OS keyring, so the JSON store
+/// never holds it in plaintext.
+fn store_password(name: &str, args: &mut TlcsConnectArgs) -> Result<(), Error> {
+    let password = std::mem::take(&mut args.password);
+    if !password.is_empty() {
+        keyring::Entry::new(KEYRING_SERVICE, name)?.set_password(&password)?;
+    }
+    Ok(())
+}
+
+fn delete_password(name: &str) -> Result<(), Error> {
+    match keyring::Entry::new(KEYRING_SERVICE, name)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Looks up the connection arguments saved under `name`, with the password
+/// restored from the OS keyring.
+pub fn load_profile(app: &AppHandle, name: &str) -> Result<TlcsConnectArgs, Error> {
+    let mut args = load_profiles(app)?
+        .remove(name)
+        .ok_or_else(|| Error::TlcsProfileNotFound(name.to_string()))?;
+    if args.password.is_empty() {
+        match keyring::Entry::new(KEYRING_SERVICE, name)?.get_password() {
+            Ok(password) => args.password = password,
+            Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(args)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_tlcs_profiles(app: AppHandle) -> Result<Vec<TlcsProfile>, Error> {
+    Ok(load_profiles(&app)?
+        .into_iter()
+        .map(|(name, mut args)| {
+            args.password.clear();
+            TlcsProfile { name, args }
+        })
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn save_tlcs_profile(
+    name: String,
+    mut args: TlcsConnectArgs,
+    app: AppHandle,
+) -> Result<(), Error> {
+    let mut profiles = load_profiles(&app)?;
+    if profiles.contains_key(&name) {
+        return Err(Error::TlcsProfileExists(name));
+    }
+    store_password(&name, &mut args)?;
+    profiles.insert(name, args);
+    store_profiles(&app, &profiles)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_tlcs_profile(
+    name: String,
+    mut args: TlcsConnectArgs,
+    app: AppHandle,
+) -> Result<(), Error> {
+    let mut profiles = load_profiles(&app)?;
+    let Some(profile) = profiles.get_mut(&name) else {
+        return Err(Error::TlcsProfileNotFound(name));
+    };
+    // An empty password keeps the one already stored in the keyring.
+    store_password(&name, &mut args)?;
+    *profile = args;
+    store_profiles(&app, &profiles)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_tlcs_profile(name: String, app: AppHandle) -> Result<(), Error> {
+    let mut profiles = load_profiles(&app)?;
+    if profiles.remove(&name).is_none() {
+        return Err(Error::TlcsProfileNotFound(name));
+    }
+    delete_password(&name)?;
+    store_profiles(&app, &profiles)
+}
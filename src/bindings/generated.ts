@@ -365,6 +365,22 @@ async getPuzzleDbInfo(file: string) : Promise<Result<PuzzleDatabaseInfo, string>
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+async followTlcsInBoard(session: string | null, tabId: string, board: number | null) : Promise<Result<TlcsFollowedGame, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("follow_tlcs_in_board", { session, tabId, board }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unfollowTlcsInBoard(tabId: string) : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unfollow_tlcs_in_board", { tabId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 }
 }
 
@@ -377,14 +393,16 @@ databaseProgress: DatabaseProgress,
 downloadProgress: DownloadProgress,
 reportProgress: ReportProgress,
 tlcsConnection: TlcsConnectionEvent,
-tlcsGame: TlcsGameEvent
+tlcsGame: TlcsGameEvent,
+tlcsTabMove: TlcsTabMoveEvent
 }>({
 bestMovesPayload: "best-moves-payload",
 databaseProgress: "database-progress",
 downloadProgress: "download-progress",
 reportProgress: "report-progress",
 tlcsConnection: "tlcs-connection",
-tlcsGame: "tlcs-game"
+tlcsGame: "tlcs-game",
+tlcsTabMove: "tlcs-tab-move"
 })
 
 /** user-defined constants **/
@@ -403,6 +421,35 @@ export type TlcsConnectionEvent = { status: TlcsConnectionStatus; message: strin
 export type TlcsConnectionStatus = "Disconnected" | "Connecting" | "Connected" | "Error"
 export type TlcsGameEvent = { state: TlcsGameState; raw: string | null }
 export type TlcsGameState = { fen: string | null; whiteClockMs: bigint | null; blackClockMs: bigint | null; status: string | null; lastMove: string | null; canOfferDraw: boolean; canAcceptDraw: boolean; canResign: boolean }
+/**
+ * The game a tab starts following, to set its mainline to before the moves
+ * of `TlcsTabMoveEvent` arrive.
+ */
+export type TlcsFollowedGame = { startFen: string; 
+/**
+ * SAN of the plies recorded so far.
+ */
+moves: string[]; result: string | null }
+/**
+ * Emitted for every move appended to a recorded game.
+ */
+export type TlcsMoveRecordedEvent = { board: number | null; san: string; uci: string; 
+/**
+ * Ply of the move, counting from 1.
+ */
+ply: bigint; fenAfter: string; 
+/**
+ * Last clock reported for the side that moved, in milliseconds.
+ */
+clock: bigint | null }
+/**
+ * Emitted for every move recorded on a board an analysis tab follows, for
+ * that tab to put at `ply` of its mainline. Nothing the user analyzed there
+ * is overwritten: a move differing from the tab's mainline, after a takeback
+ * or the user playing ahead, becomes the mainline and the moves it displaces
+ * stay as variations.
+ */
+export type TlcsTabMoveEvent = { tabId: string; recorded: TlcsMoveRecordedEvent }
 export type TlcsConnectArgs = { host: string; port: number; username: string; password: string; autoReconnect: boolean; reconnectIntervalMs: bigint }
 export type TlcsUserAction = "AcceptOffer" | "OfferDraw" | "Resign" | "DeclineDraw" | "RequestReconnect"
 export type EngineConfig = { name: string; options: UciOptionConfig[] }
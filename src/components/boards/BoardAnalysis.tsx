@@ -36,6 +36,7 @@ import PracticePanel from "../panels/practice/PracticePanel";
 import Board from "./Board";
 import EditingCard from "./EditingCard";
 import EvalListener from "./EvalListener";
+import TlcsFollowListener from "./TlcsFollowListener";
 
 function BoardAnalysis() {
   const { t } = useTranslation();
@@ -128,6 +129,12 @@ function BoardAnalysis() {
   return (
     <>
       <EvalListener />
+      {currentTab?.followedBoard && (
+        <TlcsFollowListener
+          tabId={currentTab.value}
+          board={currentTab.followedBoard.board}
+        />
+      )}
       <Portal target="#left" style={{ height: "100%" }}>
         <Board
           practicing={practicing}
@@ -0,0 +1,57 @@
+import { commands, events } from "@/bindings";
+import { positionFromFen } from "@/utils/chessops";
+import { parseUci } from "chessops";
+import { parseSan } from "chessops/san";
+import { useContext, useEffect } from "react";
+import { useStore } from "zustand";
+import { TreeStateContext } from "../common/TreeStateContext";
+
+// Puts the moves recorded on the TLCS board the tab follows on its
+// mainline, keeping what the user explored there as variations.
+function TlcsFollowListener({
+  tabId,
+  board,
+}: {
+  tabId: string;
+  board: number | null;
+}) {
+  const store = useContext(TreeStateContext)!;
+  const appendLiveMove = useStore(store, (s) => s.appendLiveMove);
+
+  useEffect(() => {
+    const unlisten = events.tlcsTabMove.listen(({ payload }) => {
+      if (payload.tabId !== tabId) return;
+      const { ply, uci, clock } = payload.recorded;
+      const move = parseUci(uci);
+      if (!move) return;
+      appendLiveMove({
+        ply: Number(ply),
+        payload: move,
+        clock: clock == null ? undefined : Number(clock),
+      });
+    });
+
+    // Closed tabs are unmounted, so the moves recorded meanwhile are caught
+    // up with here. Following again also rebinds the tab after the session
+    // was restarted.
+    commands.followTlcsInBoard(null, tabId, board).then((res) => {
+      if (res.status === "error") return;
+      const [pos] = positionFromFen(res.data.startFen);
+      if (!pos) return;
+      for (const [i, san] of res.data.moves.entries()) {
+        const move = parseSan(pos, san);
+        if (!move) break;
+        appendLiveMove({ ply: i + 1, payload: move });
+        pos.play(move);
+      }
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, [tabId, board, appendLiveMove]);
+
+  return null;
+}
+
+export default TlcsFollowListener;
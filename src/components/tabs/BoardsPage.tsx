@@ -65,6 +65,9 @@ export default function BoardsPage() {
           }
         }
         setTabs((prev) => prev.filter((tab) => tab.value !== value));
+        if (closedTab?.followedBoard) {
+          await commands.unfollowTlcsInBoard(value);
+        }
         unwrap(await commands.killEngines(value));
       }
     },
@@ -5,6 +5,8 @@ import {
   commands,
 } from "@/bindings";
 import { Chessground } from "@/chessground/Chessground";
+import { activeTabAtom, tabsAtom } from "@/state/atoms";
+import { createTab, genID } from "@/utils/tabs";
 import {
   Badge,
   Button,
@@ -22,11 +24,15 @@ import { useDisclosure } from "@mantine/hooks";
 import { notifications } from "@mantine/notifications";
 import {
   IconArrowBackUp,
+  IconEye,
   IconPlayerPlay,
   IconPlugConnected,
   IconRefresh,
   IconShieldX,
 } from "@tabler/icons-react";
+import { useNavigate } from "@tanstack/react-router";
+import { INITIAL_FEN } from "chessops/fen";
+import { useSetAtom } from "jotai";
 import { useEffect, useMemo, useState } from "react";
 
 type FormState = {
@@ -82,6 +88,10 @@ export function TlcsLivePage() {
   const [boardFen, setBoardFen] = useState<string>("start");
   const [connecting, { open: startConnecting, close: stopConnecting }] =
     useDisclosure(false);
+  const [followedBoard, setFollowedBoard] = useState<number | null>(null);
+  const setTabs = useSetAtom(tabsAtom);
+  const setActiveTab = useSetAtom(activeTabAtom);
+  const navigate = useNavigate();
 
   useEffect(() => {
     const unlistenConnection = events.tlcsConnection.listen((event) => {
@@ -155,6 +165,37 @@ export function TlcsLivePage() {
     }
   };
 
+  // Opens an analysis tab that keeps receiving the moves recorded on the
+  // board, while the user analyzes ahead in side lines.
+  const onFollowInBoard = async () => {
+    const tabId = genID();
+    const res = await commands.followTlcsInBoard(null, tabId, followedBoard);
+    if (res.status === "error") {
+      notifications.show({
+        color: "red",
+        title: "Follow failed",
+        message: res.error,
+      });
+      return;
+    }
+    const { startFen, moves, result } = res.data;
+    const setup =
+      startFen === INITIAL_FEN ? "" : `[SetUp "1"]\n[FEN "${startFen}"]\n\n`;
+    await createTab({
+      id: tabId,
+      tab: {
+        name:
+          followedBoard === null ? "Live board" : `Live board ${followedBoard}`,
+        type: "analysis",
+        followedBoard: { board: followedBoard },
+      },
+      setTabs,
+      setActiveTab,
+      pgn: `${setup}${moves.join(" ")} ${result ?? "*"}`,
+    });
+    navigate({ to: "/" });
+  };
+
   const connectionBadge = useMemo(
     () => (
       <Badge color={statusColor(connectionStatus)} variant="filled">
@@ -342,6 +383,33 @@ export function TlcsLivePage() {
                     <Text lineClamp={2}>{lastRaw ?? "No events yet"}</Text>
                   </Stack>
                 </Card>
+                <Card withBorder padding="sm" radius="md">
+                  <Stack gap="xs">
+                    <Text size="sm" c="dimmed">
+                      Recording
+                    </Text>
+                    <NumberInput
+                      size="xs"
+                      label="Board"
+                      placeholder="Default board"
+                      value={followedBoard ?? ""}
+                      onChange={(value) =>
+                        setFollowedBoard(
+                          typeof value === "number" ? value : null,
+                        )
+                      }
+                      min={1}
+                    />
+                    <Button
+                      size="xs"
+                      variant="light"
+                      leftSection={<IconEye size={14} />}
+                      onClick={onFollowInBoard}
+                    >
+                      Follow in board
+                    </Button>
+                  </Stack>
+                </Card>
               </Stack>
             </Grid.Col>
           </Grid>
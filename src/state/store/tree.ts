@@ -48,6 +48,7 @@ export interface TreeStoreState extends TreeState {
   }) => void;
 
   appendMove: (args: { payload: Move; clock?: number }) => void;
+  appendLiveMove: (args: { ply: number; payload: Move; clock?: number }) => void;
 
   makeMoves: (args: {
     payload: string[];
@@ -207,6 +208,13 @@ export const createTreeStore = (id?: string, initialTree?: TreeState) => {
         }),
       ),
 
+    appendLiveMove: ({ ply, payload, clock }) =>
+      set(
+        produce((state) => {
+          appendLiveMove(state, ply, payload, clock);
+        }),
+      ),
+
     makeMoves: ({ payload, mainline, changeHeaders = true }) =>
       set(
         produce((state) => {
@@ -594,6 +602,51 @@ function makeMove({
   }
 }
 
+// Puts a move of a followed live game at `ply` of the mainline. The lines
+// the user explored there are kept as variations, with their comments and
+// annotations, and the board stays on the node the user was looking at.
+function appendLiveMove(
+  state: TreeState,
+  ply: number,
+  move: Move,
+  clock?: number,
+) {
+  const mainLine = Array.from(treeIteratorMainLine(state.root));
+  if (ply < 1 || ply > mainLine.length) return;
+  const { position: parentPath, node: parent } = mainLine[ply - 1];
+  const [pos] = positionFromFen(parent.fen);
+  if (!pos) return;
+  const san = makeSan(pos, move);
+  if (san === "--") return;
+
+  let index = parent.children.findIndex((n) => n.san === san);
+  if (index === 0) return;
+  if (index === -1) {
+    pos.play(move);
+    parent.children.push(
+      createNode({
+        fen: makeFen(pos.toSetup()),
+        move,
+        san,
+        halfMoves: parent.halfMoves + 1,
+        clock,
+      }),
+    );
+    index = parent.children.length - 1;
+  }
+  state.dirty = true;
+  parent.children.unshift(parent.children.splice(index, 1)[0]);
+
+  if (
+    isPrefix(parentPath, state.position) &&
+    state.position.length > parentPath.length
+  ) {
+    const child = state.position[parentPath.length];
+    state.position[parentPath.length] =
+      child === index ? 0 : child < index ? child + 1 : child;
+  }
+}
+
 function isThreeFoldRepetition(state: TreeState, fen: string) {
   let node = state.root;
   const fens = [INITIAL_FEN.split(" - ")[0]];
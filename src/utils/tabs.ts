@@ -13,6 +13,9 @@ export const tabSchema = z.object({
   type: z.enum(["new", "play", "analysis", "puzzles"]),
   gameNumber: z.number().nullish(),
   file: fileMetadataSchema.nullish(),
+  // The board of the running TLCS session whose moves the tab receives,
+  // null for the default board.
+  followedBoard: z.object({ board: z.number().nullable() }).nullish(),
 });
 
 export type Tab = z.infer<typeof tabSchema>;
@@ -25,6 +28,7 @@ export function genID() {
 }
 
 export async function createTab({
+  id = genID(),
   tab,
   setTabs,
   setActiveTab,
@@ -34,6 +38,7 @@ export async function createTab({
   gameNumber,
   position,
 }: {
+  id?: string;
   tab: Omit<Tab, "value">;
   setTabs: React.Dispatch<React.SetStateAction<Tab[]>>;
   setActiveTab: React.Dispatch<React.SetStateAction<string | null>>;
@@ -43,8 +48,6 @@ export async function createTab({
   gameNumber?: number;
   position?: number[];
 }) {
-  const id = genID();
-
   if (pgn !== undefined) {
     const tree = await parsePGN(pgn, headers?.fen);
     if (headers) {
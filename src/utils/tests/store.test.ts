@@ -180,6 +180,24 @@ test("should handle setStart", () => {
   });
 });
 
+test("should keep explored lines when appending a live move", () => {
+  store.setState({ ...treeE4D5(), position: [0, 0] });
+  store.getState().appendLiveMove({ ply: 2, payload: e5 });
+
+  const root = store.getState().root;
+  expect(root.children[0].children.map((n) => n.san)).toStrictEqual([
+    "e5",
+    "d5",
+  ]);
+  expect(store.getState().position).toStrictEqual([0, 1]);
+
+  store.getState().appendLiveMove({ ply: 2, payload: d5 });
+  expect(
+    store.getState().root.children[0].children.map((n) => n.san),
+  ).toStrictEqual(["d5", "e5"]);
+  expect(store.getState().position).toStrictEqual([0, 0]);
+});
+
 test("should handle makeMove", () => {
   store.getState().makeMove({ payload: e4 });
 